@@ -20,6 +20,7 @@ use crate::{
 
 // TODO(cleanup): This could probably share some macro code with the other
 // binop_impls
+#[allow(clippy::empty_line_after_doc_comments)]
 macro_rules! float_binop_impl {
     (
         $name:ident =>
@@ -41,11 +42,11 @@ macro_rules! float_binop_impl {
         }
 
         impl FloatBinop for $name {
-            fn apply_ints(lhs: i64, rhs: i64) -> Number {
+            fn apply_ints(lhs: i64, rhs: i64) -> Result<Number, OpError> {
                 let $lhs_int = lhs;
                 let $rhs_int = rhs;
 
-                $when_ints
+                Ok($when_ints)
             }
 
             fn apply_floats(lhs: f64, rhs: f64) -> Number {
@@ -63,7 +64,7 @@ macro_rules! float_binop_impl {
                 RHS: NumLike,
             {
                 if let (Some(lhs), Some(rhs)) = (lhs.as_int(), rhs.as_int()) {
-                    Ok(Self::apply_ints(lhs, rhs))
+                    Self::apply_ints(lhs, rhs)
                 } else {
                     Ok(Self::apply_floats(
                         lhs.as_float()
@@ -115,15 +116,55 @@ float_binop!(Divide => {
 });
 
 float_binop!(IDiv => {
-    (lhs: int, rhs: int) =>  Number::Integer(lhs / rhs),
+    (lhs: int, rhs: int) =>  Number::Integer(floor_div(lhs, rhs)?),
     (lhs: float, rhs: float) => Number::Float((lhs / rhs).floor()),
 });
 
 float_binop!(Modulo => {
-    (lhs: int, rhs: int) =>  Number::Integer(lhs % rhs),
-    (lhs: float, rhs: float) => Number::Float(lhs % rhs),
+    (lhs: int, rhs: int) =>  Number::Integer(floor_mod(lhs, rhs)?),
+    (lhs: float, rhs: float) => Number::Float(floor_mod_float(lhs, rhs)),
 });
 
+/// Integer division rounding towards negative infinity, rather than towards
+/// zero like Rust's `/`.
+fn floor_div(lhs: i64, rhs: i64) -> Result<i64, OpError> {
+    if rhs == 0 {
+        return Err(OpError::DivideByZero { op: "//" });
+    }
+
+    let quotient = lhs.wrapping_div(rhs);
+    if lhs.wrapping_rem(rhs) != 0 && (lhs < 0) != (rhs < 0) {
+        Ok(quotient - 1)
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// The remainder of [`floor_div`], which has the sign of `rhs` rather than of
+/// `lhs` like Rust's `%`.
+fn floor_mod(lhs: i64, rhs: i64) -> Result<i64, OpError> {
+    if rhs == 0 {
+        // This matches the message raised by LUA itself.
+        return Err(OpError::DivideByZero { op: "%%" });
+    }
+
+    let rem = lhs.wrapping_rem(rhs);
+    if rem != 0 && (rem < 0) != (rhs < 0) {
+        Ok(rem + rhs)
+    } else {
+        Ok(rem)
+    }
+}
+
+fn floor_mod_float(lhs: f64, rhs: f64) -> f64 {
+    let rem = lhs % rhs;
+    if rem != 0.0 && (rem < 0.0) != (rhs < 0.0) {
+        rem + rhs
+    } else {
+        rem
+    }
+}
+
 float_binop!(Exponetiation => {
     (lhs: int, rhs: int) =>  Number::Float((lhs as f64).powf(rhs as f64)),
     (lhs: float, rhs: float) => Number::Float(lhs.powf(rhs)),
@@ -18,18 +18,31 @@ use crate::{
 /// Converts an `f64` to an `i64` if it falls within the range of `i64` and has
 /// no fractional component.
 pub fn f64inbounds(f: f64) -> Result<i64, OpError> {
-    if f > i64::MIN as f64 && f < i64::MAX as f64 && f.fract() == 0.0 {
+    // i64::MIN is a power of two, so it converts to a float exactly, but i64::MAX
+    // rounds up to 2^63 which is out of range.
+    if f >= i64::MIN as f64 && f < i64::MAX as f64 && f.fract() == 0.0 {
         Ok(f as i64)
     } else {
         Err(OpError::FloatToIntConversionFailed { f })
     }
 }
 
+/// Converts an operand of a bitwise operator to an integer. Floats are only
+/// accepted if they have an exact integer representation.
+fn int_operand(operand: impl NumLike, op: &'static str) -> Result<i64, OpError> {
+    match (operand.as_int(), operand.as_float()) {
+        (Some(i), _) => Ok(i),
+        (None, Some(f)) => f64inbounds(f).map_err(|_| OpError::NoIntegerRepresentation { f }),
+        (None, None) => Err(OpError::InvalidType { op }),
+    }
+}
+
 /// Generic operation for anything that looks like a number, usable during
 /// compilation
 
+#[allow(clippy::empty_line_after_doc_comments)]
 fn shift_left(lhs: i64, rhs: i64) -> i64 {
-    if rhs < -64 || rhs > 64 {
+    if !(-64..=64).contains(&rhs) {
         0
     } else if rhs == 0 {
         lhs
@@ -41,7 +54,7 @@ fn shift_left(lhs: i64, rhs: i64) -> i64 {
 }
 
 fn shift_right(lhs: i64, rhs: i64) -> i64 {
-    if rhs < -64 || rhs > 64 {
+    if !(-64..=64).contains(&rhs) {
         0
     } else if rhs == 0 {
         lhs
@@ -87,23 +100,8 @@ macro_rules! int_binop_impl {
                 LHS: NumLike,
                 RHS: NumLike,
             {
-                let lhs = if let Some(lhs) = lhs.as_int() {
-                    lhs
-                } else {
-                    f64inbounds(
-                        lhs.as_float()
-                            .ok_or(OpError::InvalidType { op: Self::NAME })?,
-                    )?
-                };
-
-                let rhs = if let Some(rhs) = rhs.as_int() {
-                    rhs
-                } else {
-                    f64inbounds(
-                        rhs.as_float()
-                            .ok_or(OpError::InvalidType { op: Self::NAME })?,
-                    )?
-                };
+                let lhs = int_operand(lhs, Self::NAME)?;
+                let rhs = int_operand(rhs, Self::NAME)?;
 
                 Ok(Self::apply_ints(lhs, rhs))
             }
@@ -26,7 +26,7 @@ pub trait BooleanOpEval {
 }
 
 pub trait FloatBinop {
-    fn apply_ints(lhs: i64, rhs: i64) -> Number;
+    fn apply_ints(lhs: i64, rhs: i64) -> Result<Number, OpError>;
     fn apply_floats(lhs: f64, rhs: f64) -> Number;
 }
 
@@ -38,9 +38,17 @@ pub enum ByteCodeError {
     InvalidTypeMetadata,
     #[error("Invalid type id")]
     InvalidTypeId,
+    #[error("Jump target is outside of the function")]
+    InvalidJumpTarget,
+    #[error("Register is outside of the declared registers")]
+    RegisterOutOfBounds,
+    #[error("Reference to a function which isn't in the chunk")]
+    InvalidFunctionId,
+    #[error("Reference to a string which isn't in the chunk")]
+    InvalidStringId,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Error)]
+#[derive(Debug, Clone, PartialEq, Error)]
 pub enum OpError {
     #[error("Invalid types for operator {op:?}")]
     InvalidType { op: &'static str },
@@ -50,8 +58,12 @@ pub enum OpError {
     InvalidForCond,
     #[error("Invalid 'for' step - expected number")]
     InvalidForStep,
+    #[error("'for' step is zero")]
+    ZeroForStep,
     #[error("Attempted to index a {ty} value")]
     NotATable { ty: &'static str },
+    #[error("undefined global variable '{name}'")]
+    UndefinedGlobal { name: Box<str> },
     #[error("Attempted to compare {lhs} with {rhs}")]
     CmpErr {
         lhs: &'static str,
@@ -59,16 +71,42 @@ pub enum OpError {
     },
     #[error("Attempted to compare two {type_name} values")]
     DuoCmpErr { type_name: &'static str },
+    #[error("attempt to perform 'n{op}0'")]
+    DivideByZero { op: &'static str },
     #[error("Float {f:?} cannot be converted to int")]
     FloatToIntConversionFailed { f: f64 },
+    #[error("number has no integer representation")]
+    NoIntegerRepresentation { f: f64 },
+    #[error("attempt to perform bitwise operation on a {ty} value")]
+    BitwiseOnNonNumber { ty: &'static str },
     #[error("Table index is NaN")]
     TableIndexNaN,
+    #[error("Table index is nil")]
+    TableIndexNil,
     #[error("Table index out of bounds")]
     TableIndexOutOfBounds,
     #[error("String length out of bounds")]
     StringLengthOutOfBounds,
+    #[error("object length is not an integer")]
+    LengthNotInteger,
+    #[error("invalid UTF-8 code")]
+    InvalidUtf8Code,
+    #[error("failed to write output: {kind}")]
+    WriteFailed { kind: std::io::ErrorKind },
     #[error("Meta method {name} not found")]
     NoSuchMetaMethod { name: &'static str },
+    #[error("'{name}' chain too long; possible loop")]
+    MetaChainTooLong { name: &'static str },
+    #[error("invalid key to 'next'")]
+    InvalidNextKey,
+    #[error("bad argument #{arg} to '{func}' ({msg})")]
+    BadArgument {
+        arg: usize,
+        func: &'static str,
+        msg: &'static str,
+    },
+    #[error("Cannot change a protected metatable")]
+    ProtectedMetatable,
     #[error("Missing label")]
     MissingLabel,
     #[error("Break outside loop")]
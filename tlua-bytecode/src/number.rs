@@ -4,12 +4,18 @@ use std::hash::{
 };
 
 use tlua_parser::expressions;
+use tlua_strings::LuaString;
 
 use crate::{
     binop::f64inbounds,
     NumLike,
 };
 
+/// A lua number, which keeps track of whether it is an integer or a float.
+///
+/// Arithmetic on two integers produces an integer, except for `/` and `^`,
+/// which always produce floats. Any operation with a float operand produces a
+/// float.
 #[derive(Debug, Clone, Copy)]
 pub enum Number {
     Float(f64),
@@ -17,6 +23,30 @@ pub enum Number {
 }
 
 impl Number {
+    /// Converts a string holding a numeral to a number, following the grammar
+    /// used by `tonumber` and for the operands of arithmetic. Returns `None` if
+    /// the string isn't a numeral.
+    ///
+    /// Surrounding whitespace and a leading sign are allowed. Integer
+    /// numerals produce integers, and those with a fraction or exponent
+    /// produce floats.
+    pub fn parse_lua(src: &[u8]) -> Option<Self> {
+        expressions::number::Number::parse(src).map(Self::from)
+    }
+
+    /// Converts the number to a string the same way Lua does, e.g. for
+    /// `tostring` and when concatenating numbers.
+    ///
+    /// Integers are rendered without a decimal point. Floats are rendered with
+    /// `%.14g`, plus a trailing `.0` if they would otherwise look like an
+    /// integer, so `1.0` becomes `"1.0"` and `1e20` becomes `"1e+20"`.
+    pub fn to_lua_string(&self) -> LuaString {
+        match *self {
+            Number::Float(f) => f.into(),
+            Number::Integer(i) => i.into(),
+        }
+    }
+
     /// Hashes the number.
     ///
     /// # Warning
@@ -36,6 +66,10 @@ impl Number {
 
     /// Hashes the number using the provided hasher.
     ///
+    /// Numbers which compare equal hash the same, so floats with an integral
+    /// value, including `-0.0`, hash as the equivalent integer. Every NaN
+    /// hashes the same, but NaN is never equal to anything, itself included.
+    ///
     /// # Warning
     /// You may not rely on equal hash values implying equal values. i.e. the
     /// following may panic:
@@ -89,10 +123,14 @@ impl From<f64> for Number {
 impl PartialEq for Number {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            // NaN != NaN and 0.0 == -0.0, as in LUA.
             (Number::Float(l0), Number::Float(r0)) => l0 == r0,
             (Number::Integer(l0), Number::Integer(r0)) => l0 == r0,
-            (Number::Float(l0), Number::Integer(r0)) => *l0 == *r0 as f64,
-            (Number::Integer(l0), Number::Float(r0)) => *l0 as f64 == *r0,
+            // Compared exactly, as large integers may round to a different value when
+            // converted to a float.
+            (Number::Float(f), Number::Integer(i)) | (Number::Integer(i), Number::Float(f)) => {
+                f64inbounds(*f).is_ok_and(|f| f == *i)
+            }
         }
     }
 }
@@ -88,7 +88,7 @@ pub type Instruction = Op<Register>;
 
 /// The full list of opcodes supported in tlua's bytecode format. This is
 /// generic over the register type to allow intermediate forms of bytecode.
-#[derive(Clone, Copy, PartialEq, From)]
+#[derive(Clone, PartialEq, From)]
 pub enum Op<RegisterTy> {
     Nop,
     /// `[dest] += [src]`
@@ -139,6 +139,8 @@ pub enum Op<RegisterTy> {
     Or(Or),
     /// `[dest] = [dest].to_string() + [src].to_string()`.
     Concat(Concat),
+    /// `[dest] = [start].to_string() + ... + [start + count - 1].to_string()`.
+    ConcatN(ConcatN),
     /// `[dest] = [dest].len()`.
     Length(Length),
     /// Immediately return from the current function with a specific error.
@@ -151,6 +153,9 @@ pub enum Op<RegisterTy> {
     /// Jump to a specific instruction if the value in the register evaluates to
     /// false.
     JumpNot(JumpNot),
+    /// Jump to a specific instruction if the value in the register evaluates to
+    /// true.
+    JumpIf(JumpIf),
     /// Jump to a specific instruction if the value in the register is exactly
     /// Nil
     JumpNil(JumpNil),
@@ -197,6 +202,10 @@ pub enum Op<RegisterTy> {
     /// Copy all return values from a function into this function's output list
     /// and then return from the function.
     CopyRetFromRetAndRet,
+    /// Marks the immediately preceding call as being in tail position. The
+    /// callee replaces the current function's frame and its results become
+    /// this function's results.
+    TailCall,
     /// Copy the next available return value into the target register.
     ConsumeRetRange(ConsumeRetRange),
     /// Copy all the available return values into a table.
@@ -251,6 +260,25 @@ impl std::fmt::Debug for Length {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, From)]
+pub struct ConcatN {
+    pub dst: ImmediateRegister,
+    pub start: usize,
+    pub count: usize,
+}
+
+impl std::fmt::Debug for ConcatN {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} = concat({:?}..{:?})",
+            self.dst,
+            ImmediateRegister::from(self.start),
+            ImmediateRegister::from(self.start + self.count)
+        )
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, From)]
 pub struct Jump {
     pub target: usize,
@@ -274,6 +302,18 @@ impl std::fmt::Debug for JumpNot {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, From)]
+pub struct JumpIf {
+    pub cond: ImmediateRegister,
+    pub target: usize,
+}
+
+impl std::fmt::Debug for JumpIf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ? jmp {}", self.cond, self.target)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, From)]
 pub struct JumpNil {
     pub cond: ImmediateRegister,
@@ -354,7 +394,7 @@ impl std::fmt::Debug for SetRet {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, From)]
+#[derive(Clone, PartialEq, From)]
 pub struct Raise {
     pub err: OpError,
 }
@@ -365,7 +405,7 @@ impl std::fmt::Debug for Raise {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, From)]
+#[derive(Clone, PartialEq, From)]
 pub struct RaiseIfNot {
     pub src: ImmediateRegister,
     pub err: OpError,
@@ -589,11 +629,13 @@ where
             Op::And(op) => op.fmt(f),
             Op::Or(op) => op.fmt(f),
             Op::Concat(op) => op.fmt(f),
+            Op::ConcatN(op) => op.fmt(f),
             Op::Length(op) => op.fmt(f),
             Op::Raise(op) => op.fmt(f),
             Op::RaiseIfNot(op) => op.fmt(f),
             Op::Jump(op) => op.fmt(f),
             Op::JumpNot(op) => op.fmt(f),
+            Op::JumpIf(op) => op.fmt(f),
             Op::JumpNil(op) => op.fmt(f),
             Op::Lookup(op) => op.fmt(f),
             Op::SetProperty(op) => op.fmt(f),
@@ -616,6 +658,7 @@ where
             Op::CallCopyRet(op) => op.fmt(f),
             Op::CallCopyVa(op) => op.fmt(f),
             Op::CopyRetFromRetAndRet => write!(f, "ret out += results..."),
+            Op::TailCall => write!(f, "ret tailcall"),
             Op::ConsumeRetRange(op) => op.fmt(f),
             Op::SetAllPropertiesFromRet(op) => op.fmt(f),
         }
@@ -19,9 +19,6 @@ impl StringLike for &LuaString {
 
 impl From<&Number> for LuaString {
     fn from(num: &Number) -> Self {
-        match *num {
-            Number::Float(f) => f.into(),
-            Number::Integer(i) => i.into(),
-        }
+        num.to_lua_string()
     }
 }
@@ -8,14 +8,17 @@ use tlua_parser::{
         retstat::RetStatement,
         Block,
     },
+    expressions::Expression,
     list::List,
     statement::Statement,
 };
 
 use crate::{
+    compiler::LabelId,
     CompileError,
     CompileExpression,
     CompileStatement,
+    DiagnosticKind,
     NodeOutput,
     Scope,
 };
@@ -32,12 +35,23 @@ impl CompileStatement for List<'_, Statement<'_>> {
 
 impl CompileStatement for RetStatement<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<Option<OpError>, CompileError> {
-        let mut outputs = self.expressions.iter();
+        let mut outputs = self.expressions.iter().peekable();
         if outputs.len() == 0 {
             scope.emit(opcodes::Op::Ret);
             return Ok(None);
         }
 
+        if let (1, Some(Expression::FunctionCall(call))) = (outputs.len(), outputs.peek()) {
+            // A lone call in a return statement is in tail position, so the callee can take
+            // over this function's frame rather than nesting inside of it.
+            if let NodeOutput::Err(err) = CompileExpression::compile(*call, scope)? {
+                return Ok(Some(err));
+            }
+
+            scope.emit(opcodes::Op::TailCall);
+            return Ok(None);
+        }
+
         let normal_retc = outputs.len() - 1;
 
         for _ in 0..normal_retc {
@@ -87,12 +101,87 @@ pub(crate) fn emit_block(
     scope: &mut Scope,
     block: &Block,
 ) -> Result<Option<OpError>, CompileError> {
-    for stat in block.statements.iter() {
-        stat.compile(scope)?;
+    let mut spans = block.spans.iter();
+
+    // Labels which are only followed by other labels or empty statements are at the
+    // end of the block, where none of its locals are visible any longer.
+    let block_end = if block.ret.is_some() {
+        block.statements.len()
+    } else {
+        block
+            .statements
+            .iter()
+            .enumerate()
+            .filter(|(_, stat)| !matches!(stat, Statement::Label(_) | Statement::Empty(_)))
+            .last()
+            .map_or(0, |(idx, _)| idx + 1)
+    };
+
+    // Set once control can't reach the next statement, and cleared by labels since
+    // they may be the target of a goto.
+    let mut unreachable = false;
+
+    for (idx, stat) in block.statements.iter().enumerate() {
+        let span = spans.next().copied();
+        if let Some(span) = span {
+            scope.mark_location(span);
+        }
+
+        match stat {
+            Statement::Label(_) => unreachable = false,
+            Statement::Empty(_) => (),
+            _ => {
+                if let (true, Some(span)) = (unreachable, span) {
+                    scope.report(span, DiagnosticKind::UnreachableCode);
+                    // Only the first unreachable statement is reported.
+                    unreachable = false;
+                } else {
+                    unreachable = always_exits(stat);
+                }
+            }
+        }
+
+        match stat {
+            Statement::Label(label) if idx >= block_end => {
+                scope
+                    .label_block_end(LabelId::Named(label.0))
+                    .map_err(|err| err.or_span(span))?;
+            }
+            stat => {
+                stat.compile(scope).map_err(|err| err.or_span(span))?;
+            }
+        }
     }
 
     match block.ret.as_ref() {
-        Some(ret) => ret.compile(scope),
+        Some(ret) => {
+            let span = spans.next().copied();
+            if let Some(span) = span {
+                scope.mark_location(span);
+                if unreachable {
+                    scope.report(span, DiagnosticKind::UnreachableCode);
+                }
+            }
+            ret.compile(scope).map_err(|err| err.or_span(span))
+        }
         None => Ok(None),
     }
 }
+
+/// Whether control can never continue past `stat` to the statement following
+/// it.
+fn always_exits(stat: &Statement) -> bool {
+    match stat {
+        Statement::Break(_) | Statement::Goto(_) => true,
+        Statement::Do(block) => {
+            block.ret.is_some()
+                || block
+                    .statements
+                    .iter()
+                    .filter(|stat| !matches!(stat, Statement::Empty(_)))
+                    .last()
+                    .is_some_and(always_exits)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,222 @@
+use tlua_bytecode::{
+    opcodes::*,
+    ByteCodeError,
+    Constant,
+    ImmediateRegister,
+};
+
+use crate::{
+    verify::immediate_ranges,
+    Function,
+};
+
+/// A position in a [`FunctionBuilder`] which may be jumped to before it is
+/// known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Label(usize);
+
+/// Builds the instructions of a [`Function`] by hand, for generating code
+/// without going through LUA source.
+///
+/// Jumps refer to [`Label`]s, which are resolved to instruction offsets by
+/// [`FunctionBuilder::finish`]. The function's immediates are sized to fit
+/// every register used by its instructions.
+///
+/// The built function runs with the scope depth of a chunk's top-level code,
+/// so it can be used as [`Chunk::main`](crate::Chunk::main).
+///
+/// Binary operators such as [`FunctionBuilder::add`] panic if their `dst` is
+/// the same register as their `rhs` but not their `lhs`.
+#[derive(Debug, Default)]
+pub struct FunctionBuilder {
+    instructions: Vec<Instruction>,
+    /// The offset each label is bound to, if it has been bound.
+    labels: Vec<Option<usize>>,
+    /// The offset of each jump instruction along with the label it targets.
+    jumps: Vec<(usize, Label)>,
+}
+
+impl FunctionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The offset of the next instruction to be emitted.
+    pub fn position(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Creates a label which isn't bound to a position yet.
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Binds `label` to the next instruction to be emitted.
+    ///
+    /// # Panics
+    /// If the label was already bound.
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        let position = self.position();
+        let bound = &mut self.labels[label.0];
+        assert!(bound.is_none(), "Label bound twice");
+        *bound = Some(position);
+        self
+    }
+
+    /// Emits any instruction. Jumps emitted this way must already have their
+    /// final target.
+    pub fn emit(&mut self, instruction: impl Into<Instruction>) -> &mut Self {
+        self.instructions.push(instruction.into());
+        self
+    }
+
+    /// `[dst] = constant`
+    pub fn load_const(&mut self, dst: ImmediateRegister, constant: Constant) -> &mut Self {
+        self.emit(LoadConstant { dst, src: constant })
+    }
+
+    /// `[dst] = [src]`
+    pub fn copy(&mut self, dst: ImmediateRegister, src: ImmediateRegister) -> &mut Self {
+        self.emit(DuplicateRegister { dst, src })
+    }
+
+    /// `[dst] = [lhs] + [rhs]`
+    pub fn add(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self {
+        self.binop::<Add>(dst, lhs, rhs)
+    }
+
+    /// `[dst] = [lhs] - [rhs]`
+    pub fn sub(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self {
+        self.binop::<Subtract>(dst, lhs, rhs)
+    }
+
+    /// `[dst] = [lhs] * [rhs]`
+    pub fn mul(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self {
+        self.binop::<Times>(dst, lhs, rhs)
+    }
+
+    /// `[dst] = [lhs] < [rhs]`
+    pub fn less_than(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self {
+        self.binop::<LessThan>(dst, lhs, rhs)
+    }
+
+    /// `[dst] = [lhs] == [rhs]`
+    pub fn equals(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self {
+        self.binop::<Equals>(dst, lhs, rhs)
+    }
+
+    /// Binary operators update their lhs in place, so it is copied to `dst`
+    /// first if they differ.
+    ///
+    /// # Panics
+    /// If `dst` is the same register as `rhs` but not `lhs`, as the rhs would
+    /// be overwritten before it is read.
+    fn binop<Op>(
+        &mut self,
+        dst: ImmediateRegister,
+        lhs: ImmediateRegister,
+        rhs: ImmediateRegister,
+    ) -> &mut Self
+    where
+        Op: From<(ImmediateRegister, ImmediateRegister)> + Into<Instruction>,
+    {
+        if dst != lhs {
+            assert_ne!(dst, rhs, "Destination overlaps the rhs");
+            self.copy(dst, lhs);
+        }
+        self.emit(Op::from((dst, rhs)))
+    }
+
+    /// Unconditionally continues execution at `target`.
+    pub fn jump(&mut self, target: Label) -> &mut Self {
+        self.jump_to(Jump::from(0), target)
+    }
+
+    /// Continues execution at `target` if `[cond]` is truthy.
+    pub fn jump_if(&mut self, cond: ImmediateRegister, target: Label) -> &mut Self {
+        self.jump_to(JumpIf::from((cond, 0)), target)
+    }
+
+    /// Continues execution at `target` if `[cond]` is falsy.
+    pub fn jump_if_not(&mut self, cond: ImmediateRegister, target: Label) -> &mut Self {
+        self.jump_to(JumpNot::from((cond, 0)), target)
+    }
+
+    fn jump_to(&mut self, jump: impl Into<Instruction>, target: Label) -> &mut Self {
+        self.jumps.push((self.position(), target));
+        self.emit(jump)
+    }
+
+    /// Adds `[src]` to the function's results.
+    pub fn set_ret(&mut self, src: ImmediateRegister) -> &mut Self {
+        self.emit(SetRet { src })
+    }
+
+    /// Returns from the function.
+    pub fn ret(&mut self) -> &mut Self {
+        self.emit(Op::Ret)
+    }
+
+    /// Resolves jumps to their labels and produces the function.
+    ///
+    /// Fails with [`ByteCodeError::InvalidJumpTarget`] if a jump targets a
+    /// label which was never bound.
+    pub fn finish(self) -> Result<Function, ByteCodeError> {
+        let Self {
+            mut instructions,
+            labels,
+            jumps,
+        } = self;
+
+        for (position, label) in jumps {
+            let target = labels[label.0].ok_or(ByteCodeError::InvalidJumpTarget)?;
+            match &mut instructions[position] {
+                Op::Jump(Jump { target: jump })
+                | Op::JumpIf(JumpIf { target: jump, .. })
+                | Op::JumpNot(JumpNot { target: jump, .. }) => *jump = target,
+                _ => unreachable!("Only jumps are recorded as targeting labels"),
+            }
+        }
+
+        let immediates = instructions
+            .iter()
+            .flat_map(immediate_ranges)
+            .map(|(start, count)| start + count)
+            .max()
+            .unwrap_or_default();
+
+        Ok(Function {
+            immediates,
+            instructions: instructions.into(),
+            // The scope just inside of the global scope.
+            scope_depth: 1,
+            ..Default::default()
+        })
+    }
+}
@@ -18,6 +18,8 @@ use crate::{
     block::emit_block,
     Chunk,
     CompileError,
+    CompilerOptions,
+    Diagnostic,
 };
 
 mod register;
@@ -46,31 +48,42 @@ pub(crate) enum LabelId {
 }
 
 #[derive(Debug)]
-pub(crate) struct Compiler {
-    root: RootScope,
+pub(crate) struct Compiler<'src> {
+    root: RootScope<'src>,
 }
 
-impl Compiler {
-    pub(crate) fn new(strings: StringTable) -> Self {
+impl<'src> Compiler<'src> {
+    pub(crate) fn new(strings: StringTable, src: &'src str, options: CompilerOptions) -> Self {
         Self {
-            root: RootScope::new(strings),
+            root: RootScope::new(strings, src, options),
         }
     }
 
-    pub(crate) fn compile_ast(mut self, ast: Block) -> Result<Chunk, CompileError> {
+    /// Compiles `ast`, along with any diagnostics reported while compiling it.
+    pub(crate) fn compile_ast(
+        mut self,
+        ast: Block,
+    ) -> (Result<Chunk, CompileError>, Vec<Diagnostic>) {
         let main = {
             let mut main = self.root.start_main();
-            {
+            let result = {
                 let mut block = main.start();
                 let mut scope = block.enter();
 
-                emit_block(&mut scope, &ast)?;
-            }
+                emit_block(&mut scope, &ast)
+            };
 
-            main.complete_main()
+            match result {
+                Ok(_) => main.complete_main(),
+                Err(err) => return (Err(err), self.root.take_diagnostics()),
+            }
         };
 
-        Ok(self.root.into_chunk(main))
+        let diagnostics = self.root.take_diagnostics();
+        let chunk = self.root.into_chunk(main);
+        debug_assert_eq!(chunk.verify(), Ok(()), "Compiled a malformed chunk");
+
+        (Ok(chunk), diagnostics)
     }
 }
 
@@ -10,7 +10,10 @@ use std::{
     ops::Range,
 };
 
-use indexmap::IndexSet;
+use indexmap::{
+    IndexMap,
+    IndexSet,
+};
 use tlua_bytecode::{
     opcodes,
     ByteCodeError,
@@ -19,6 +22,8 @@ use tlua_bytecode::{
 };
 use tlua_parser::{
     identifiers::Ident,
+    LineIndex,
+    SourceSpan,
     StringTable,
 };
 
@@ -36,7 +41,12 @@ use crate::{
     },
     Chunk,
     CompileError,
+    CompilerOptions,
+    Diagnostic,
+    DiagnosticKind,
     FuncId,
+    LocalVariable,
+    SourceLocation,
 };
 
 const GLOBAL_SCOPE: u16 = 0;
@@ -44,8 +54,9 @@ const GLOBAL_SCOPE: u16 = 0;
 /// Manages tracking the maping from identifier to register for a particular
 /// scope.
 #[derive(Debug)]
-pub(super) struct RootScope {
+pub(super) struct RootScope<'src> {
     strings: StringTable,
+    options: CompilerOptions,
 
     /// All identifiers visible in the current scope. As scopes introduce
     /// shadows, they update this list to reflect the currently visible
@@ -60,20 +71,43 @@ pub(super) struct RootScope {
 
     /// The current list of functions created in this scope.
     functions: Vec<UnasmFunction>,
+
+    line_index: LineIndex<'src>,
+
+    /// Problems found in the source which don't prevent it from compiling.
+    diagnostics: Vec<Diagnostic>,
+
+    /// The locals in scope which haven't been read since they were declared,
+    /// along with the statement declaring them, in order of declaration.
+    /// Locals which are still unread when their block ends are reported as
+    /// unused.
+    unread_locals: IndexMap<OffsetRegister, (Ident, SourceSpan)>,
 }
 
-impl RootScope {
-    pub(super) fn new(strings: StringTable) -> Self {
+impl<'src> RootScope<'src> {
+    pub(super) fn new(strings: StringTable, src: &'src str, options: CompilerOptions) -> Self {
         Self {
             strings,
+            options,
+            line_index: LineIndex::new(src),
             visible_idents: Default::default(),
             next_global_id: 0,
             current_scope_id: 0,
             functions: Default::default(),
+            diagnostics: Default::default(),
+            unread_locals: Default::default(),
         }
     }
 
-    pub(super) fn start_main(&mut self) -> FunctionScope {
+    /// Takes the diagnostics reported so far, in the order they appear in the
+    /// source.
+    pub(super) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start());
+        diagnostics
+    }
+
+    pub(super) fn start_main(&mut self) -> FunctionScope<'_, 'src> {
         let scope_id = self.next_scope_id();
         let scope_depth = NonZeroUsize::new(usize::from(GLOBAL_SCOPE + 1)).unwrap();
         FunctionScope::new(self, scope_id, scope_depth, HasVaArgs::None, 0)
@@ -82,16 +116,20 @@ impl RootScope {
     pub(super) fn into_chunk(self, main: UnasmFunction) -> Chunk {
         debug_assert_eq!(self.next_global_id, self.visible_idents.len());
 
+        let mut globals = self
+            .visible_idents
+            .into_iter()
+            .map(|(global, reg)| {
+                debug_assert_eq!(reg.source_scope_depth, GLOBAL_SCOPE);
+                (global, usize::from(reg.offset))
+            })
+            .collect::<Vec<_>>();
+        globals.sort_unstable_by_key(|&(_, offset)| offset);
+
         Chunk {
+            name: None,
             strings: self.strings,
-            globals_map: self
-                .visible_idents
-                .into_iter()
-                .map(|(global, reg)| {
-                    debug_assert_eq!(reg.source_scope_depth, GLOBAL_SCOPE);
-                    (global, reg.offset.into())
-                })
-                .collect(),
+            globals_map: globals.into_iter().collect(),
             functions: self
                 .functions
                 .into_iter()
@@ -105,20 +143,33 @@ impl RootScope {
         self.current_scope_id += 1;
         self.current_scope_id
     }
+
+    fn locate(&self, span: SourceSpan) -> SourceLocation {
+        let line = self
+            .line_index
+            .line_of(span.start())
+            .expect("Spans are within the source");
+
+        SourceLocation {
+            line: line + 1,
+            span,
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct FunctionScope<'function> {
-    root_scope: &'function mut RootScope,
+pub(crate) struct FunctionScope<'function, 'src> {
+    root_scope: &'function mut RootScope<'src>,
 
     scope_id: usize,
     scope_depth: NonZeroUsize,
 
     has_va_args: HasVaArgs,
 
-    /// The current labels visible in the scope. See [`unresolved_jumps`] for
+    /// The current labels visible in the scope, mapped to their location & the
+    /// depth of the scope declaring them. See [`unresolved_jumps`] for
     /// information on how these are handled.
-    labels: HashMap<LabelId, usize>,
+    labels: HashMap<LabelId, (usize, usize)>,
 
     /// A map from label to scope id to a list of unresolved jumps in that
     /// scope.
@@ -163,10 +214,13 @@ pub(crate) struct FunctionScope<'function> {
     /// end
     /// ::b:: -- but it's valid here, since c's scope has ended.
     /// ```
-    unresolved_jumps: HashMap<LabelId, BTreeMap<usize, Vec<usize>>>,
+    unresolved_jumps: HashMap<LabelId, BTreeMap<usize, Vec<PendingJump>>>,
 
     free_registers: IndexSet<ImmediateRegister>,
 
+    /// The ids of the loops enclosing the current position, innermost last,
+    /// along with the depth of the scope declaring each loop's exit label.
+    loop_ids: Vec<(usize, usize)>,
     next_loop_id: usize,
     next_if_id: usize,
     next_immediate: usize,
@@ -174,9 +228,9 @@ pub(crate) struct FunctionScope<'function> {
     function: UnasmFunction,
 }
 
-impl<'function> FunctionScope<'function> {
+impl<'function, 'src> FunctionScope<'function, 'src> {
     fn new(
-        root_scope: &'function mut RootScope,
+        root_scope: &'function mut RootScope<'src>,
         scope_id: usize,
         scope_depth: NonZeroUsize,
         has_va_args: HasVaArgs,
@@ -190,17 +244,20 @@ impl<'function> FunctionScope<'function> {
             labels: Default::default(),
             unresolved_jumps: Default::default(),
             free_registers: Default::default(),
+            loop_ids: Default::default(),
             next_loop_id: 0,
             next_if_id: 0,
             next_immediate: 0,
             function: UnasmFunction {
                 named_args: argc,
+                is_vararg: matches!(has_va_args, HasVaArgs::Some),
+                scope_depth: scope_depth.get(),
                 ..Default::default()
             },
         }
     }
 
-    pub(crate) fn start<'block>(&'block mut self) -> BlockScope<'block, 'function> {
+    pub(crate) fn start<'block>(&'block mut self) -> BlockScope<'block, 'function, 'src> {
         let scope_id = self.scope_id;
         let scope_depth = self.scope_depth;
 
@@ -224,21 +281,30 @@ impl<'function> FunctionScope<'function> {
         LabelId::If { id }
     }
 
-    fn push_loop_id(&mut self) -> LabelId {
+    fn push_loop_id(&mut self, depth: usize) -> LabelId {
+        // Ids aren't reused, since sibling loops in the same block would otherwise
+        // declare the same exit label.
         let id = self.next_loop_id;
         self.next_loop_id += 1;
+        self.loop_ids.push((id, depth));
 
         LabelId::Loop { id }
     }
 
     fn pop_loop_id(&mut self) {
-        self.next_loop_id -= 1;
+        self.loop_ids.pop();
     }
 
     fn current_loop_id(&self) -> Option<LabelId> {
-        self.next_loop_id
-            .checked_sub(1)
-            .map(|id| LabelId::Loop { id })
+        self.loop_ids.last().map(|&(id, _)| LabelId::Loop { id })
+    }
+
+    fn loop_depth(&self, loop_id: usize) -> Option<usize> {
+        self.loop_ids
+            .iter()
+            .rev()
+            .find(|&&(id, _)| id == loop_id)
+            .map(|&(_, depth)| depth)
     }
 
     fn push_immediate(&mut self) -> ImmediateRegister {
@@ -272,7 +338,15 @@ impl<'function> FunctionScope<'function> {
             .zip(self.free_registers.iter().skip(1))
             .all(|(&first, &second)| usize::from(first) + 1 == usize::from(second));
 
-        if is_contiguous {
+        // The free registers can only be reclaimed if nothing above them is still in
+        // use.
+        let is_top_of_stack = self
+            .free_registers
+            .last()
+            .map(|&last| usize::from(last) + 1 == self.next_immediate)
+            .unwrap_or(true);
+
+        if is_contiguous && is_top_of_stack {
             self.next_immediate = self
                 .free_registers
                 .first()
@@ -302,9 +376,21 @@ impl<'function> FunctionScope<'function> {
     }
 }
 
+/// A jump to a label which hasn't been declared yet.
+#[derive(Debug, Clone, Copy)]
+struct PendingJump {
+    /// The first of the instructions reserved for the jump.
+    position: usize,
+    /// The depth of the scope which is being jumped out of.
+    depth: usize,
+    /// The number of scopes which the reserved instructions can pop before
+    /// jumping.
+    max_pops: usize,
+}
+
 #[derive(Debug)]
-pub(crate) struct BlockScope<'block, 'function> {
-    function_scope: &'block mut FunctionScope<'function>,
+pub(crate) struct BlockScope<'block, 'function, 'src> {
+    function_scope: &'block mut FunctionScope<'function, 'src>,
 
     original_scope_id: usize,
     current_scope_id: usize,
@@ -316,7 +402,7 @@ pub(crate) struct BlockScope<'block, 'function> {
     declared_labels: HashSet<LabelId>,
 }
 
-impl Drop for BlockScope<'_, '_> {
+impl Drop for BlockScope<'_, '_, '_> {
     fn drop(&mut self) {
         if let Some(location) = self.pending_scope_push {
             self.overwrite(
@@ -341,16 +427,43 @@ impl Drop for BlockScope<'_, '_> {
             let removed = self.function_scope.labels.remove(&label);
             debug_assert!(removed.is_some());
         }
+
+        // Child blocks have already ended, so every unread local at this depth belongs
+        // to this block.
+        let depth = self.scope_depth.get();
+        let RootScope {
+            strings,
+            diagnostics,
+            unread_locals,
+            ..
+        } = &mut *self.function_scope.root_scope;
+
+        unread_locals.retain(|register, &mut (name, span)| {
+            if usize::from(register.source_scope_depth) != depth {
+                return true;
+            }
+
+            diagnostics.push(Diagnostic {
+                span,
+                kind: DiagnosticKind::UnusedLocal {
+                    name: strings
+                        .get_ident(name)
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                },
+            });
+            false
+        });
     }
 }
 
-impl<'block, 'function> BlockScope<'block, 'function> {
-    pub(crate) fn enter<'scope>(&'scope mut self) -> Scope<'scope, 'block, 'function> {
+impl<'block, 'function, 'src> BlockScope<'block, 'function, 'src> {
+    pub(crate) fn enter<'scope>(&'scope mut self) -> Scope<'scope, 'block, 'function, 'src> {
         Scope { block_scope: self }
     }
 
     fn new_with_pushed_scope(
-        function_scope: &'block mut FunctionScope<'function>,
+        function_scope: &'block mut FunctionScope<'function, 'src>,
         scope_id: usize,
         scope_depth: NonZeroUsize,
     ) -> Self {
@@ -372,7 +485,7 @@ impl<'block, 'function> BlockScope<'block, 'function> {
     }
 
     fn new(
-        function_scope: &'block mut FunctionScope<'function>,
+        function_scope: &'block mut FunctionScope<'function, 'src>,
         scope_id: usize,
         scope_depth: NonZeroUsize,
         pending_scope_push: Option<usize>,
@@ -404,19 +517,23 @@ impl<'block, 'function> BlockScope<'block, 'function> {
 }
 
 #[derive(Debug)]
-pub(crate) struct Scope<'scope, 'block, 'function> {
-    block_scope: &'scope mut BlockScope<'block, 'function>,
+pub(crate) struct Scope<'scope, 'block, 'function, 'src> {
+    block_scope: &'scope mut BlockScope<'block, 'function, 'src>,
 }
 
-impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
+impl<'scope, 'block, 'function, 'src> Scope<'scope, 'block, 'function, 'src> {
     pub(crate) fn string_table(&self) -> &StringTable {
         &self.block_scope.function_scope.root_scope.strings
     }
 
-    /// Check if varargs are available in scope
-    pub(crate) fn check_varargs(&self) -> Result<(), CompileError> {
+    pub(crate) fn options(&self) -> &CompilerOptions {
+        &self.block_scope.function_scope.root_scope.options
+    }
+
+    /// Check if varargs are available in scope for the `...` at `span`
+    pub(crate) fn check_varargs(&self, span: SourceSpan) -> Result<(), CompileError> {
         match self.block_scope.function_scope.has_va_args {
-            HasVaArgs::None => Err(CompileError::NoVarArgsAvailable),
+            HasVaArgs::None => Err(CompileError::NoVarArgsAvailable { span }),
             HasVaArgs::Some => Ok(()),
         }
     }
@@ -426,21 +543,78 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
         self.block_scope.function_scope.function.instructions.len()
     }
 
+    /// Record that instructions emitted from this point onwards belong to the
+    /// source at `span`.
+    pub(crate) fn mark_location(&mut self, span: SourceSpan) {
+        let offset = self.next_instruction();
+        let location = self.block_scope.function_scope.root_scope.locate(span);
+
+        let line_table = &mut self.block_scope.function_scope.function.line_table;
+        match line_table.last_mut() {
+            // Nothing was emitted for the previous statement, so it can never be the source
+            // of an error.
+            Some(last) if last.0 == offset => *last = (offset, location),
+            _ => line_table.push((offset, location)),
+        }
+    }
+
+    /// Record a problem with the source at `span` which doesn't prevent it from
+    /// compiling.
+    pub(crate) fn report(&mut self, span: SourceSpan, kind: DiagnosticKind) {
+        self.block_scope
+            .function_scope
+            .root_scope
+            .diagnostics
+            .push(Diagnostic { span, kind });
+    }
+
     /// Add a label tracking the current instruction position that can be
     /// referenced by labeled jumps.
     pub(crate) fn label_current_instruction(&mut self, label: LabelId) -> Result<(), CompileError> {
-        let label = label;
+        self.label_instruction(label, false)
+    }
+
+    /// Add a label at the end of the current block. Since none of the block's
+    /// locals are in scope there, jumps from anywhere in the block may target
+    /// it.
+    pub(crate) fn label_block_end(&mut self, label: LabelId) -> Result<(), CompileError> {
+        self.label_instruction(label, true)
+    }
+
+    /// The name of `label` for error messages. Labels generated by the compiler
+    /// don't appear in the source, so they are only named for debugging.
+    fn label_name(&self, label: LabelId) -> String {
+        match label {
+            LabelId::Named(ident) => self
+                .block_scope
+                .function_scope
+                .root_scope
+                .strings
+                .get_ident(ident)
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            label => format!("{:?}", label),
+        }
+    }
+
+    fn label_instruction(
+        &mut self,
+        label: LabelId,
+        at_block_end: bool,
+    ) -> Result<(), CompileError> {
         let location = self.block_scope.function_scope.function.instructions.len();
+        let depth = self.block_scope.scope_depth.get();
 
         if self
             .block_scope
             .function_scope
             .labels
-            .insert(label, location)
+            .insert(label, (location, depth))
             .is_some()
         {
             return Err(CompileError::DuplicateLabel {
-                label: format!("{:?}", label),
+                label: self.label_name(label),
+                span: None,
             });
         }
 
@@ -453,22 +627,37 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
             .unresolved_jumps
             .get_mut(&label)
         {
-            if maybe_resolve
-                .range(self.block_scope.original_scope_id..self.block_scope.current_scope_id)
-                .next()
-                .is_some()
+            let resolved_scopes = if at_block_end {
+                self.block_scope.original_scope_id..
+            } else {
+                self.block_scope.current_scope_id..
+            };
+
+            if !at_block_end
+                && maybe_resolve
+                    .range(self.block_scope.original_scope_id..self.block_scope.current_scope_id)
+                    .next()
+                    .is_some()
             {
                 return Err(CompileError::JumpIntoLocalScope {
-                    label: format!("{:?}", label),
+                    label: self.label_name(label),
+                    span: None,
                 });
             }
 
+            let instructions = &mut self.block_scope.function_scope.function.instructions;
             for pending_jump in maybe_resolve
-                .range_mut(self.block_scope.current_scope_id..)
+                .range_mut(resolved_scopes)
                 .flat_map(|(_, items)| items.drain(..))
             {
-                self.block_scope.function_scope.function.instructions[pending_jump] =
-                    opcodes::Jump::from(location).into();
+                // Jumps are only resolved from this scope or its children, so they can only
+                // leave scopes.
+                let pops = pending_jump.depth - depth;
+                debug_assert!(pops <= pending_jump.max_pops);
+
+                let reserved = &mut instructions[pending_jump.position..][..=pops];
+                reserved[..pops].fill(UnasmOp::PopScope);
+                reserved[pops] = opcodes::Jump::from(location).into();
             }
         }
 
@@ -488,8 +677,11 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
 
     /// Create a new loop label. The caller must call pop_loop_label after
     /// using.
+    ///
+    /// The loop's exit label must be declared in the current block.
     pub(crate) fn push_loop_label(&mut self) -> LabelId {
-        self.block_scope.function_scope.push_loop_id()
+        let depth = self.block_scope.scope_depth.get();
+        self.block_scope.function_scope.push_loop_id(depth)
     }
 
     /// Pop the current loop label.
@@ -497,14 +689,51 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
         self.block_scope.function_scope.pop_loop_id()
     }
 
-    /// Emit an instruction jumping to a label. If the specified label does not
+    /// Emit an instruction jumping to a label, preceded by instructions popping
+    /// any scopes which are left by the jump. If the specified label does not
     /// exist, it will default to raising an error. If the label is added later
-    /// in the scope, the instruction will be updated to jump to that location.
+    /// in the scope, the instructions will be updated to jump to that location.
     pub(crate) fn emit_jump_label(&mut self, label: LabelId) -> usize {
+        let depth = self.block_scope.scope_depth.get();
+
         match self.block_scope.function_scope.labels.get(&label) {
-            Some(&location) => self.emit(opcodes::Jump::from(location)),
+            Some(&(location, label_depth)) => {
+                self.emit_scope_pops(depth - label_depth);
+                self.emit(opcodes::Jump::from(location))
+            }
             None => {
+                let (depth, max_pops) = match label {
+                    // The scopes left by a loop's exit jump are already known, so they can be
+                    // popped right away.
+                    LabelId::Loop { id } => {
+                        let loop_depth = self
+                            .block_scope
+                            .function_scope
+                            .loop_depth(id)
+                            .unwrap_or(depth);
+                        self.emit_scope_pops(depth - loop_depth);
+                        (loop_depth, 0)
+                    }
+                    // If statements only jump to their exit label from the block declaring it.
+                    LabelId::If { .. } => (depth, 0),
+                    // A named label may be declared in any enclosing block of the function, so
+                    // enough space is reserved to leave all of them.
+                    LabelId::Named(_) => (
+                        depth,
+                        depth - self.block_scope.function_scope.scope_depth.get(),
+                    ),
+                };
+
                 let position = self.emit(opcodes::Raise::from(OpError::MissingLabel));
+                for _ in 0..max_pops {
+                    self.emit(UnasmOp::Nop);
+                }
+
+                let pending_jump = PendingJump {
+                    position,
+                    depth,
+                    max_pops,
+                };
                 match self
                     .block_scope
                     .function_scope
@@ -514,7 +743,7 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
                     hash_map::Entry::Vacant(new_scope_entries) => {
                         new_scope_entries.insert(BTreeMap::from([(
                             self.block_scope.current_scope_id,
-                            vec![position],
+                            vec![pending_jump],
                         )]));
                     }
                     hash_map::Entry::Occupied(mut scope_entries) => {
@@ -523,10 +752,10 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
                             .entry(self.block_scope.current_scope_id)
                         {
                             btree_map::Entry::Vacant(new_list) => {
-                                new_list.insert(vec![position]);
+                                new_list.insert(vec![pending_jump]);
                             }
                             btree_map::Entry::Occupied(mut list) => {
-                                list.get_mut().push(position);
+                                list.get_mut().push(pending_jump);
                             }
                         }
                     }
@@ -543,6 +772,14 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
         self.block_scope.emit(opcode)
     }
 
+    /// Emit instructions leaving `count` scopes, for control flow which jumps
+    /// out of them rather than reaching the end of each block.
+    fn emit_scope_pops(&mut self, count: usize) {
+        for _ in 0..count {
+            self.emit(UnasmOp::PopScope);
+        }
+    }
+
     pub(crate) fn reserve_jump_isn(&mut self) -> usize {
         self.block_scope
             .emit(opcodes::Raise::from(OpError::ByteCodeError {
@@ -567,29 +804,66 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
         self.new_local(ident)
     }
 
+    /// Map a new register for a variable declared by a `local` statement. The
+    /// variable is reported as unused if it is never read, unless its name
+    /// starts with an underscore.
+    pub(crate) fn declare_local(
+        &mut self,
+        ident: Ident,
+    ) -> Result<MappedLocalRegister, CompileError> {
+        let register = self.new_local(ident)?;
+
+        let function = &self.block_scope.function_scope.function;
+        let root = &mut *self.block_scope.function_scope.root_scope;
+        let is_ignored = root
+            .strings
+            .get_ident(ident)
+            .is_none_or(|name| name.starts_with(b"_"));
+
+        if let Some(&(_, location)) = function.line_table.last().filter(|_| !is_ignored) {
+            root.unread_locals.insert(*register, (ident, location.span));
+        }
+
+        Ok(register)
+    }
+
     /// Map a new register for a local variable.
     pub(crate) fn new_local(&mut self, ident: Ident) -> Result<MappedLocalRegister, CompileError> {
         self.block_scope.current_scope_id =
             self.block_scope.function_scope.root_scope.next_scope_id();
 
+        let max_depth = self.options().max_scope_depth();
+        let max_locals = self.options().max_locals();
+
+        let depth = self.block_scope.scope_depth.get();
+        if depth > max_depth {
+            return Err(CompileError::ScopeNestingTooDeep {
+                max: max_depth,
+                span: None,
+            });
+        }
+
+        let offset = self.block_scope.declared_locals.len();
+        if offset >= max_locals {
+            return Err(CompileError::TooManyLocals {
+                max: max_locals,
+                span: None,
+            });
+        }
+
         let offset_register = OffsetRegister {
-            source_scope_depth: if self.block_scope.scope_depth.get() >= usize::from(u16::MAX) {
-                return Err(CompileError::ScopeNestingTooDeep {
-                    max: usize::from(u16::MAX - 1),
-                });
-            } else {
-                self.block_scope.scope_depth.get().try_into().unwrap()
-            },
-            offset: self
-                .block_scope
-                .declared_locals
-                .len()
-                .try_into()
-                .map_err(|_| CompileError::TooManyLocals {
-                    max: u16::MAX.into(),
-                })?,
+            source_scope_depth: depth.try_into().expect("Depth is within the limit"),
+            offset: offset.try_into().expect("Offset is within the limit"),
         };
         self.block_scope.function_scope.function.local_registers += 1;
+        self.block_scope
+            .function_scope
+            .function
+            .locals
+            .push(LocalVariable {
+                name: ident,
+                register: offset_register.into(),
+            });
 
         let prev = self
             .block_scope
@@ -629,10 +903,27 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
             .pop_immediate_range(range.range);
     }
 
-    /// Lookup the appropriate register for a specific identifier.
+    /// Lookup the appropriate register for a specific identifier in order to
+    /// read from it.
     pub(crate) fn read_variable(
         &mut self,
         ident: Ident,
+    ) -> Result<MappedLocalRegister, CompileError> {
+        let register = self.write_variable(ident)?;
+        self.block_scope
+            .function_scope
+            .root_scope
+            .unread_locals
+            .shift_remove(&*register);
+
+        Ok(register)
+    }
+
+    /// Lookup the appropriate register for a specific identifier in order to
+    /// assign to it.
+    pub(crate) fn write_variable(
+        &mut self,
+        ident: Ident,
     ) -> Result<MappedLocalRegister, CompileError> {
         match self
             .block_scope
@@ -645,15 +936,24 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
             hash_map::Entry::Vacant(global) => {
                 // No ident is in scope, must be a global
                 let global_id = self.block_scope.function_scope.root_scope.next_global_id;
+
+                let max_globals = self
+                    .block_scope
+                    .function_scope
+                    .root_scope
+                    .options
+                    .max_globals();
+                if global_id >= max_globals {
+                    return Err(CompileError::TooManyGlobals {
+                        max: max_globals,
+                        span: None,
+                    });
+                }
                 self.block_scope.function_scope.root_scope.next_global_id += 1;
 
                 let offset_register = OffsetRegister {
                     source_scope_depth: GLOBAL_SCOPE,
-                    offset: global_id
-                        .try_into()
-                        .map_err(|_| CompileError::TooManyGlobals {
-                            max: u16::MAX.into(),
-                        })?,
+                    offset: global_id.try_into().expect("Offset is within the limit"),
                 };
                 Ok(MappedLocalRegister::from(*global.insert(offset_register)))
             }
@@ -663,24 +963,38 @@ impl<'scope, 'block, 'function> Scope<'scope, 'block, 'function> {
     /// Instruct the compiler to emit a sequence of instruction corresponding to
     /// raising an error with a compile-time known type.
     pub(crate) fn write_raise(&mut self, err: OpError) -> OpError {
-        self.emit(opcodes::Raise::from(err));
+        self.emit(opcodes::Raise::from(err.clone()));
         err
     }
 
-    pub(crate) fn new_function(&mut self, has_va_args: HasVaArgs, argc: usize) -> FunctionScope {
+    pub(crate) fn new_function(
+        &mut self,
+        has_va_args: HasVaArgs,
+        argc: usize,
+    ) -> FunctionScope<'_, 'src> {
         let scope_id = self.block_scope.function_scope.root_scope.next_scope_id();
         let scope_depth = NonZeroUsize::new(self.block_scope.scope_depth.get() + 1).unwrap();
+        // Functions are defined by the statement currently being compiled.
+        let defined_at = self
+            .block_scope
+            .function_scope
+            .function
+            .line_table
+            .last()
+            .map(|&(_, location)| location);
 
-        FunctionScope::new(
+        let mut function = FunctionScope::new(
             self.block_scope.function_scope.root_scope,
             scope_id,
             scope_depth,
             has_va_args,
             argc,
-        )
+        );
+        function.function.defined_at = defined_at;
+        function
     }
 
-    pub(crate) fn new_block<'sub>(&'sub mut self) -> BlockScope<'sub, 'function> {
+    pub(crate) fn new_block<'sub>(&'sub mut self) -> BlockScope<'sub, 'function, 'src> {
         let scope_id = self.block_scope.function_scope.root_scope.next_scope_id();
         let scope_depth = NonZeroUsize::new(self.block_scope.scope_depth.get() + 1).unwrap();
 
@@ -7,6 +7,8 @@ use tlua_bytecode::{
 use crate::{
     Function,
     Instructions,
+    LocalVariable,
+    SourceLocation,
 };
 
 pub(crate) trait AssembleOp {
@@ -32,7 +34,6 @@ impl From<OffsetRegister> for Register {
 
 pub(crate) type LocalRegister = OffsetRegister;
 
-#[must_use]
 pub(crate) type MappedLocalRegister = MappedRegister<LocalRegister>;
 
 impl AssembleOp for MappedRegister<LocalRegister> {
@@ -79,11 +80,13 @@ impl AssembleOp for UnasmOp {
             Op::And(op) => op.into(),
             Op::Or(op) => op.into(),
             Op::Concat(op) => op.into(),
+            Op::ConcatN(op) => op.into(),
             Op::Length(op) => op.into(),
             Op::Raise(op) => op.into(),
             Op::RaiseIfNot(op) => op.into(),
             Op::Jump(op) => op.into(),
             Op::JumpNot(op) => op.into(),
+            Op::JumpIf(op) => op.into(),
             Op::JumpNil(op) => op.into(),
             Op::Lookup(op) => op.into(),
             Op::SetProperty(op) => op.into(),
@@ -112,6 +115,7 @@ impl AssembleOp for UnasmOp {
             Op::CallCopyRet(op) => op.into(),
             Op::CallCopyVa(op) => op.into(),
             Op::CopyRetFromRetAndRet => Op::CopyRetFromRetAndRet,
+            Op::TailCall => Op::TailCall,
             Op::ConsumeRetRange(op) => op.into(),
             Op::SetAllPropertiesFromRet(op) => op.into(),
         }
@@ -121,9 +125,14 @@ impl AssembleOp for UnasmOp {
 #[derive(Debug, Default, Clone)]
 pub(crate) struct UnasmFunction {
     pub(crate) named_args: usize,
+    pub(crate) is_vararg: bool,
     pub(crate) immediates: usize,
     pub(crate) local_registers: usize,
     pub(crate) instructions: Vec<UnasmOp>,
+    pub(crate) line_table: Vec<(usize, SourceLocation)>,
+    pub(crate) locals: Vec<LocalVariable>,
+    pub(crate) defined_at: Option<SourceLocation>,
+    pub(crate) scope_depth: usize,
 }
 
 impl UnasmFunction {
@@ -131,20 +140,30 @@ impl UnasmFunction {
         let Self {
             instructions,
             named_args,
+            is_vararg,
             local_registers,
             immediates,
+            line_table,
+            locals,
+            defined_at,
+            scope_depth,
         } = self;
 
         Function {
             local_registers,
             immediates,
             named_args,
+            is_vararg,
             instructions: Instructions::from(
                 instructions
                     .into_iter()
                     .map(UnasmOp::assemble)
                     .collect::<Vec<_>>(),
             ),
+            line_table,
+            locals,
+            defined_at,
+            scope_depth,
         }
     }
 }
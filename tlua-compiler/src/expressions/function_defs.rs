@@ -1,17 +1,18 @@
 use tlua_bytecode::opcodes;
 use tlua_parser::{
+    block::Block,
     expressions::function_defs::FnBody,
     identifiers::Ident,
 };
 
 use crate::{
+    block::emit_block,
     compiler::{
         HasVaArgs,
         RegisterOps,
     },
     CompileError,
     CompileExpression,
-    CompileStatement,
     FuncId,
     NodeOutput,
     Scope,
@@ -22,8 +23,7 @@ pub(crate) fn emit_fn(
     has_va_args: HasVaArgs,
     is_method: bool,
     params: impl ExactSizeIterator<Item = Ident>,
-    body: impl ExactSizeIterator<Item = impl CompileStatement>,
-    ret: Option<&impl CompileStatement>,
+    body: &Block,
 ) -> Result<FuncId, CompileError> {
     let mut func = scope.new_function(has_va_args, params.len() + usize::from(is_method));
     {
@@ -42,17 +42,11 @@ pub(crate) fn emit_fn(
             scope.new_local(param)?;
         }
 
-        for stat in body {
-            stat.compile(&mut scope)?;
-        }
+        emit_block(&mut scope, body)?;
 
-        match ret {
-            Some(ret) => ret.compile(&mut scope)?,
-            None => {
-                scope.emit(opcodes::Op::Ret);
-                None
-            }
-        };
+        if body.ret.is_none() {
+            scope.emit(opcodes::Op::Ret);
+        }
     }
 
     Ok(func.complete())
@@ -69,8 +63,7 @@ impl CompileExpression for FnBody<'_> {
             },
             false,
             self.params.named_params.iter().copied(),
-            self.body.statements.iter(),
-            self.body.ret.as_ref(),
+            &self.body,
         )?;
 
         let reg = scope.push_immediate();
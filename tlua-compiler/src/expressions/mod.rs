@@ -44,7 +44,7 @@ impl CompileExpression for ConstantString {
 
 impl CompileExpression for VarArgs {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, super::CompileError> {
-        scope.check_varargs()?;
+        scope.check_varargs(self.span)?;
         Ok(NodeOutput::VAStack)
     }
 }
@@ -3,15 +3,20 @@ use tlua_bytecode::{
     self,
     binop::{
         traits::{
-            BooleanOpEval,
             ComparisonOpEval,
             NumericOpEval,
         },
         *,
     },
+    opcodes::{
+        ConcatN,
+        JumpIf,
+        JumpNot,
+    },
     Constant,
     ImmediateRegister,
     OpError,
+    Truthy,
 };
 use tlua_parser::{
     expressions::{
@@ -22,7 +27,10 @@ use tlua_parser::{
 };
 
 use crate::{
-    compiler::unasm::UnasmOp,
+    compiler::{
+        unasm::UnasmOp,
+        JumpTemplate,
+    },
     CompileError,
     CompileExpression,
     NodeOutput,
@@ -41,8 +49,7 @@ where
     Rhs: CompileExpression,
     ConstEval: FnOnce(&StringTable, Constant, Constant) -> Result<Constant, OpError>,
 {
-    let lhs = lhs.compile(scope)?;
-    let rhs = rhs.compile(scope)?;
+    let (lhs, rhs) = compile_operands(scope, lhs, rhs)?;
 
     match (lhs, rhs) {
         (NodeOutput::Constant(lhs), NodeOutput::Constant(rhs)) => {
@@ -63,6 +70,35 @@ where
     }
 }
 
+/// Compiles both operands of a binary operator, ensuring that `lhs` is fully
+/// evaluated before `rhs` is. Constants are left as-is so that they can still
+/// be folded.
+fn compile_operands<Lhs, Rhs>(
+    scope: &mut Scope,
+    lhs: Lhs,
+    rhs: Rhs,
+) -> Result<(NodeOutput, NodeOutput), CompileError>
+where
+    Lhs: CompileExpression,
+    Rhs: CompileExpression,
+{
+    let lhs = match lhs.compile(scope)? {
+        lhs @ NodeOutput::Constant(_) => lhs,
+        // Anything else might be observed or clobbered by evaluating the rhs, e.g. the
+        // results of a call would be replaced by the results of a call in the rhs.
+        lhs => NodeOutput::Immediate(lhs.into_register(scope)),
+    };
+
+    let rhs = match rhs.compile(scope)? {
+        // The results of a call must be consumed directly after it, before a constant
+        // lhs is loaded.
+        rhs @ NodeOutput::ReturnValues => NodeOutput::Immediate(rhs.into_register(scope)),
+        rhs => rhs,
+    };
+
+    Ok((lhs, rhs))
+}
+
 fn write_numeric_binop<Op>(
     scope: &mut Scope,
     lhs: &Expression,
@@ -71,11 +107,48 @@ fn write_numeric_binop<Op>(
 where
     Op: NumericOpEval + From<(ImmediateRegister, ImmediateRegister)> + Into<UnasmOp>,
 {
-    write_binop::<Op, _, _, _>(scope, lhs, rhs, |_, lhs, rhs| {
-        Op::evaluate(lhs, rhs).map(|num| num.into())
+    write_binop::<Op, _, _, _>(scope, lhs, rhs, |strings, lhs, rhs| {
+        Op::evaluate(coerce_numeral(strings, lhs), coerce_numeral(strings, rhs))
+            .map(|num| num.into())
     })
 }
 
+fn write_int_binop<Op>(
+    scope: &mut Scope,
+    lhs: &Expression,
+    rhs: &Expression,
+) -> Result<NodeOutput, CompileError>
+where
+    Op: NumericOpEval + From<(ImmediateRegister, ImmediateRegister)> + Into<UnasmOp>,
+{
+    write_binop::<Op, _, _, _>(scope, lhs, rhs, |strings, lhs, rhs| {
+        match (coerce_numeral(strings, lhs), coerce_numeral(strings, rhs)) {
+            (
+                Constant::Float(_) | Constant::Integer(_),
+                operand @ (Constant::Nil | Constant::Bool(_) | Constant::String(_)),
+            )
+            | (operand @ (Constant::Nil | Constant::Bool(_) | Constant::String(_)), _) => {
+                Err(OpError::BitwiseOnNonNumber {
+                    ty: operand.short_type_name(),
+                })
+            }
+            (lhs, rhs) => Op::evaluate(lhs, rhs).map(|num| num.into()),
+        }
+    })
+}
+
+/// Converts string constants holding a numeral to the number they hold, as
+/// arithmetic on them would at runtime.
+fn coerce_numeral(strings: &StringTable, constant: Constant) -> Constant {
+    match constant {
+        Constant::String(id) => strings
+            .get_string(id)
+            .and_then(|string| tlua_bytecode::Number::parse_lua(string))
+            .map_or(constant, Constant::from),
+        constant => constant,
+    }
+}
+
 fn write_cmp_binop<Op>(
     scope: &mut Scope,
     lhs: &Expression,
@@ -112,15 +185,37 @@ where
     })
 }
 
-fn write_boolean_binop<Op>(
+/// Writes a short-circuiting boolean operation. The result is `lhs` if
+/// evaluating `lhs` as a bool produces `short_circuit_when`, otherwise `rhs` is
+/// evaluated and becomes the result.
+fn write_boolean_binop<Jump>(
     scope: &mut Scope,
     lhs: &Expression,
     rhs: &Expression,
+    short_circuit_when: bool,
 ) -> Result<NodeOutput, CompileError>
 where
-    Op: BooleanOpEval + From<(ImmediateRegister, ImmediateRegister)> + Into<UnasmOp>,
+    Jump: From<(ImmediateRegister, usize)> + Into<UnasmOp>,
 {
-    write_binop::<Op, _, _, _>(scope, lhs, rhs, |_, lhs, rhs| Ok(Op::evaluate(lhs, rhs)))
+    let lhs = match lhs.compile(scope)? {
+        NodeOutput::Constant(lhs) => {
+            return if lhs.as_bool() == short_circuit_when {
+                Ok(NodeOutput::Constant(lhs))
+            } else {
+                rhs.compile(scope)
+            };
+        }
+        lhs => lhs.into_register(scope),
+    };
+
+    let skip_rhs = JumpTemplate::<Jump>::conditional_at(scope.reserve_jump_isn(), lhs);
+
+    rhs.compile(scope)?.into_existing_register(scope, lhs);
+
+    let after_rhs = scope.next_instruction();
+    skip_rhs.resolve_to(after_rhs, scope);
+
+    Ok(NodeOutput::Immediate(lhs))
 }
 
 impl CompileExpression for operator::Plus<'_> {
@@ -167,38 +262,51 @@ impl CompileExpression for operator::Exponetiation<'_> {
 
 impl CompileExpression for operator::BitAnd<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_numeric_binop::<BitAnd>(scope, self.lhs, self.rhs)
+        write_int_binop::<BitAnd>(scope, self.lhs, self.rhs)
     }
 }
 
 impl CompileExpression for operator::BitOr<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_numeric_binop::<BitOr>(scope, self.lhs, self.rhs)
+        write_int_binop::<BitOr>(scope, self.lhs, self.rhs)
     }
 }
 
 impl CompileExpression for operator::BitXor<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_numeric_binop::<BitXor>(scope, self.lhs, self.rhs)
+        write_int_binop::<BitXor>(scope, self.lhs, self.rhs)
     }
 }
 
 impl CompileExpression for operator::ShiftLeft<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_numeric_binop::<ShiftLeft>(scope, self.lhs, self.rhs)
+        write_int_binop::<ShiftLeft>(scope, self.lhs, self.rhs)
     }
 }
 
 impl CompileExpression for operator::ShiftRight<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_numeric_binop::<ShiftRight>(scope, self.lhs, self.rhs)
+        write_int_binop::<ShiftRight>(scope, self.lhs, self.rhs)
     }
 }
 
 impl CompileExpression for operator::Concat<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        let lhs = self.lhs.compile(scope)?;
-        let rhs = self.rhs.compile(scope)?;
+        // Concatenation is right associative, so `a .. b .. c` is parsed as `a .. (b ..
+        // c)`.
+        let mut operands = vec![self.lhs];
+        let mut rest = self.rhs;
+        while let Expression::BinaryOp(operator::BinaryOperator::Concat(concat)) = rest {
+            operands.push(concat.lhs);
+            rest = concat.rhs;
+        }
+        operands.push(rest);
+
+        if operands.len() > 2 {
+            return write_concat_chain(scope, &operands);
+        }
+
+        let (lhs, rhs) = compile_operands(scope, self.lhs, self.rhs)?;
 
         let lhs = lhs.into_register(scope);
 
@@ -210,6 +318,35 @@ impl CompileExpression for operator::Concat<'_> {
     }
 }
 
+/// Evaluates each operand of a chain of concatenations in order, then joins
+/// them with a single instruction so that no intermediate strings are built.
+fn write_concat_chain(
+    scope: &mut Scope,
+    operands: &[&Expression],
+) -> Result<NodeOutput, CompileError> {
+    let dst = scope.push_immediate();
+
+    let operand_range = scope.reserve_immediate_range(operands.len());
+    let mut operand_registers = operand_range.iter().peekable();
+    let mut scope = guard_on_success(scope, |scope| scope.pop_immediate_range(operand_range));
+
+    let start = usize::from(
+        *operand_registers
+            .peek()
+            .expect("Chains have at least 3 operands"),
+    );
+
+    for (reg, operand) in operand_registers.zip(operands) {
+        operand
+            .compile(&mut scope)?
+            .into_existing_register(&mut scope, reg);
+    }
+
+    scope.emit(ConcatN::from((dst, start, operands.len())));
+
+    Ok(NodeOutput::Immediate(dst))
+}
+
 impl CompileExpression for operator::LessThan<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
         write_cmp_binop::<LessThan>(scope, self.lhs, self.rhs)
@@ -248,12 +385,12 @@ impl CompileExpression for operator::NotEqual<'_> {
 
 impl CompileExpression for operator::And<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_boolean_binop::<And>(scope, self.lhs, self.rhs)
+        write_boolean_binop::<JumpNot>(scope, self.lhs, self.rhs, false)
     }
 }
 
 impl CompileExpression for operator::Or<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
-        write_boolean_binop::<Or>(scope, self.lhs, self.rhs)
+        write_boolean_binop::<JumpIf>(scope, self.lhs, self.rhs, true)
     }
 }
@@ -51,7 +51,7 @@ impl CompileExpression for Negation<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
         write_unary_op::<UnaryMinus, _, _>(scope, &self.0, |_, v| match v {
             Constant::Float(f) => Ok((-f).into()),
-            Constant::Integer(i) => Ok((-i).into()),
+            Constant::Integer(i) => Ok(i.wrapping_neg().into()),
             _ => Err(tlua_bytecode::OpError::InvalidType { op: "negation" }),
         })
     }
@@ -60,9 +60,13 @@ impl CompileExpression for Negation<'_> {
 impl CompileExpression for BitNot<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
         write_unary_op::<UnaryBitNot, _, _>(scope, &self.0, |_, v| match v {
-            Constant::Float(f) => f64inbounds(f).map(|i| (!i).into()),
+            Constant::Float(f) => f64inbounds(f)
+                .map(|i| (!i).into())
+                .map_err(|_| tlua_bytecode::OpError::NoIntegerRepresentation { f }),
             Constant::Integer(i) => Ok((!i).into()),
-            _ => Err(tlua_bytecode::OpError::InvalidType { op: "bitwise not" }),
+            operand => Err(tlua_bytecode::OpError::BitwiseOnNonNumber {
+                ty: operand.short_type_name(),
+            }),
         })
     }
 }
@@ -39,13 +39,13 @@ pub(crate) fn emit_init_sequence<'a, 'f>(
 where
     'f: 'a,
 {
-    let mut arraylike = vec![];
-    let mut last_field_va = false;
-
     let index = scope.push_immediate();
     let mut scope = guard_on_success(scope, |scope| scope.pop_immediate(index));
 
-    for field in fields {
+    let mut arraylike = vec![];
+
+    let mut fields = fields.peekable();
+    while let Some(field) = fields.next() {
         match field {
             Field::Named { name, expression } => {
                 index.set_from_constant(&mut scope, ConstantString::from(name).into())?;
@@ -56,8 +56,6 @@ where
                 let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(value));
 
                 scope.emit(opcodes::SetProperty::from((table, index, value)));
-
-                last_field_va = false;
             }
             Field::Indexed {
                 index: index_expr,
@@ -75,55 +73,68 @@ where
                 let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(value));
 
                 scope.emit(opcodes::SetProperty::from((table, index, value)));
-
-                last_field_va = false;
+            }
+            Field::Arraylike { expression } if fields.peek().is_none() => {
+                // The last field expands to all of its values. The results of a call must be
+                // consumed by the instruction immediately following it, so the other entries
+                // are stored first.
+                let va_start = arraylike.len();
+                emit_array_entries(&mut scope, table, index, 0, std::mem::take(&mut arraylike))?;
+
+                match expression.compile(&mut scope)? {
+                    NodeOutput::ReturnValues => {
+                        scope.emit(opcodes::SetAllPropertiesFromRet::from((
+                            table,
+                            va_start + 1,
+                        )));
+                    }
+                    NodeOutput::VAStack => {
+                        scope.emit(opcodes::SetAllPropertiesFromVa::from((table, va_start + 1)));
+                    }
+                    init => emit_array_entries(&mut scope, table, index, va_start, vec![init])?,
+                }
             }
             Field::Arraylike { expression } => {
-                arraylike.push(expression.compile(&mut scope)?);
-                last_field_va = matches!(
-                    arraylike.last(),
-                    Some(NodeOutput::VAStack | NodeOutput::ReturnValues)
-                );
+                arraylike.push(match expression.compile(&mut scope)? {
+                    init @ NodeOutput::Constant(_) => init,
+                    // Anything else might be clobbered by evaluating the following fields, e.g. the
+                    // results of a call would be replaced by the results of the next call.
+                    init => NodeOutput::Immediate(init.into_register(&mut scope)),
+                });
             }
         }
     }
 
-    let va_start = arraylike.len();
-    let (last, initializers) = if last_field_va {
-        (arraylike.pop(), arraylike)
-    } else {
-        (None, arraylike)
-    };
-
-    for (array_index, init) in initializers.into_iter().enumerate() {
-        let value = init.into_register(&mut scope);
-        let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(value));
-
-        index.set_from_constant(
-            &mut scope,
-            i64::try_from(array_index + 1)
-                .map_err(|_| CompileError::TooManyTableEntries {
-                    max: i64::MAX as usize,
-                })?
-                .into(),
-        )?;
+    emit_array_entries(&mut scope, table, index, 0, arraylike)?;
 
-        scope.emit(opcodes::SetProperty::from((table, index, value)));
-    }
+    Ok(None)
+}
 
-    if let Some(last) = last {
-        match last {
-            NodeOutput::ReturnValues => {
-                scope.emit(opcodes::SetAllPropertiesFromRet::from((table, va_start)));
-            }
-            NodeOutput::VAStack => {
-                scope.emit(opcodes::SetAllPropertiesFromVa::from((table, va_start)));
-            }
-            _ => {
-                unreachable!("Only VA and return value nodes need special handling.")
-            }
+/// Stores `entries` in the array part of `table`, starting after `skip`
+/// entries.
+fn emit_array_entries(
+    scope: &mut Scope,
+    table: ImmediateRegister,
+    index: ImmediateRegister,
+    skip: usize,
+    entries: Vec<NodeOutput>,
+) -> Result<(), CompileError> {
+    let max_entries = scope.options().max_table_entries();
+    for (array_index, init) in (skip..).zip(entries) {
+        if array_index >= max_entries {
+            return Err(CompileError::TooManyTableEntries {
+                max: max_entries,
+                span: None,
+            });
         }
+
+        let value = init.into_register(scope);
+        let mut scope = guard_on_success(&mut *scope, |scope| scope.pop_immediate(value));
+
+        index.set_from_constant(&mut scope, (array_index as i64 + 1).into())?;
+
+        scope.emit(opcodes::SetProperty::from((table, index, value)));
     }
 
-    Ok(None)
+    Ok(())
 }
@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
     num::NonZeroUsize,
+    ops::Range,
+    rc::Rc,
 };
 
 use derive_more::{
@@ -8,31 +9,49 @@ use derive_more::{
     From,
     Into,
 };
+use indexmap::IndexMap;
 use scopeguard::guard_on_success;
 use thiserror::Error;
 use tlua_bytecode::{
     opcodes::Instruction,
+    ByteCodeError,
     Constant,
     ImmediateRegister,
     OpError,
+    Register,
     TypeId,
 };
 use tlua_parser::{
     expressions::Expression,
     identifiers::Ident,
     parse_chunk,
+    parse_chunk_with_options,
     statement::Statement,
     ASTAllocator,
     ChunkParseError,
+    SourceSpan,
+};
+pub use tlua_parser::{
+    ParseOptions,
     StringTable,
+    UnicodeEscapes,
+    UnknownEscapes,
 };
+use tlua_strings::LuaString;
 
 mod block;
+mod builder;
 mod compiler;
 mod expressions;
+mod peephole;
 mod prefix_expression;
 mod statement;
+mod verify;
 
+pub use self::builder::{
+    FunctionBuilder,
+    Label,
+};
 use self::compiler::Scope;
 use crate::compiler::{
     unasm::MappedLocalRegister,
@@ -110,6 +129,12 @@ impl NodeOutput {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, From, Into)]
 pub struct FuncId(usize);
 
+impl FuncId {
+    /// Identifies a chunk's top-level code, which isn't part of
+    /// [`Chunk::functions`].
+    pub const MAIN: FuncId = FuncId(usize::MAX);
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BuiltinType {
     Table,
@@ -139,26 +164,189 @@ impl TryFrom<TypeId> for BuiltinType {
     }
 }
 
+/// An error which prevents a chunk from compiling.
+///
+/// Errors which the compiler finds carry the location of the statement or
+/// token which caused them, when it's known.
 #[derive(Debug, Error)]
 pub enum CompileError {
     #[error("Error parsing lua source: {0:?}")]
     ParseError(ChunkParseError),
     #[error("Duplicate label: {label}")]
-    DuplicateLabel { label: String },
+    DuplicateLabel {
+        label: String,
+        span: Option<SourceSpan>,
+    },
     #[error("Goto {label} jumps into scope of local")]
-    JumpIntoLocalScope { label: String },
-    #[error("Cannot use ... outside of a vararg function")]
-    NoVarArgsAvailable,
+    JumpIntoLocalScope {
+        label: String,
+        span: Option<SourceSpan>,
+    },
+    #[error("Cannot use ... outside of a vararg function at {span}")]
+    NoVarArgsAvailable { span: SourceSpan },
     #[error("Allocated globals exceeded the maximum of {max:}")]
-    TooManyGlobals { max: usize },
+    TooManyGlobals {
+        max: usize,
+        span: Option<SourceSpan>,
+    },
     #[error("Allocated locals exceeded the maximum of {max:}")]
-    TooManyLocals { max: usize },
+    TooManyLocals {
+        max: usize,
+        span: Option<SourceSpan>,
+    },
     #[error("The level of scope nesting has exceeded the maximum depth of {max:}")]
-    ScopeNestingTooDeep { max: usize },
+    ScopeNestingTooDeep {
+        max: usize,
+        span: Option<SourceSpan>,
+    },
     #[error("The specified table index exceeds the max entries.")]
-    TooManyTableEntries { max: usize },
+    TooManyTableEntries {
+        max: usize,
+        span: Option<SourceSpan>,
+    },
+}
+
+impl CompileError {
+    /// The location in the source of the construct which caused the error, if
+    /// known.
+    pub fn span(&self) -> Option<SourceSpan> {
+        match self {
+            CompileError::ParseError(err) => Some(err.error.location()),
+            CompileError::NoVarArgsAvailable { span } => Some(*span),
+            CompileError::DuplicateLabel { span, .. }
+            | CompileError::JumpIntoLocalScope { span, .. }
+            | CompileError::TooManyGlobals { span, .. }
+            | CompileError::TooManyLocals { span, .. }
+            | CompileError::ScopeNestingTooDeep { span, .. }
+            | CompileError::TooManyTableEntries { span, .. } => *span,
+        }
+    }
+
+    /// Attaches `span` to the error if it doesn't have a location yet. Errors
+    /// are raised deep inside of a statement, so the innermost statement which
+    /// sees the error provides its location.
+    pub(crate) fn or_span(mut self, location: Option<SourceSpan>) -> Self {
+        match &mut self {
+            CompileError::ParseError(_) | CompileError::NoVarArgsAvailable { .. } => (),
+            CompileError::DuplicateLabel { span, .. }
+            | CompileError::JumpIntoLocalScope { span, .. }
+            | CompileError::TooManyGlobals { span, .. }
+            | CompileError::TooManyLocals { span, .. }
+            | CompileError::ScopeNestingTooDeep { span, .. }
+            | CompileError::TooManyTableEntries { span, .. } => {
+                *span = span.or(location);
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(feature = "rendered-errors")]
+impl CompileError {
+    pub fn build_report(&self) -> ariadne::Report<std::ops::Range<usize>> {
+        use ariadne::{
+            Label,
+            Report,
+            ReportKind,
+        };
+
+        if let CompileError::ParseError(err) = self {
+            return err.build_report();
+        }
+
+        let start = self.span().map_or(0, |span| span.start());
+        let report =
+            Report::build(ReportKind::Error, (), start).with_message("Failed to compile LUA");
+
+        match self.span() {
+            Some(span) => report
+                .with_label(Label::new(span.start()..span.end()).with_message(self))
+                .finish(),
+            None => report.with_note(self).finish(),
+        }
+    }
+}
+
+/// A problem in the source which doesn't prevent it from compiling, e.g. for
+/// use by linters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The location of the statement with the problem.
+    pub span: SourceSpan,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DiagnosticKind {
+    /// A local which is never read after being declared. Locals whose names
+    /// start with an underscore are never reported.
+    #[error("Unused local {name}")]
+    UnusedLocal { name: String },
+    /// A statement which can never be reached because it follows a `return`,
+    /// `break`, or `goto`.
+    #[error("Unreachable code")]
+    UnreachableCode,
+}
+
+/// Options controlling how source code is compiled. This includes limits on
+/// the size of the code accepted by the compiler, e.g. to reject unreasonably
+/// large scripts from untrusted sources.
+///
+/// The bytecode can't represent more than 65536 globals or locals in one
+/// scope, or scopes nested more than 65534 deep, so larger limits are capped
+/// to those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompilerOptions {
+    /// The maximum number of distinct global variables a chunk may refer to.
+    pub max_globals: usize,
+    /// The maximum number of locals which may be declared in one block.
+    pub max_locals: usize,
+    /// The maximum depth of nested blocks & functions which declare locals.
+    pub max_scope_depth: usize,
+    /// The maximum number of positional entries in a table constructor.
+    pub max_table_entries: usize,
+    /// How the source code is interpreted by the parser, e.g. which escape
+    /// sequences are accepted in string literals.
+    pub parse_options: ParseOptions,
 }
 
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            max_globals: MAX_REGISTERS,
+            max_locals: MAX_REGISTERS,
+            max_scope_depth: MAX_SCOPE_DEPTH,
+            max_table_entries: i64::MAX as usize,
+            parse_options: ParseOptions::default(),
+        }
+    }
+}
+
+impl CompilerOptions {
+    pub(crate) fn max_globals(&self) -> usize {
+        self.max_globals.min(MAX_REGISTERS)
+    }
+
+    pub(crate) fn max_locals(&self) -> usize {
+        self.max_locals.min(MAX_REGISTERS)
+    }
+
+    pub(crate) fn max_scope_depth(&self) -> usize {
+        self.max_scope_depth.min(MAX_SCOPE_DEPTH)
+    }
+
+    pub(crate) fn max_table_entries(&self) -> usize {
+        self.max_table_entries.min(i64::MAX as usize)
+    }
+}
+
+/// Register offsets within a scope are 16 bits.
+const MAX_REGISTERS: usize = u16::MAX as usize + 1;
+/// Scope depths are 16 bits, and the depth of every function is one more than
+/// the scope it's defined in.
+const MAX_SCOPE_DEPTH: usize = u16::MAX as usize - 1;
+
 #[derive(Debug)]
 pub(crate) enum Void {}
 
@@ -175,7 +363,13 @@ trait CompileExpression {
 impl CompileExpression for Expression<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<NodeOutput, CompileError> {
         match self {
-            Expression::Parenthesized(expr) => expr.compile(scope),
+            // Parentheses truncate a call or `...` to its first value.
+            Expression::Parenthesized(expr) => match expr.compile(scope)? {
+                output @ (NodeOutput::ReturnValues | NodeOutput::VAStack) => {
+                    Ok(NodeOutput::Immediate(output.into_register(scope)))
+                }
+                output => Ok(output),
+            },
             Expression::Variable(expr) => expr.compile(scope),
             Expression::FunctionCall(expr) => CompileExpression::compile(expr, scope),
             Expression::Nil(expr) => expr.compile(scope),
@@ -238,7 +432,7 @@ where
     }
 }
 
-#[derive(Clone, Deref, From)]
+#[derive(Default, Clone, Deref, From)]
 pub struct Instructions(Vec<Instruction>);
 
 impl std::fmt::Debug for Instructions {
@@ -251,27 +445,284 @@ impl std::fmt::Debug for Instructions {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The position in the source code which produced a range of instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The 1-based line the statement starts on.
+    pub line: usize,
+    pub span: SourceSpan,
+}
+
+/// A local variable declared by a function, including its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalVariable {
+    pub name: Ident,
+    pub register: Register,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Function {
     pub named_args: usize,
+    pub is_vararg: bool,
     pub local_registers: usize,
     pub immediates: usize,
     pub instructions: Instructions,
+    /// Maps the offset of the first instruction emitted for each statement to
+    /// the location of that statement, sorted by offset.
+    pub line_table: Vec<(usize, SourceLocation)>,
+    /// Every local declared in the function, in the order of declaration.
+    /// Locals in sibling blocks may share a register.
+    pub locals: Vec<LocalVariable>,
+    /// The location of the statement containing the function's definition, or
+    /// `None` for the top-level code of a chunk.
+    pub defined_at: Option<SourceLocation>,
+    /// The depth of the scope holding the function's locals. Every scope
+    /// above it, starting with the global scope at depth 0, encloses the
+    /// function's definition and may be referred to as an upvalue.
+    pub scope_depth: usize,
 }
 
-#[derive(Debug, Clone)]
+impl Function {
+    /// Looks up the location of the statement which emitted the instruction at
+    /// `offset`.
+    pub fn location_of(&self, offset: usize) -> Option<SourceLocation> {
+        let index = self
+            .line_table
+            .partition_point(|&(start, _)| start <= offset);
+
+        index.checked_sub(1).map(|index| self.line_table[index].1)
+    }
+
+    /// Splits the function's instructions into the ranges emitted by each
+    /// statement, along with the location of that statement.
+    fn statement_ranges(&self) -> impl Iterator<Item = (Range<usize>, SourceLocation)> + '_ {
+        let ends = self
+            .line_table
+            .iter()
+            .skip(1)
+            .map(|&(start, _)| start)
+            .chain(std::iter::once(self.instructions.len()));
+
+        self.line_table
+            .iter()
+            .zip(ends)
+            .map(|(&(start, location), end)| (start..end, location))
+            .filter(|(range, _)| !range.is_empty())
+    }
+}
+
+/// A range of instructions in one function of a chunk, all of which were
+/// emitted for the statement at `location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTableEntry {
+    pub func: FuncId,
+    /// The offsets of the instructions within the function.
+    pub instructions: Range<usize>,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Chunk {
+    /// The name given to the chunk when it was compiled, e.g. the file it was
+    /// loaded from.
+    pub name: Option<String>,
     pub strings: StringTable,
-    pub globals_map: HashMap<Ident, usize>,
+    /// Maps each global variable used by the chunk to its register in the
+    /// global scope. Entries are in order of their registers, which are
+    /// numbered from 0 without gaps.
+    pub globals_map: IndexMap<Ident, usize>,
     pub functions: Vec<Function>,
     pub main: Function,
 }
 
+impl Chunk {
+    /// Runs a peephole optimizer over the instructions of every function in the
+    /// chunk. The optimized chunk behaves the same as the original, but may
+    /// execute fewer instructions.
+    ///
+    /// Instruction offsets, e.g. those in a line table or an execution profile,
+    /// differ between the original and optimized chunk.
+    pub fn optimize(mut self) -> Self {
+        for function in std::iter::once(&mut self.main).chain(self.functions.iter_mut()) {
+            peephole::optimize(function);
+        }
+
+        self
+    }
+
+    /// Checks that the instructions of every function in the chunk are well
+    /// formed, e.g. for chunks which were built by hand rather than by the
+    /// compiler. Executing a chunk which fails verification may panic.
+    ///
+    /// This checks that jumps stay within their function, that instructions
+    /// which consume the results of a call immediately follow one, that
+    /// registers are within the counts declared by their function, and that
+    /// referenced functions & strings exist in the chunk.
+    pub fn verify(&self) -> Result<(), ByteCodeError> {
+        verify::verify(self)
+    }
+
+    /// Maps the instructions of every function in the chunk, starting with
+    /// [`FuncId::MAIN`], to the statements which emitted them. Instructions
+    /// are identified the same way as in an execution profile, so this is
+    /// enough to tell which lines of the source were executed.
+    pub fn line_table(&self) -> Vec<LineTableEntry> {
+        std::iter::once((FuncId::MAIN, &self.main))
+            .chain(
+                self.functions
+                    .iter()
+                    .enumerate()
+                    .map(|(id, function)| (FuncId::from(id), function)),
+            )
+            .flat_map(|(func, function)| {
+                function
+                    .statement_ranges()
+                    .map(move |(instructions, location)| LineTableEntry {
+                        func,
+                        instructions,
+                        location,
+                    })
+            })
+            .collect()
+    }
+
+    /// The chunk's top-level code.
+    pub fn main_function(&self) -> &Function {
+        &self.main
+    }
+
+    /// Looks up the function `id`, which may be [`FuncId::MAIN`].
+    pub fn function(&self, id: FuncId) -> Option<&Function> {
+        if id == FuncId::MAIN {
+            Some(&self.main)
+        } else {
+            self.functions.get(usize::from(id))
+        }
+    }
+
+    /// Describes the function `id`, which may be [`FuncId::MAIN`].
+    pub fn function_info(&self, id: FuncId) -> Option<FunctionInfo<'_>> {
+        let function = self.function(id)?;
+
+        Some(FunctionInfo {
+            chunk_name: self.name.as_deref(),
+            named_args: function.named_args,
+            is_vararg: function.is_vararg,
+            local_registers: function.local_registers,
+            immediates: function.immediates,
+            locals: function
+                .locals
+                .iter()
+                .map(|local| {
+                    let name = self
+                        .strings
+                        .get_ident(local.name)
+                        .expect("Locals are interned in the chunk");
+                    (name, local.register)
+                })
+                .collect(),
+            defined_at: function.defined_at,
+        })
+    }
+}
+
+/// Copies a borrowed chunk so that it can be shared, e.g. by the functions
+/// created when it is executed. Passing an owned chunk or an `Rc<Chunk>`
+/// instead avoids the copy.
+impl From<&Chunk> for Rc<Chunk> {
+    fn from(chunk: &Chunk) -> Self {
+        Rc::new(chunk.clone())
+    }
+}
+
+/// Debugging information about a compiled function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInfo<'chunk> {
+    pub chunk_name: Option<&'chunk str>,
+    pub named_args: usize,
+    pub is_vararg: bool,
+    /// The number of registers used by the function's local variables.
+    pub local_registers: usize,
+    /// The number of registers used for temporary values.
+    pub immediates: usize,
+    /// The name of each local variable, including parameters, and the register
+    /// holding it.
+    pub locals: Vec<(&'chunk LuaString, Register)>,
+    pub defined_at: Option<SourceLocation>,
+}
+
+impl std::fmt::Display for FunctionInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chunk_name = self.chunk_name.unwrap_or("?");
+        match self.defined_at {
+            Some(location) => f.write_fmt(format_args!(
+                "function defined at {}:{}",
+                chunk_name, location.line
+            )),
+            None => f.write_fmt(format_args!("main chunk {}", chunk_name)),
+        }
+    }
+}
+
 pub fn compile(src: &str) -> Result<Chunk, CompileError> {
+    compile_with(src, &mut StringTable::default())
+}
+
+/// Compiles `src` as a chunk called `name`, which is reported alongside
+/// locations in the chunk.
+pub fn compile_named(src: &str, name: &str) -> Result<Chunk, CompileError> {
+    compile_with(src, &mut StringTable::default()).map(|chunk| Chunk {
+        name: Some(name.to_string()),
+        ..chunk
+    })
+}
+
+/// Compiles `src`, interning its strings into `strings` so that they may be
+/// shared with other chunks compiled against the same table.
+///
+/// The returned chunk carries a copy of `strings` as it was at the end of
+/// compilation, and its identifiers & constants are indices into that table.
+/// Strings are never removed from a table, so the chunk remains valid with
+/// any later version of `strings`, but not with any other table.
+pub fn compile_with(src: &str, strings: &mut StringTable) -> Result<Chunk, CompileError> {
+    compile_with_strings_and_options(src, strings, CompilerOptions::default())
+}
+
+/// Compiles `src`, also returning any non-fatal problems found in it. Some
+/// diagnostics may be reported even if compilation fails.
+pub fn compile_with_diagnostics(src: &str) -> (Result<Chunk, CompileError>, Vec<Diagnostic>) {
     let alloc = ASTAllocator::default();
     let mut strings = StringTable::default();
 
-    let ast = parse_chunk(src, &alloc, &mut strings).map_err(CompileError::ParseError)?;
+    match parse_chunk(src, &alloc, &mut strings) {
+        Ok(ast) => Compiler::new(strings, src, CompilerOptions::default()).compile_ast(ast),
+        Err(err) => (Err(CompileError::ParseError(err)), vec![]),
+    }
+}
+
+/// Compiles `src` as described by `options`, rejecting it if it exceeds any of
+/// their limits.
+pub fn compile_with_options(src: &str, options: CompilerOptions) -> Result<Chunk, CompileError> {
+    compile_with_strings_and_options(src, &mut StringTable::default(), options)
+}
+
+fn compile_with_strings_and_options(
+    src: &str,
+    strings: &mut StringTable,
+    options: CompilerOptions,
+) -> Result<Chunk, CompileError> {
+    let alloc = ASTAllocator::default();
+
+    let ast = parse_chunk_with_options(src, &alloc, strings, options.parse_options)
+        .map_err(CompileError::ParseError)?;
+
+    let chunk = Compiler::new(strings.clone(), src, options)
+        .compile_ast(ast)
+        .0?;
+
+    // The compiler may intern strings of its own, e.g. `self` for methods, which
+    // must not be given to other strings by later chunks.
+    strings.clone_from(&chunk.strings);
 
-    Compiler::new(strings).compile_ast(ast)
+    Ok(chunk)
 }
@@ -0,0 +1,154 @@
+use tlua_bytecode::{
+    opcodes::{
+        DuplicateRegister,
+        Instruction,
+        Jump,
+        JumpIf,
+        JumpNil,
+        JumpNot,
+        LoadConstant,
+        LoadRegister,
+        Op,
+        Store,
+    },
+    ImmediateRegister,
+};
+
+use crate::Function;
+
+/// Simplifies the instructions of `function` without changing its behavior,
+/// repeating until no more instructions can be removed.
+///
+/// This removes no-ops, jumps to the instruction which follows them, loads of a
+/// local which was just stored from an immediate, and writes to immediates
+/// which are overwritten by the next instruction.
+pub(crate) fn optimize(function: &mut Function) {
+    loop {
+        let removed = simplify(&mut function.instructions.0);
+        if !removed.iter().any(|&removed| removed) {
+            break;
+        }
+
+        remove_instructions(function, &removed);
+    }
+}
+
+/// Rewrites the instructions in place & flags those which can be removed.
+fn simplify(instructions: &mut [Instruction]) -> Vec<bool> {
+    let mut is_target = vec![false; instructions.len() + 1];
+    for instruction in instructions.iter() {
+        if let Some(target) = jump_target(instruction) {
+            is_target[target] = true;
+        }
+    }
+
+    let mut removed = vec![false; instructions.len()];
+    for idx in 0..instructions.len() {
+        if matches!(instructions[idx], Op::Nop) || jump_target(&instructions[idx]) == Some(idx + 1)
+        {
+            // Conditional jumps only read their condition, so they can be dropped too.
+            removed[idx] = true;
+            continue;
+        }
+
+        let Some(next) = instructions.get(idx + 1).cloned() else {
+            break;
+        };
+
+        match (instructions[idx].clone(), next) {
+            (
+                Op::Store(Store { dst, src }),
+                Op::LoadRegister(LoadRegister {
+                    dst: load,
+                    src: from,
+                }),
+            ) if dst == from && !is_target[idx + 1] => {
+                // The stored value is still available in `src`.
+                if load == src {
+                    removed[idx + 1] = true;
+                } else {
+                    instructions[idx + 1] = DuplicateRegister { dst: load, src }.into();
+                }
+            }
+            (
+                Op::LoadConstant(LoadConstant { dst, .. })
+                | Op::LoadRegister(LoadRegister { dst, .. })
+                | Op::DuplicateRegister(DuplicateRegister { dst, .. }),
+                next,
+            ) if overwrites(&next, dst) => {
+                // Every path through this instruction continues to the next one, so the
+                // value can never be observed, even if the next one is a jump target.
+                removed[idx] = true;
+            }
+            _ => (),
+        }
+    }
+
+    removed
+}
+
+pub(crate) fn jump_target(instruction: &Instruction) -> Option<usize> {
+    match *instruction {
+        Op::Jump(Jump { target })
+        | Op::JumpNot(JumpNot { target, .. })
+        | Op::JumpIf(JumpIf { target, .. })
+        | Op::JumpNil(JumpNil { target, .. }) => Some(target),
+        _ => None,
+    }
+}
+
+fn jump_target_mut(instruction: &mut Instruction) -> Option<&mut usize> {
+    match instruction {
+        Op::Jump(Jump { target })
+        | Op::JumpNot(JumpNot { target, .. })
+        | Op::JumpIf(JumpIf { target, .. })
+        | Op::JumpNil(JumpNil { target, .. }) => Some(target),
+        _ => None,
+    }
+}
+
+/// Checks if `instruction` writes to `reg` without first reading it.
+fn overwrites(instruction: &Instruction, reg: ImmediateRegister) -> bool {
+    match *instruction {
+        Op::LoadConstant(LoadConstant { dst, .. }) | Op::LoadRegister(LoadRegister { dst, .. }) => {
+            dst == reg
+        }
+        Op::DuplicateRegister(DuplicateRegister { dst, src }) => dst == reg && src != reg,
+        _ => false,
+    }
+}
+
+/// Drops the flagged instructions, moving jump targets & statement starts
+/// which referred to a removed instruction to the one after it.
+fn remove_instructions(function: &mut Function, removed: &[bool]) {
+    // The new offset of each instruction, plus one past the end for jumps which
+    // exit the function.
+    let new_offsets = removed
+        .iter()
+        .chain(std::iter::once(&false))
+        .scan(0, |kept, &removed| {
+            let offset = *kept;
+            *kept += usize::from(!removed);
+            Some(offset)
+        })
+        .collect::<Vec<_>>();
+
+    let instructions = std::mem::take(&mut function.instructions.0);
+    function.instructions.0 = instructions
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, &removed)| !removed)
+        .map(|(mut instruction, _)| {
+            if let Some(target) = jump_target_mut(&mut instruction) {
+                *target = new_offsets[*target];
+            }
+            instruction
+        })
+        .collect();
+
+    // Statements whose instructions were all removed now start at the same offset
+    // as the next one, which the line table treats as empty.
+    for (start, _) in function.line_table.iter_mut() {
+        *start = new_offsets[*start];
+    }
+}
@@ -132,6 +132,8 @@ where
             }
             PrefixAtom::Function(atom) => {
                 emit_call(scope, table_reg, atom)?;
+                // Only the first result of a call in the middle of a path is used.
+                table_reg.set_from_ret(scope)?;
             }
         };
     }
@@ -196,6 +198,8 @@ pub(crate) fn emit_standard_call(
             .next()
             .expect("Should still have arg registers");
 
+        // The receiver has already been evaluated into the target register, so it is
+        // copied out as `self` before the method replaces it.
         arg_reg.set_from_immediate(&mut scope, target)?;
         let index_reg = scope.push_immediate();
         index_reg.set_from_constant(&mut scope, Constant::String(method.into()))?;
@@ -206,6 +210,12 @@ pub(crate) fn emit_standard_call(
         scope.emit(opcodes::Lookup::from((target, target, index_reg)));
     }
 
+    if arg_registers.len() == 0 {
+        // Only the receiver of a method call is passed.
+        scope.emit(opcodes::Call::from((target, first_arg_idx, argc)));
+        return Ok(None);
+    }
+
     for _ in 0..arg_registers.len() - 1 {
         let arg_reg = arg_registers.next().expect("Still in bounds of args");
 
@@ -252,6 +262,18 @@ pub(crate) fn emit_standard_call(
     Ok(None)
 }
 
+/// Maps the target of an assignment. Unlike [`map_var`], assigning to a local
+/// doesn't count as reading it.
+pub(crate) fn map_assignment_target(
+    scope: &mut Scope,
+    expr: &VarPrefixExpression,
+) -> Result<Either<MappedLocalRegister, TableIndex>, CompileError> {
+    match expr {
+        VarPrefixExpression::Name(ident) => Ok(Either::Left(scope.write_variable(*ident)?)),
+        expr => map_var(scope, expr),
+    }
+}
+
 pub(crate) fn map_var(
     scope: &mut Scope,
     expr: &VarPrefixExpression,
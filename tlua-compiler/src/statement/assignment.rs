@@ -24,7 +24,7 @@ impl CompileStatement for Assignment<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<Option<OpError>, CompileError> {
         emit_assignments(
             scope,
-            prefix_expression::map_var,
+            prefix_expression::map_assignment_target,
             |scope, dest, init| match dest {
                 Either::Left(var) => {
                     let init = init.into_register(scope);
@@ -52,77 +52,73 @@ pub(crate) fn emit_assignments<VarExpr, VarDest>(
     scope: &mut Scope,
     mut compile_var: impl FnMut(&mut Scope, VarExpr) -> Result<VarDest, CompileError>,
     mut assign_var: impl FnMut(&mut Scope, VarDest, NodeOutput) -> Result<(), CompileError>,
-    mut vars: impl ExactSizeIterator<Item = VarExpr> + Clone,
-    mut inits: impl ExactSizeIterator<Item = impl CompileExpression> + Clone,
+    vars: impl ExactSizeIterator<Item = VarExpr>,
+    inits: impl ExactSizeIterator<Item = impl CompileExpression>,
 ) -> Result<Option<OpError>, CompileError> {
-    let common_length = 0..(vars.len().min(inits.len().saturating_sub(1)));
-    for _ in common_length {
-        let dest = compile_var(scope, vars.next().expect("Still in common length"))?;
+    let var_count = vars.len();
+    let init_count = inits.len();
 
-        let init = inits
-            .next()
-            .expect("Still in common length")
-            .compile(scope)?;
+    // Every value is computed before any variable is assigned, so that e.g. `a, b =
+    // b, a` swaps the two values.
+    let mut values = Vec::with_capacity(var_count);
+    for (idx, init) in inits.enumerate() {
+        let value = init.compile(scope)?;
 
-        assign_var(scope, dest, init)?;
-    }
-
-    if let Some(dest) = vars.next() {
-        let dest = compile_var(scope, dest)?;
-
-        match inits.next() {
-            Some(init) => {
-                let init = init.compile(scope)?;
-
-                match init {
-                    NodeOutput::ReturnValues => {
-                        let consumed_values = vars.len() + 1;
-                        let mut regs = scope.reserve_immediate_range(consumed_values).iter();
-
-                        let first = regs.next().expect("At least one var.");
-                        scope.emit(opcodes::ConsumeRetRange::from((
-                            usize::from(first),
-                            consumed_values,
-                        )));
-
-                        assign_var(scope, dest, NodeOutput::Immediate(first))?;
-                        for (dest, reg) in vars.zip(regs) {
-                            let dest = compile_var(scope, dest)?;
-                            assign_var(scope, dest, NodeOutput::Immediate(reg))?;
-                        }
-                    }
-                    NodeOutput::VAStack => {
-                        let consumed_values = vars.len() + 1;
-                        let mut regs = scope.reserve_immediate_range(consumed_values).iter();
+        if idx >= var_count {
+            // Surplus values are only evaluated for their side effects.
+            match value {
+                NodeOutput::Constant(_)
+                | NodeOutput::ReturnValues
+                | NodeOutput::VAStack
+                | NodeOutput::Err(_) => (),
+                value => {
+                    let reg = value.into_register(scope);
+                    scope.pop_immediate(reg);
+                }
+            }
+            continue;
+        }
 
-                        let first = regs.next().expect("At least one var.");
-                        scope.emit(opcodes::LoadVa::from((
-                            usize::from(first),
-                            0,
-                            consumed_values,
-                        )));
+        match value {
+            // A trailing call or `...` supplies the values for all of the remaining variables.
+            NodeOutput::ReturnValues if idx + 1 == init_count => {
+                let consumed_values = var_count - idx;
+                let regs = scope.reserve_immediate_range(consumed_values);
+                let first = regs.iter().next().expect("At least one var.");
 
-                        assign_var(scope, dest, NodeOutput::Immediate(first))?;
-                        for (dest, reg) in vars.zip(regs) {
-                            let dest = compile_var(scope, dest)?;
-                            assign_var(scope, dest, NodeOutput::Immediate(reg))?;
-                        }
-                    }
-                    init => {
-                        assign_var(scope, dest, init)?;
-                    }
-                }
+                scope.emit(opcodes::ConsumeRetRange::from((
+                    usize::from(first),
+                    consumed_values,
+                )));
+                values.extend(regs.iter().map(NodeOutput::Immediate));
             }
-            None => {
-                assign_var(scope, dest, NodeOutput::Constant(Constant::Nil))?;
+            NodeOutput::VAStack if idx + 1 == init_count => {
+                let consumed_values = var_count - idx;
+                let regs = scope.reserve_immediate_range(consumed_values);
+                let first = regs.iter().next().expect("At least one var.");
+
+                scope.emit(opcodes::LoadVa::from((
+                    usize::from(first),
+                    0,
+                    consumed_values,
+                )));
+                values.extend(regs.iter().map(NodeOutput::Immediate));
             }
+            // Constants can't be changed by the assignments, but anything else might be.
+            value @ NodeOutput::Constant(_) => values.push(value),
+            value => values.push(NodeOutput::Immediate(value.into_register(scope))),
         }
+    }
 
-        debug_assert!(inits.next().is_none());
-    } else {
-        for init in inits {
-            init.compile(scope)?;
-        }
+    values.resize_with(var_count, || NodeOutput::Constant(Constant::Nil));
+
+    let dests = vars
+        .map(|var| compile_var(scope, var))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Like the reference implementation, variables are assigned from right to left.
+    for (dest, value) in dests.into_iter().zip(values).rev() {
+        assign_var(scope, dest, value)?;
     }
 
     Ok(None)
@@ -30,8 +30,7 @@ impl CompileStatement for FnDecl<'_> {
                     },
                     name.method.is_some(),
                     body.params.named_params.iter().copied(),
-                    body.body.statements.iter(),
-                    body.body.ret.as_ref(),
+                    &body.body,
                 )?;
 
                 let func = scope.push_immediate();
@@ -88,7 +87,7 @@ impl CompileStatement for FnDecl<'_> {
                 // This variable will be in scope for all child scopes :(
                 // So we have to allocate a register for it here before compiling the function
                 // body.
-                let register = scope.new_local(*name)?;
+                let register = scope.declare_local(*name)?;
 
                 let fn_id = emit_fn(
                     scope,
@@ -99,8 +98,7 @@ impl CompileStatement for FnDecl<'_> {
                     },
                     false,
                     body.params.named_params.iter().copied(),
-                    body.body.statements.iter(),
-                    body.body.ret.as_ref(),
+                    &body.body,
                 )?;
 
                 // Because this is a local function declaration, we know we're the first write
@@ -10,6 +10,7 @@ use tlua_bytecode::{
 use tlua_parser::statement::for_loop::ForLoop;
 
 use crate::{
+    block::emit_block,
     compiler::{
         JumpTemplate,
         RegisterOps,
@@ -73,63 +74,71 @@ impl CompileStatement for ForLoop<'_> {
         zero.set_from_constant(&mut scope, 0.into())?;
         let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(zero));
 
-        let pending_skip_flip_step = {
-            // Check for a negative step, we always want to be dealing with negative steps
-            // for simplicity.
-            let ge_zero = scope.push_immediate();
-            ge_zero.set_from_immediate(&mut scope, step)?;
-            let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(ge_zero));
-
-            scope.emit(opcodes::GreaterEqual::from((ge_zero, zero)));
-
-            // If the step is negative, skip the extra work to negate it.
-            JumpTemplate::<opcodes::JumpNot>::conditional_at(scope.reserve_jump_isn(), ge_zero)
-        };
+        // Whether the loop counts up, which decides how the loop variable is compared
+        // against the limit.
+        let ascending = scope.push_immediate();
+        ascending.set_from_immediate(&mut scope, step)?;
+        scope.emit(opcodes::GreaterThan::from((ascending, zero)));
+        let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(ascending));
 
         {
             // Check for a zero step to raise an error.
-            let gt_zero = scope.push_immediate();
-            gt_zero.set_from_immediate(&mut scope, step)?;
-            let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(gt_zero));
+            let ne_zero = scope.push_immediate();
+            ne_zero.set_from_immediate(&mut scope, step)?;
+            let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(ne_zero));
 
-            scope.emit(opcodes::GreaterThan::from((gt_zero, zero)));
+            scope.emit(opcodes::NotEqual::from((ne_zero, zero)));
 
-            scope.emit(opcodes::RaiseIfNot::from((
-                gt_zero,
-                OpError::InvalidForStep,
-            )));
+            scope.emit(opcodes::RaiseIfNot::from((ne_zero, OpError::ZeroForStep)));
         }
 
-        // Positive step, flip it and terminating condition so they're always negative.
-        // This is okay because |i64::MIN| >= i64::MAX
-        scope.emit(opcodes::UnaryMinus::from((init, init)));
-        scope.emit(opcodes::UnaryMinus::from((limit, limit)));
-        scope.emit(opcodes::UnaryMinus::from((step, step)));
+        // The condition is checked after the body, so that each iteration only has to
+        // branch on the direction of the loop once.
+        let pending_first_check =
+            JumpTemplate::<opcodes::JumpNot>::unconditional_at(scope.reserve_jump_isn());
 
-        pending_skip_flip_step.resolve_to(scope.next_instruction(), &mut scope);
+        let body_start = scope.next_instruction();
+
+        {
+            // Each iteration has its own copy of the loop variable, so closures created in
+            // the body don't see later iterations.
+            let mut scope = scope.new_block();
+            let mut scope = scope.enter();
+
+            scope
+                .new_local(self.var)?
+                .set_from_immediate(&mut scope, init)?;
+            scope.emit(opcodes::Add::from((init, step)));
+
+            emit_block(&mut scope, &self.body)?;
+        }
 
-        let cond_check_start = scope.next_instruction();
+        pending_first_check.resolve_to(scope.next_instruction(), &mut scope);
 
-        let pending_skip_body = {
+        {
             let cond_outcome = scope.push_immediate();
-            cond_outcome.set_from_immediate(&mut scope, init)?;
             let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(cond_outcome));
 
-            scope.emit(opcodes::GreaterEqual::from((cond_outcome, limit)));
+            let pending_check_descending = JumpTemplate::<opcodes::JumpNot>::conditional_at(
+                scope.reserve_jump_isn(),
+                ascending,
+            );
 
-            JumpTemplate::<opcodes::JumpNot>::conditional_at(scope.reserve_jump_isn(), cond_outcome)
-        };
+            cond_outcome.set_from_immediate(&mut scope, limit)?;
+            scope.emit(opcodes::GreaterEqual::from((cond_outcome, init)));
+            scope.emit(opcodes::JumpIf::from((cond_outcome, body_start)));
 
-        scope
-            .new_local(self.var)?
-            .set_from_immediate(&mut scope, init)?;
-        scope.emit(opcodes::Add::from((init, step)));
+            let pending_exit =
+                JumpTemplate::<opcodes::JumpNot>::unconditional_at(scope.reserve_jump_isn());
 
-        self.body.compile(&mut scope)?;
+            pending_check_descending.resolve_to(scope.next_instruction(), &mut scope);
 
-        scope.emit(opcodes::Jump::from(cond_check_start));
+            cond_outcome.set_from_immediate(&mut scope, init)?;
+            scope.emit(opcodes::GreaterEqual::from((cond_outcome, limit)));
+            scope.emit(opcodes::JumpIf::from((cond_outcome, body_start)));
 
-        pending_skip_body.resolve_to(scope.next_instruction(), &mut scope);
+            pending_exit.resolve_to(scope.next_instruction(), &mut scope);
+        }
 
         scope.label_current_instruction(loop_exit_label)?;
         scope.pop_loop_label();
@@ -1,18 +1,15 @@
-use scopeguard::guard_on_success;
 use tlua_bytecode::{
     opcodes,
     OpError,
 };
-use tlua_parser::{
-    identifiers::Ident,
-    statement::foreach_loop::ForEachLoop,
-};
+use tlua_parser::statement::foreach_loop::ForEachLoop;
 
 use crate::{
     block::emit_block,
     compiler::{
         JumpTemplate,
         RegisterOps,
+        RegisterRange,
     },
     statement::assignment::emit_assignments,
     CompileError,
@@ -27,15 +24,30 @@ impl CompileStatement for ForEachLoop<'_> {
         let mut scope = scope.enter();
         let loop_exit_label = scope.push_loop_label();
 
-        let (loop_start, pending_skip_body) = emit_loop_header(
-            self.vars.iter().copied(),
-            self.expressions.iter(),
-            &mut scope,
-        )?;
+        // The control values are read by every iteration, so their registers must stay
+        // reserved until the end of the loop rather than being reused by the body.
+        let var_inits = scope.reserve_immediate_range(LOOP_ARGS + self.vars.iter().count());
+
+        let (loop_start, pending_skip_body) =
+            emit_loop_header(self.expressions.iter(), &var_inits, &mut scope)?;
+
+        {
+            // Each iteration has its own copies of the loop variables, so closures created
+            // in the body don't see later iterations.
+            let mut scope = scope.new_block();
+            let mut scope = scope.enter();
+
+            // The first named variable is the control variable.
+            let registers = var_inits.iter().skip(LOOP_ARGS - 1);
+            for (var, reg) in self.vars.iter().copied().zip(registers) {
+                scope.new_local(var)?.set_from_immediate(&mut scope, reg)?;
+            }
 
-        emit_block(&mut scope, &self.body)?;
+            emit_block(&mut scope, &self.body)?;
+        }
 
         scope.emit(opcodes::Jump::from(loop_start));
+        scope.pop_immediate_range(var_inits);
 
         pending_skip_body.resolve_to(scope.next_instruction(), &mut scope);
 
@@ -46,15 +58,16 @@ impl CompileStatement for ForEachLoop<'_> {
     }
 }
 
+/// The number of hidden control values: the iterator function, its state, the
+/// control variable and the to-be-closed value.
+const LOOP_ARGS: usize = 4;
+
 fn emit_loop_header(
-    mut vars: impl ExactSizeIterator<Item = Ident>,
     inits: impl ExactSizeIterator<Item = impl CompileExpression> + Clone,
+    var_inits: &RegisterRange,
     scope: &mut Scope,
 ) -> Result<(usize, JumpTemplate<opcodes::JumpNil>), CompileError> {
-    const LOOP_ARGS: usize = 4;
-    let var_inits = scope.reserve_immediate_range(LOOP_ARGS + vars.len());
     let mut var_init_regsiters = var_inits.iter();
-    let mut scope = guard_on_success(scope, |scope| scope.pop_immediate_range(var_inits));
 
     let to_be_closed = var_init_regsiters.next().expect("At least one control var");
     let iter_func = var_init_regsiters.next().expect("At least one control var");
@@ -64,7 +77,7 @@ fn emit_loop_header(
     let control_vars_list = [iter_func, state, control, to_be_closed];
 
     emit_assignments(
-        &mut scope,
+        scope,
         |_scope, var| Ok(var),
         |scope, var, init| {
             init.into_existing_register(scope, var);
@@ -77,17 +90,9 @@ fn emit_loop_header(
     let loop_start = scope.emit(opcodes::Call::from((iter_func, usize::from(state), 2)));
     scope.emit(opcodes::ConsumeRetRange::from((
         usize::from(control),
-        vars.len(),
+        var_init_regsiters.len(),
     )));
 
-    let named_control = vars.next().expect("At least one named variable");
-    scope
-        .new_local(named_control)?
-        .set_from_immediate(&mut scope, control)?;
-    for (var, reg) in vars.zip(var_init_regsiters) {
-        scope.new_local(var)?.set_from_immediate(&mut scope, reg)?;
-    }
-
     Ok((
         loop_start,
         JumpTemplate::<opcodes::JumpNil>::conditional_at(scope.reserve_jump_isn(), control),
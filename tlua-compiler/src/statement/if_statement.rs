@@ -29,10 +29,14 @@ impl CompileStatement for If<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<Option<OpError>, CompileError> {
         let exit_label = scope.create_if_label();
 
-        compile_if_block(scope, exit_label, &self.cond, &self.body)?;
-
-        for ElseIf { cond, body } in self.elif.iter() {
-            compile_if_block(scope, exit_label, cond, body)?;
+        // Every branch but the last jumps to the shared exit label once it is done. The
+        // last branch falls through to it instead.
+        let branches = std::iter::once((&self.cond, &self.body))
+            .chain(self.elif.iter().map(|ElseIf { cond, body }| (cond, body)));
+        let branch_count = 1 + self.elif.len();
+        for (idx, (cond, body)) in branches.enumerate() {
+            let is_last = idx + 1 == branch_count && self.else_final.is_none();
+            compile_if_block(scope, (!is_last).then_some(exit_label), cond, body)?;
         }
 
         if let Some(else_block) = self.else_final.as_ref() {
@@ -45,7 +49,7 @@ impl CompileStatement for If<'_> {
 
 fn compile_if_block(
     scope: &mut Scope,
-    exit_label: LabelId,
+    exit_label: Option<LabelId>,
     cond: &Expression,
     body: &Block,
 ) -> Result<(), CompileError> {
@@ -72,7 +76,9 @@ fn compile_if_block(
 
     body.compile(scope)?;
 
-    scope.emit_jump_label(exit_label);
+    if let Some(exit_label) = exit_label {
+        scope.emit_jump_label(exit_label);
+    }
     if let Some(jump) = jump_template {
         jump.resolve_to(scope.next_instruction(), scope);
     }
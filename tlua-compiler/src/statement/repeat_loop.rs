@@ -8,7 +8,10 @@ use tlua_parser::statement::repeat_loop::RepeatLoop;
 
 use crate::{
     block::emit_block,
-    compiler::unasm::UnasmOp,
+    compiler::{
+        unasm::UnasmOp,
+        JumpTemplate,
+    },
     CompileError,
     CompileExpression,
     CompileStatement,
@@ -18,33 +21,45 @@ use crate::{
 
 impl CompileStatement for RepeatLoop<'_> {
     fn compile(&self, scope: &mut Scope) -> Result<Option<OpError>, CompileError> {
-        let loop_exit_label = scope.push_loop_label();
         let block_start = scope.next_instruction();
 
         let mut scope = scope.new_block();
         let mut scope = scope.enter();
 
+        let loop_exit_label = scope.push_loop_label();
+
         emit_block(&mut scope, &self.body)?;
 
+        // Unlike any other block, the body's locals are still in scope for the
+        // condition.
         let cond = self.terminator.compile(&mut scope)?;
 
-        let jump_op: UnasmOp = match cond {
+        // Every iteration starts a new scope, so the current one is popped before
+        // looping.
+        match cond {
             NodeOutput::Constant(c) => {
                 if c.as_bool() {
                     // Loop immediately terminates, no need to jump
-                    UnasmOp::Nop
+                    scope.emit(UnasmOp::Nop);
                 } else {
                     // Infinite loop, no need to evaluate op
-                    opcodes::Jump::from(block_start).into()
+                    scope.emit(UnasmOp::PopScope);
+                    scope.emit(opcodes::Jump::from(block_start));
                 }
             }
             cond => {
                 let cond_reg = cond.into_register(&mut scope);
-                let _scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(cond_reg));
-                opcodes::JumpNot::from((cond_reg, block_start)).into()
+                let mut scope = guard_on_success(&mut scope, |scope| scope.pop_immediate(cond_reg));
+
+                let pending_exit = JumpTemplate::<opcodes::JumpIf>::conditional_at(
+                    scope.reserve_jump_isn(),
+                    cond_reg,
+                );
+                scope.emit(UnasmOp::PopScope);
+                scope.emit(opcodes::Jump::from(block_start));
+                pending_exit.resolve_to(scope.next_instruction(), &mut scope);
             }
         };
-        scope.emit(jump_op);
 
         scope.label_current_instruction(loop_exit_label)?;
         scope.pop_loop_label();
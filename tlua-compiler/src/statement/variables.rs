@@ -15,7 +15,7 @@ impl CompileStatement for LocalVarList<'_> {
         assignment::emit_assignments(
             scope,
             |scope, var| match var.attribute {
-                None => scope.new_local(var.name),
+                None => scope.declare_local(var.name),
                 Some(_) => todo!(),
             },
             |scope, reg, src| {
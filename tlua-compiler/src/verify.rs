@@ -0,0 +1,223 @@
+use tlua_bytecode::{
+    opcodes::*,
+    ByteCodeError,
+    Constant,
+    ImmediateRegister,
+    MappedRegister,
+    Register,
+    TypeId,
+};
+
+use crate::{
+    peephole::jump_target,
+    BuiltinType,
+    Chunk,
+    Function,
+};
+
+/// Checks that every function in `chunk` can be executed without the VM
+/// encountering malformed instructions.
+pub(crate) fn verify(chunk: &Chunk) -> Result<(), ByteCodeError> {
+    let functions = || std::iter::once(&chunk.main).chain(chunk.functions.iter());
+
+    // Upvalues may be in the scope of any enclosing function or block, which isn't
+    // recorded, so they are only checked against the largest scope in the chunk.
+    let max_scope_size = functions()
+        .map(|function| function.local_registers.max(max_block_size(function)))
+        .max()
+        .unwrap_or_default();
+
+    functions().try_for_each(|function| verify_function(chunk, function, max_scope_size))
+}
+
+fn verify_function(
+    chunk: &Chunk,
+    function: &Function,
+    max_scope_size: usize,
+) -> Result<(), ByteCodeError> {
+    let instructions = function.instructions.as_slice();
+
+    // Jumping to the end of the function returns from it.
+    let mut is_target = vec![false; instructions.len() + 1];
+    for target in instructions.iter().filter_map(jump_target) {
+        *is_target
+            .get_mut(target)
+            .ok_or(ByteCodeError::InvalidJumpTarget)? = true;
+    }
+
+    let block_size = max_block_size(function);
+    let max_block_depth = function.scope_depth
+        + instructions
+            .iter()
+            .filter(|instruction| matches!(instruction, Op::PushScope(_)))
+            .count();
+
+    let check_mapped = |MappedRegister(Register { scope, offset }): MappedRegister<Register>| {
+        let (scope, offset) = (usize::from(scope), usize::from(offset));
+        let size = if scope == 0 {
+            chunk.globals_map.len()
+        } else if scope < function.scope_depth {
+            max_scope_size
+        } else if scope == function.scope_depth {
+            function.local_registers
+        } else if scope <= max_block_depth {
+            block_size
+        } else {
+            0
+        };
+
+        if offset < size {
+            Ok(())
+        } else {
+            Err(ByteCodeError::RegisterOutOfBounds)
+        }
+    };
+
+    for (idx, instruction) in instructions.iter().enumerate() {
+        // Instructions which consume the results of a call are executed as part of
+        // the call, so they can't be reached any other way.
+        let follows_call = idx > 0
+            && !is_target[idx]
+            && matches!(
+                instructions[idx - 1],
+                Op::Call(_) | Op::CallCopyRet(_) | Op::CallCopyVa(_)
+            );
+
+        match *instruction {
+            Op::CopyRetFromRetAndRet | Op::TailCall if !follows_call => {
+                return Err(ByteCodeError::MissingCallInvocation);
+            }
+            Op::CallCopyRet(_) | Op::ConsumeRetRange(_) | Op::SetAllPropertiesFromRet(_)
+                if !follows_call =>
+            {
+                return Err(ByteCodeError::UnexpectedCallInstruction);
+            }
+            Op::LoadRegister(LoadRegister { src, .. }) => check_mapped(src)?,
+            Op::Store(Store { dst, .. }) => check_mapped(dst)?,
+            Op::LoadConstant(LoadConstant {
+                src: Constant::String(s),
+                ..
+            }) if chunk.strings.get_string(s).is_none() => {
+                return Err(ByteCodeError::InvalidStringId);
+            }
+            Op::Alloc(Alloc { type_id, .. }) => match type_id {
+                TypeId::Primitive(_) => return Err(ByteCodeError::InvalidTypeId),
+                type_id => verify_type_id(chunk, type_id)?,
+            },
+            Op::CheckType(CheckType {
+                expected_type_id, ..
+            }) => verify_type_id(chunk, expected_type_id)?,
+            _ => (),
+        }
+
+        let in_bounds = immediate_ranges(instruction)
+            .into_iter()
+            .all(|(start, count)| start.saturating_add(count) <= function.immediates);
+        if !in_bounds {
+            return Err(ByteCodeError::RegisterOutOfBounds);
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_type_id(chunk: &Chunk, type_id: TypeId) -> Result<(), ByteCodeError> {
+    match BuiltinType::try_from(type_id) {
+        Ok(BuiltinType::Function(id)) if usize::from(id) >= chunk.functions.len() => {
+            Err(ByteCodeError::InvalidFunctionId)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The size of the largest block scope pushed by `function`.
+fn max_block_size(function: &Function) -> usize {
+    function
+        .instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Op::PushScope(ScopeDescriptor { size }) => Some(*size),
+            _ => None,
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+/// The ranges of immediate registers used by `instruction`, as a start & a
+/// count.
+pub(crate) fn immediate_ranges(instruction: &Instruction) -> Vec<(usize, usize)> {
+    let single = |reg: ImmediateRegister| (usize::from(reg), 1);
+
+    match *instruction {
+        Op::Add(Add { lhs, rhs })
+        | Op::Subtract(Subtract { lhs, rhs })
+        | Op::Times(Times { lhs, rhs })
+        | Op::Modulo(Modulo { lhs, rhs })
+        | Op::Divide(Divide { lhs, rhs })
+        | Op::Exponetiation(Exponetiation { lhs, rhs })
+        | Op::IDiv(IDiv { lhs, rhs })
+        | Op::BitAnd(BitAnd { lhs, rhs })
+        | Op::BitOr(BitOr { lhs, rhs })
+        | Op::BitXor(BitXor { lhs, rhs })
+        | Op::ShiftLeft(ShiftLeft { lhs, rhs })
+        | Op::ShiftRight(ShiftRight { lhs, rhs })
+        | Op::LessThan(LessThan { lhs, rhs })
+        | Op::LessEqual(LessEqual { lhs, rhs })
+        | Op::GreaterThan(GreaterThan { lhs, rhs })
+        | Op::GreaterEqual(GreaterEqual { lhs, rhs })
+        | Op::Equals(Equals { lhs, rhs })
+        | Op::NotEqual(NotEqual { lhs, rhs })
+        | Op::And(And { lhs, rhs })
+        | Op::Or(Or { lhs, rhs })
+        | Op::Concat(Concat { lhs, rhs }) => vec![single(lhs), single(rhs)],
+        Op::UnaryMinus(UnaryMinus { dst, src })
+        | Op::UnaryBitNot(UnaryBitNot { dst, src })
+        | Op::Not(Not { dst, src })
+        | Op::Length(Length { dst, src })
+        | Op::DuplicateRegister(DuplicateRegister { dst, src })
+        | Op::CheckType(CheckType { dst, src, .. }) => vec![single(dst), single(src)],
+        Op::ConcatN(ConcatN { dst, start, count }) => vec![single(dst), (start, count)],
+        Op::Lookup(Lookup { dst, src, idx }) | Op::SetProperty(SetProperty { dst, idx, src }) => {
+            vec![single(dst), single(src), single(idx)]
+        }
+        Op::RaiseIfNot(RaiseIfNot { src, .. })
+        | Op::Store(Store { src, .. })
+        | Op::SetRet(SetRet { src }) => vec![single(src)],
+        Op::JumpNot(JumpNot { cond, .. })
+        | Op::JumpIf(JumpIf { cond, .. })
+        | Op::JumpNil(JumpNil { cond, .. }) => vec![single(cond)],
+        Op::SetAllPropertiesFromVa(SetAllPropertiesFromVa { dst, .. })
+        | Op::SetAllPropertiesFromRet(SetAllPropertiesFromRet { dst, .. })
+        | Op::LoadConstant(LoadConstant { dst, .. })
+        | Op::LoadRegister(LoadRegister { dst, .. })
+        | Op::Alloc(Alloc { dst, .. }) => vec![single(dst)],
+        Op::LoadVa(LoadVa {
+            dst_start, count, ..
+        })
+        | Op::ConsumeRetRange(ConsumeRetRange { dst_start, count }) => vec![(dst_start, count)],
+        Op::Call(Call {
+            target,
+            mapped_args_start,
+            mapped_args_count,
+        })
+        | Op::CallCopyRet(CallCopyRet {
+            target,
+            mapped_args_start,
+            mapped_args_count,
+        })
+        | Op::CallCopyVa(CallCopyVa {
+            target,
+            mapped_args_start,
+            mapped_args_count,
+        }) => vec![single(target), (mapped_args_start, mapped_args_count)],
+        Op::Nop
+        | Op::Raise(_)
+        | Op::Jump(_)
+        | Op::CopyRetFromVaAndRet
+        | Op::Ret
+        | Op::PushScope(_)
+        | Op::PopScope
+        | Op::CopyRetFromRetAndRet
+        | Op::TailCall => vec![],
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use crate::{
     combinators::parse_list0,
     lexer::Token,
@@ -6,15 +8,28 @@ use crate::{
     ASTAllocator,
     ParseError,
     PeekableLexer,
+    SourceSpan,
 };
 
 pub mod retstat;
 use self::retstat::RetStatement;
 
-#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Default)]
 pub struct Block<'chunk> {
     pub statements: List<'chunk, Statement<'chunk>>,
     pub ret: Option<RetStatement<'chunk>>,
+    /// The location of each of `statements` in the source, followed by the
+    /// location of `ret` if it is present.
+    pub spans: &'chunk [SourceSpan],
+}
+
+// Spans are excluded so that blocks compare equal regardless of their
+// formatting.
+impl PartialEq for Block<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements && self.ret == other.ret
+    }
 }
 
 impl<'chunk> Block<'chunk> {
@@ -22,10 +37,34 @@ impl<'chunk> Block<'chunk> {
         lexer: &mut PeekableLexer,
         alloc: &'chunk ASTAllocator,
     ) -> Result<Self, ParseError> {
-        let statements = parse_list0(lexer, alloc, Statement::try_parse)?;
-        let ret = RetStatement::try_parse(lexer, alloc)?;
+        let spans = RefCell::new(vec![]);
+
+        let statements = parse_list0(lexer, alloc, |lexer, alloc| {
+            let start = lexer.current_span().start;
+            let statement = Statement::try_parse(lexer, alloc)?;
+            if statement.is_some() {
+                spans.borrow_mut().push(SourceSpan {
+                    start,
+                    end: lexer.previous_end,
+                });
+            }
+            Ok(statement)
+        })?;
 
-        Ok(Self { statements, ret })
+        let start = lexer.current_span().start;
+        let ret = RetStatement::try_parse(lexer, alloc)?;
+        if ret.is_some() {
+            spans.borrow_mut().push(SourceSpan {
+                start,
+                end: lexer.previous_end,
+            });
+        }
+
+        Ok(Self {
+            statements,
+            ret,
+            spans: alloc.alloc_slice(&spans.into_inner()),
+        })
     }
 
     pub(crate) fn parse_do(
@@ -78,6 +117,7 @@ mod tests {
             result,
             Block {
                 statements: Default::default(),
+                spans: &[],
                 ret: None
             }
         );
@@ -97,6 +137,7 @@ mod tests {
             result,
             Block {
                 statements: Default::default(),
+                spans: &[],
                 ret: Some(RetStatement {
                     expressions: List::new(&mut ListNode::new(Expression::Number(
                         Number::Integer(10)
@@ -107,4 +148,23 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn records_statement_spans() -> anyhow::Result<()> {
+        let src = "local a = 'x'\n  foo(a) ;\nreturn a";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = final_parser!((src.as_bytes(), &alloc, &mut strings) => Block::parse)?;
+
+        let spans = result
+            .spans
+            .iter()
+            .map(|span| &src[span.start()..span.end()])
+            .collect::<Vec<_>>();
+
+        assert_eq!(spans, vec!["local a = 'x'", "foo(a)", ";", "return a"]);
+
+        Ok(())
+    }
 }
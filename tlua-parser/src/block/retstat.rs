@@ -7,6 +7,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct RetStatement<'chunk> {
     pub expressions: List<'chunk, Expression<'chunk>>,
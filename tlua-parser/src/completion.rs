@@ -0,0 +1,239 @@
+use crate::{
+    expressions::Expression,
+    lexer::Token,
+    prefix_expression::PrefixExpression,
+    tokens::lossless_tokens,
+    ASTAllocator,
+    SourceSpan,
+    SpannedTokenStream,
+    StringTable,
+};
+
+/// The kind of member being accessed by a [`PartialAccess`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    /// A field, e.g. `obj.field`.
+    Field,
+    /// A method, e.g. `obj:method()`.
+    Method,
+}
+
+/// A member access which is still being typed, e.g. `obj.` or `obj:na`, as
+/// found by [`parse_for_completion`].
+#[derive(Debug, PartialEq)]
+pub struct PartialAccess<'chunk> {
+    /// The expression whose member is being accessed.
+    pub receiver: Expression<'chunk>,
+    /// The location of `receiver` in the source.
+    pub receiver_span: SourceSpan,
+    pub kind: AccessKind,
+    /// The part of the member's name which precedes the cursor. This is empty
+    /// if the cursor immediately follows the `.` or `:`.
+    pub name_span: SourceSpan,
+}
+
+/// Finds the member access being typed at byte `offset` of `input`, so that
+/// an editor can offer completions for the member's name.
+///
+/// Only the source before `offset` is considered, and it doesn't need to be
+/// valid. Returns `None` if `offset` doesn't follow a `.` or `:`, optionally
+/// followed by part of a name, or the expression before it can't be parsed.
+///
+/// This is separate from [`parse_chunk`](crate::parse_chunk), which never
+/// accepts incomplete source.
+pub fn parse_for_completion<'chunk>(
+    input: &str,
+    offset: usize,
+    alloc: &'chunk ASTAllocator,
+    strings: &mut StringTable,
+) -> Option<PartialAccess<'chunk>> {
+    let src = input.get(..offset)?;
+
+    // Every significant token before the cursor, along with how deeply it is nested
+    // in brackets.
+    let mut depth = 0usize;
+    let tokens = lossless_tokens(src)
+        .filter(|(_, token, _)| !token.is_whitespace())
+        .map(|(span, token, _)| {
+            if matches!(token, Token::RParen | Token::RBracket | Token::RBrace) {
+                depth = depth.saturating_sub(1);
+            }
+            let nesting = depth;
+            if matches!(token, Token::LParen | Token::LBracket | Token::LBrace) {
+                depth += 1;
+            }
+            (span, token, nesting)
+        })
+        .collect::<Vec<_>>();
+
+    let (tokens, name_span) = match tokens.split_last() {
+        Some((&(span, Token::Ident, _), rest)) if span.end == offset => (rest, span),
+        _ => (
+            &tokens[..],
+            SourceSpan {
+                start: offset,
+                end: offset,
+            },
+        ),
+    };
+
+    let (&(access, token, access_depth), tokens) = tokens.split_last()?;
+    let kind = match token {
+        Token::Period => AccessKind::Field,
+        Token::Colon => AccessKind::Method,
+        _ => return None,
+    };
+
+    // The receiver is the longest prefix expression which ends at the access. It
+    // can only contain the tokens of a prefix expression, ignoring any which
+    // are nested in brackets, and it starts with a name or parenthesis.
+    let run = tokens
+        .iter()
+        .rev()
+        .take_while(|(_, token, depth)| {
+            *depth > access_depth || (*depth == access_depth && in_prefix_expression(*token))
+        })
+        .filter(|(_, token, depth)| {
+            *depth == access_depth && matches!(token, Token::Ident | Token::LParen)
+        })
+        .collect::<Vec<_>>();
+
+    let receiver_src = &src.as_bytes()[..access.start];
+    let receiver = run.into_iter().rev().find_map(|&(start, _, _)| {
+        let mut lexer = SpannedTokenStream::new(receiver_src, strings);
+        lexer.set_source_loc(&receiver_src[start.start..]);
+
+        match PrefixExpression::try_parse(&mut lexer, alloc) {
+            Ok(Some(expr)) if lexer.peek().is_none() => {
+                let span = SourceSpan {
+                    start: start.start,
+                    end: lexer.previous_end,
+                };
+                Some((Expression::from((expr, alloc)), span))
+            }
+            _ => None,
+        }
+    });
+
+    receiver.map(|(receiver, receiver_span)| PartialAccess {
+        receiver,
+        receiver_span,
+        kind,
+        name_span,
+    })
+}
+
+/// Checks if `token` may be part of a prefix expression, outside of any
+/// brackets in it.
+fn in_prefix_expression(token: Token) -> bool {
+    matches!(
+        token,
+        Token::Ident
+            | Token::Period
+            | Token::Colon
+            | Token::LParen
+            | Token::RParen
+            | Token::LBracket
+            | Token::RBracket
+            | Token::LBrace
+            | Token::RBrace
+            | Token::SingleQuotedStringStart
+            | Token::DoubleQuotedStringStart
+            | Token::MultilineStringStart(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        parse_for_completion,
+        AccessKind,
+    };
+    use crate::{
+        expressions::Expression,
+        prefix_expression::{
+            HeadAtom,
+            VarAtom,
+            VarPrefixExpression,
+        },
+        ASTAllocator,
+        StringTable,
+    };
+
+    /// Returns the source of the receiver & the partial name at the end of
+    /// `src`.
+    fn complete(src: &str) -> Option<(&str, AccessKind, &str)> {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        parse_for_completion(src, src.len(), &alloc, &mut strings).map(|access| {
+            (
+                &src[access.receiver_span.start()..access.receiver_span.end()],
+                access.kind,
+                &src[access.name_span.start()..access.name_span.end()],
+            )
+        })
+    }
+
+    #[test]
+    fn completes_fields_and_methods() {
+        assert_eq!(complete("obj."), Some(("obj", AccessKind::Field, "")));
+        assert_eq!(complete("obj.fi"), Some(("obj", AccessKind::Field, "fi")));
+        assert_eq!(complete("obj:"), Some(("obj", AccessKind::Method, "")));
+        assert_eq!(
+            complete("local x = obj.a['b']:m"),
+            Some(("obj.a['b']", AccessKind::Method, "m"))
+        );
+        assert_eq!(
+            complete("f(a, b.c).d(1) . "),
+            Some(("f(a, b.c).d(1)", AccessKind::Field, ""))
+        );
+        assert_eq!(
+            complete("if x then return (t or u). -- done"),
+            Some(("(t or u)", AccessKind::Field, ""))
+        );
+    }
+
+    #[test]
+    fn completes_inside_of_incomplete_source() {
+        assert_eq!(
+            complete("function f()\n  print(a + obj.x.y"),
+            Some(("obj.x", AccessKind::Field, "y"))
+        );
+        assert_eq!(
+            complete("local t = { a = 1, b = x.y:"),
+            Some(("x.y", AccessKind::Method, ""))
+        );
+    }
+
+    #[test]
+    fn ignores_other_positions() {
+        assert_eq!(complete(""), None);
+        assert_eq!(complete("obj"), None);
+        assert_eq!(complete("a .. "), None);
+        assert_eq!(complete("x = 1."), None);
+        assert_eq!(complete("x = 'a'."), None);
+        assert_eq!(complete("x = ."), None);
+    }
+
+    #[test]
+    fn produces_receiver_expression() {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let src = "return a.b.c";
+        let access =
+            parse_for_completion(src, src.len(), &alloc, &mut strings).expect("Found an access");
+
+        let a = strings.lookup_ident("a").unwrap();
+        let b = strings.lookup_ident("b").unwrap();
+        let expected = VarPrefixExpression::TableAccess {
+            head: HeadAtom::Name(a),
+            middle: Default::default(),
+            last: &VarAtom::Name(b),
+        };
+        assert_eq!(access.receiver, Expression::Variable(&expected));
+    }
+}
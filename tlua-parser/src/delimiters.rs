@@ -0,0 +1,210 @@
+use thiserror::Error;
+
+use crate::{
+    lexer::Token,
+    tokens::{
+        lossless_tokens,
+        PublicToken,
+    },
+    SourceSpan,
+};
+
+/// A problem found by [`check_delimiters`].
+#[derive(Debug, Clone, Copy, Error, PartialEq, Eq)]
+pub enum DelimiterError {
+    /// A bracket or block which is never closed. `expected` is the token which
+    /// would close it.
+    #[error("unclosed delimiter - expected '{expected}'")]
+    Unclosed { expected: &'static str },
+    /// A closing bracket or keyword which doesn't close anything.
+    #[error("unexpected '{found}'")]
+    Unexpected { found: &'static str },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opener {
+    Paren,
+    Brace,
+    Bracket,
+    /// A `while` or `for` loop, which is closed by `end` once its `do` is seen.
+    Loop {
+        has_do: bool,
+    },
+    /// Any other keyword which is closed by `end`: `do`, `if` & `function`.
+    Block,
+    Repeat,
+}
+
+impl Opener {
+    fn closed_by(self) -> &'static str {
+        match self {
+            Opener::Paren => ")",
+            Opener::Brace => "}",
+            Opener::Bracket => "]",
+            Opener::Loop { has_do: false } => "do",
+            Opener::Loop { has_do: true } | Opener::Block => "end",
+            Opener::Repeat => "until",
+        }
+    }
+}
+
+/// Checks that the brackets & block keywords in `src` are balanced, without
+/// parsing it. This is much cheaper than [`parse_chunk`](crate::parse_chunk),
+/// and reports every problem rather than only the first, which makes it
+/// suitable for quick feedback in an editor.
+///
+/// Unclosed openers are reported at the span of the opener, and unexpected
+/// closers at their own span. Strings & comments are skipped as a whole, but
+/// the source is otherwise not validated.
+///
+/// When a closer doesn't match the innermost opener but matches one further
+/// out, the openers in between are reported as unclosed and the closer is
+/// matched. Otherwise the closer is reported and ignored.
+pub fn check_delimiters(src: &str) -> Vec<(SourceSpan, DelimiterError)> {
+    let mut openers = Vec::<(SourceSpan, Opener)>::new();
+    let mut errors = vec![];
+
+    let mut close = |openers: &mut Vec<(SourceSpan, Opener)>,
+                     span: SourceSpan,
+                     found: &'static str,
+                     matches: fn(Opener) -> bool| {
+        match openers.iter().rposition(|&(_, opener)| matches(opener)) {
+            Some(idx) => {
+                errors.extend(openers.drain(idx + 1..).map(|(span, opener)| {
+                    (
+                        span,
+                        DelimiterError::Unclosed {
+                            expected: opener.closed_by(),
+                        },
+                    )
+                }));
+                openers.pop();
+            }
+            None => errors.push((span, DelimiterError::Unexpected { found })),
+        }
+    };
+
+    for (span, token, public) in lossless_tokens(src) {
+        if matches!(public, PublicToken::String | PublicToken::Error(_)) {
+            continue;
+        }
+
+        match token {
+            Token::LParen => openers.push((span, Opener::Paren)),
+            Token::LBrace => openers.push((span, Opener::Brace)),
+            Token::LBracket => openers.push((span, Opener::Bracket)),
+            Token::KWwhile | Token::KWfor => openers.push((span, Opener::Loop { has_do: false })),
+            Token::KWif | Token::KWfunction => openers.push((span, Opener::Block)),
+            Token::KWrepeat => openers.push((span, Opener::Repeat)),
+            Token::KWdo => match openers.last_mut() {
+                Some((_, Opener::Loop { has_do })) if !*has_do => *has_do = true,
+                _ => openers.push((span, Opener::Block)),
+            },
+            Token::RParen => close(&mut openers, span, ")", |o| o == Opener::Paren),
+            Token::RBrace => close(&mut openers, span, "}", |o| o == Opener::Brace),
+            Token::RBracket => close(&mut openers, span, "]", |o| o == Opener::Bracket),
+            Token::KWend => close(&mut openers, span, "end", |o| {
+                matches!(o, Opener::Loop { has_do: true } | Opener::Block)
+            }),
+            Token::KWuntil => close(&mut openers, span, "until", |o| o == Opener::Repeat),
+            _ => (),
+        }
+    }
+
+    errors.extend(openers.into_iter().map(|(span, opener)| {
+        (
+            span,
+            DelimiterError::Unclosed {
+                expected: opener.closed_by(),
+            },
+        )
+    }));
+
+    errors.sort_by_key(|(span, _)| span.start());
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        check_delimiters,
+        DelimiterError,
+    };
+
+    fn check(src: &str) -> Vec<(&str, DelimiterError)> {
+        check_delimiters(src)
+            .into_iter()
+            .map(|(span, err)| (&src[span.start()..span.end()], err))
+            .collect()
+    }
+
+    #[test]
+    fn balanced_source() {
+        let src = indoc::indoc! {"
+            local t = { [1] = (2), f = function(...) return ... end }
+            for i = 1, 10 do
+                while i < 5 do i = i + 1 end
+                if i then repeat local x = t[i] until x elseif t then do end else end
+            end
+            local s = '( [ {' .. [[ end ]] -- until )
+            --[==[ function ]==]
+        "};
+
+        assert_eq!(check(src), vec![]);
+    }
+
+    #[test]
+    fn unclosed_openers() {
+        assert_eq!(
+            check("f(a, {b[1]"),
+            vec![
+                ("(", DelimiterError::Unclosed { expected: ")" }),
+                ("{", DelimiterError::Unclosed { expected: "}" }),
+            ]
+        );
+
+        assert_eq!(
+            check("function f() if a then repeat until b"),
+            vec![
+                ("function", DelimiterError::Unclosed { expected: "end" }),
+                ("if", DelimiterError::Unclosed { expected: "end" }),
+            ]
+        );
+
+        assert_eq!(
+            check("while true print(1) end"),
+            vec![
+                ("while", DelimiterError::Unclosed { expected: "do" }),
+                ("end", DelimiterError::Unexpected { found: "end" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn unexpected_closers() {
+        assert_eq!(
+            check("a = 1) end until x ]"),
+            vec![
+                (")", DelimiterError::Unexpected { found: ")" }),
+                ("end", DelimiterError::Unexpected { found: "end" }),
+                ("until", DelimiterError::Unexpected { found: "until" }),
+                ("]", DelimiterError::Unexpected { found: "]" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatched_closers_recover() {
+        // The `end` closes the function, so the paren inside of it is unclosed, but
+        // the brace is unrelated and left alone.
+        assert_eq!(
+            check("f(function() g( end) x = {1]}"),
+            vec![
+                ("(", DelimiterError::Unclosed { expected: ")" }),
+                ("]", DelimiterError::Unexpected { found: "]" }),
+            ]
+        );
+    }
+}
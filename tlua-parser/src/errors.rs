@@ -26,6 +26,8 @@ pub(crate) enum SyntaxError {
     InvalidEscapeSequence,
     #[error("UTF-8 value too large")]
     Utf8ValueTooLarge,
+    #[error("UTF-8 value is not a unicode scalar value")]
+    InvalidUnicodeScalar,
     #[error("Unclosed UTF-8 escape sequence")]
     UnclosedUnicodeEscapeSequence,
     #[error("malformed number")]
@@ -44,7 +46,8 @@ pub(crate) enum SyntaxError {
     ExpectedToken3(Token, Token, Token),
     #[error("Expected a string")]
     ExpectedString,
-    #[allow(unused)]
+    #[error("offset is inside of a token")]
+    OffsetInsideToken,
     #[error("Expected end of file, found: {0:}")]
     ExpectedEOF(Token),
 }
@@ -62,6 +65,11 @@ impl std::fmt::Display for ParseError {
 }
 
 impl ParseError {
+    /// The location in the source where the error was found.
+    pub fn location(&self) -> SourceSpan {
+        self.location
+    }
+
     pub(crate) fn from_here(lexer: &mut PeekableLexer, err: SyntaxError) -> Self {
         Self {
             error: err,
@@ -77,14 +85,37 @@ impl<T> From<ParseError> for Result<T, ParseError> {
 }
 
 #[derive(Debug, Error)]
-#[error("Errors parsing chunk: {error:#}")]
 pub struct ChunkParseError {
     pub error: ParseError,
+    /// The source text at the location of the error, e.g. the unexpected
+    /// token, or `None` if the error is at the end of the input.
+    pub found: Option<String>,
 }
 
-impl From<ParseError> for ChunkParseError {
-    fn from(error: ParseError) -> Self {
-        ChunkParseError { error }
+impl std::fmt::Display for ChunkParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.found {
+            Some(found) => f.write_fmt(format_args!(
+                "Errors parsing chunk: {:#}, found '{}'",
+                self.error, found
+            )),
+            None => f.write_fmt(format_args!(
+                "Errors parsing chunk: {:#}, found end of file",
+                self.error
+            )),
+        }
+    }
+}
+
+impl ChunkParseError {
+    pub(crate) fn new(error: ParseError, src: &[u8]) -> Self {
+        let SourceSpan { start, end } = error.location;
+        let found = src
+            .get(start..end)
+            .filter(|_| start < src.len())
+            .map(|found| String::from_utf8_lossy(found).into_owned());
+
+        ChunkParseError { error, found }
     }
 }
 
@@ -13,6 +13,7 @@ use crate::{
     SyntaxError,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct FnParams<'chunk> {
     /// Note that LUA 5.4 doesn't distinguish multiple variables during function
@@ -54,6 +55,7 @@ impl<'chunk> FnParams<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct FnBody<'chunk> {
     pub params: FnParams<'chunk>,
@@ -72,6 +74,7 @@ impl<'chunk> FnBody<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct FnName<'chunk> {
     pub path: List<'chunk, Ident>,
@@ -225,6 +228,7 @@ mod tests {
                 },
                 body: Block {
                     statements: Default::default(),
+                    spans: &[],
                     ret: Some(RetStatement {
                         expressions: List::new(&mut ListNode::new(Expression::Number(
                             Number::Integer(10)
@@ -21,6 +21,7 @@ use crate::{
     ASTAllocator,
     ParseError,
     PeekableLexer,
+    SourceSpan,
     SyntaxError,
 };
 
@@ -37,15 +38,32 @@ use self::{
         BinaryOperator,
         UnaryOperator,
     },
-    tables::TableConstructor,
+    tables::{
+        Field,
+        TableConstructor,
+    },
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Nil;
 
-#[derive(Debug, PartialEq)]
-pub struct VarArgs;
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct VarArgs {
+    /// The location of the `...` in the source.
+    pub span: SourceSpan,
+}
+
+// The span is excluded so that expressions compare equal regardless of their
+// formatting.
+impl PartialEq for VarArgs {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum Expression<'chunk> {
     Parenthesized(&'chunk Expression<'chunk>),
@@ -73,6 +91,51 @@ impl<'chunk> From<(PrefixExpression<'chunk>, &'chunk ASTAllocator)> for Expressi
 }
 
 impl<'chunk> Expression<'chunk> {
+    /// Checks if evaluating the expression can have no side effects, so that it
+    /// can be removed or reordered.
+    ///
+    /// This is conservative: function calls, indexing & `#` may run a
+    /// metamethod, as may `..` & `~` unless their operands are constants, and
+    /// `...` is treated as impure as well. Other operators are assumed to be
+    /// free of side effects when their operands are.
+    pub fn is_pure(&self) -> bool {
+        match self {
+            Self::Nil(_) | Self::Bool(_) | Self::Number(_) | Self::String(_) | Self::FnDef(_) => {
+                true
+            }
+            Self::Variable(var) => matches!(var, VarPrefixExpression::Name(_)),
+            Self::Parenthesized(expr) => expr.is_pure(),
+            Self::TableConstructor(table) => table.fields.iter().all(|field| match field {
+                Field::Named { expression, .. } | Field::Arraylike { expression } => {
+                    expression.is_pure()
+                }
+                Field::Indexed { index, expression } => index.is_pure() && expression.is_pure(),
+            }),
+            Self::UnaryOp(UnaryOperator::Length(_)) => false,
+            Self::UnaryOp(op @ UnaryOperator::BitNot(_)) => op.operand().is_constant(),
+            Self::UnaryOp(op) => op.operand().is_pure(),
+            Self::BinaryOp(op @ BinaryOperator::Concat(_)) => {
+                let (lhs, rhs) = op.operands();
+                lhs.is_constant() && rhs.is_constant()
+            }
+            Self::BinaryOp(op) => {
+                let (lhs, rhs) = op.operands();
+                lhs.is_pure() && rhs.is_pure()
+            }
+            Self::FunctionCall(_) | Self::VarArgs(_) => false,
+        }
+    }
+
+    /// Checks if the expression is a literal value, which can't have a
+    /// metatable.
+    fn is_constant(&self) -> bool {
+        match self {
+            Self::Nil(_) | Self::Bool(_) | Self::Number(_) | Self::String(_) => true,
+            Self::Parenthesized(expr) => expr.is_constant(),
+            _ => false,
+        }
+    }
+
     pub(crate) fn try_parse(
         lexer: &mut PeekableLexer,
         alloc: &'chunk ASTAllocator,
@@ -152,10 +215,13 @@ impl<'chunk> Expression<'chunk> {
                     LexedNumber::Float(f) => Self::Number(Number::Float(*f)),
                     LexedNumber::Int(i) => Self::Number(Number::Integer(*i)),
                     LexedNumber::MalformedNumber => {
-                        return Err(ParseError::from_here(lexer, SyntaxError::MalformedNumber));
+                        return Err(ParseError {
+                            error: SyntaxError::MalformedNumber,
+                            location: token.span,
+                        });
                     }
                 },
-                LeafToken::Ellipses => Self::VarArgs(VarArgs),
+                LeafToken::Ellipses => Self::VarArgs(VarArgs { span: token.span }),
                 LeafToken::KWfunction => {
                     let body = FnBody::parse(lexer, alloc)?;
                     Self::FnDef(alloc.alloc(body))
@@ -179,9 +245,29 @@ mod tests {
         },
         final_parser,
         ASTAllocator,
+        ParseError,
+        SourceSpan,
         StringTable,
+        SyntaxError,
     };
 
+    #[test]
+    pub fn malformed_number_reports_its_span() {
+        for (src, location) in [("a + 0x", 4..6), ("123abc + 1", 0..6)] {
+            let alloc = ASTAllocator::default();
+            let mut strings = StringTable::default();
+            let result = final_parser!((src.as_bytes(), &alloc, &mut strings) => Expression::parse);
+
+            assert_eq!(
+                result,
+                Err(ParseError {
+                    error: SyntaxError::MalformedNumber,
+                    location: SourceSpan::from(location),
+                })
+            );
+        }
+    }
+
     #[test]
     pub fn parses_varargs() -> anyhow::Result<()> {
         let src = "...";
@@ -189,7 +275,9 @@ mod tests {
         let alloc = ASTAllocator::default();
         let mut strings = StringTable::default();
         let result = final_parser!((src.as_bytes(), &alloc, &mut strings) => Expression::parse)?;
-        assert_eq!(result, Expression::VarArgs(VarArgs));
+        assert!(
+            matches!(result, Expression::VarArgs(VarArgs { span }) if span == SourceSpan::from(0..3))
+        );
 
         Ok(())
     }
@@ -211,6 +299,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn purity() -> anyhow::Result<()> {
+        for (src, pure) in [
+            ("nil", true),
+            ("'a' .. 1.5", true),
+            ("~(1)", true),
+            ("a + b * -c", true),
+            ("(a == b) and not c or d ~ 1", true),
+            ("function() return f() end", true),
+            ("{ 1, x = a, [b] = c }", true),
+            ("f()", false),
+            ("a:m()", false),
+            ("a.b", false),
+            ("a[1] + 2", false),
+            ("#a", false),
+            ("a .. b", false),
+            ("'a' .. b", false),
+            ("~a", false),
+            ("...", false),
+            ("1 + (...)", false),
+            ("{ f() }", false),
+            ("{ [a.b] = 1 }", false),
+        ] {
+            let alloc = ASTAllocator::default();
+            let mut strings = StringTable::default();
+            let result =
+                final_parser!((src.as_bytes(), &alloc, &mut strings) => Expression::parse)?;
+
+            assert_eq!(result.is_pure(), pure, "{src}");
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn sizeof_expr() {
         let left = std::mem::size_of::<Expression>();
@@ -1,5 +1,83 @@
+use logos::Logos;
+
+use crate::lexer::{
+    LexedNumber,
+    Token,
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Number {
     Float(f64),
     Integer(i64),
 }
+
+impl Number {
+    /// Converts a string to a number the way LUA does for arithmetic on
+    /// strings, e.g. `"0x10" * 1`.
+    ///
+    /// The string must hold a single numeral, optionally preceded by a sign
+    /// and surrounded by whitespace. Returns `None` for anything else.
+    pub fn parse(src: &[u8]) -> Option<Self> {
+        let src = src.trim_ascii_start();
+        let (negative, src) = match src.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            Some((b'+', rest)) => (false, rest),
+            _ => (false, src),
+        };
+
+        let mut lexer = Token::lexer(src.trim_ascii_end());
+        let number = match lexer.next()? {
+            Token::HexInt(number)
+            | Token::HexFloat(number)
+            | Token::HexFloatNoPower(number)
+            | Token::Int(number)
+            | Token::Float(number) => number,
+            _ => return None,
+        };
+
+        if lexer.next().is_some() {
+            return None;
+        }
+
+        match number {
+            LexedNumber::Float(f) if negative => Some(Self::Float(-f)),
+            LexedNumber::Float(f) => Some(Self::Float(f)),
+            LexedNumber::Int(i) if negative => Some(Self::Integer(i.wrapping_neg())),
+            LexedNumber::Int(i) => Some(Self::Integer(i)),
+            LexedNumber::MalformedNumber => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::Number;
+
+    #[test]
+    pub fn parses_numeric_strings() {
+        assert_eq!(Number::parse(b"10"), Some(Number::Integer(10)));
+        assert_eq!(Number::parse(b" \t-0x10\n"), Some(Number::Integer(-16)));
+        assert_eq!(Number::parse(b"+3.5"), Some(Number::Float(3.5)));
+        assert_eq!(Number::parse(b"1e2"), Some(Number::Float(100.0)));
+        assert_eq!(Number::parse(b"0x1p4"), Some(Number::Float(16.0)));
+        assert_eq!(Number::parse(b"0x1.8p1"), Some(Number::Float(3.0)));
+        assert_eq!(Number::parse(b"-0xA.8"), Some(Number::Float(-10.5)));
+        assert_eq!(Number::parse(b"+0x10"), Some(Number::Integer(16)));
+        assert_eq!(Number::parse(b"5."), Some(Number::Float(5.0)));
+
+        assert_eq!(Number::parse(b""), None);
+        assert_eq!(Number::parse(b"-"), None);
+        assert_eq!(Number::parse(b"abc"), None);
+        assert_eq!(Number::parse(b"1 2"), None);
+        assert_eq!(Number::parse(b"12abc"), None);
+        assert_eq!(Number::parse(b"- 1"), None);
+        assert_eq!(Number::parse(b"0x"), None);
+        assert_eq!(Number::parse(b"1.5x"), None);
+        assert_eq!(Number::parse(b"0x1p4 z"), None);
+        assert_eq!(Number::parse(b"--1"), None);
+        assert_eq!(Number::parse(b"inf"), None);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    expressions::Expression,
+    expressions::{
+        number::Number,
+        Expression,
+    },
     lexer::{
         SpannedToken,
         Token,
@@ -14,6 +17,7 @@ use crate::{
 #[cfg(test)]
 mod tests;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum UnaryOperator<'chunk> {
     Minus(Negation<'chunk>),
@@ -22,6 +26,7 @@ pub enum UnaryOperator<'chunk> {
     BitNot(BitNot<'chunk>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum BinaryOperator<'chunk> {
     Plus(Plus<'chunk>),
@@ -47,6 +52,48 @@ pub enum BinaryOperator<'chunk> {
     Or(Or<'chunk>),
 }
 
+impl<'chunk> UnaryOperator<'chunk> {
+    /// The expression the operator is applied to.
+    pub fn operand(&self) -> &'chunk Expression<'chunk> {
+        match *self {
+            Self::Minus(Negation(operand))
+            | Self::Not(Not(operand))
+            | Self::Length(Length(operand))
+            | Self::BitNot(BitNot(operand)) => operand,
+        }
+    }
+}
+
+impl<'chunk> BinaryOperator<'chunk> {
+    /// The left & right hand sides of the operator.
+    pub fn operands(&self) -> (&'chunk Expression<'chunk>, &'chunk Expression<'chunk>) {
+        match *self {
+            Self::Plus(Plus { lhs, rhs })
+            | Self::Minus(Minus { lhs, rhs })
+            | Self::Times(Times { lhs, rhs })
+            | Self::Divide(Divide { lhs, rhs })
+            | Self::IDiv(IDiv { lhs, rhs })
+            | Self::Modulo(Modulo { lhs, rhs })
+            | Self::Exponetiation(Exponetiation { lhs, rhs })
+            | Self::BitAnd(BitAnd { lhs, rhs })
+            | Self::BitOr(BitOr { lhs, rhs })
+            | Self::BitXor(BitXor { lhs, rhs })
+            | Self::ShiftLeft(ShiftLeft { lhs, rhs })
+            | Self::ShiftRight(ShiftRight { lhs, rhs })
+            | Self::Concat(Concat { lhs, rhs })
+            | Self::LessThan(LessThan { lhs, rhs })
+            | Self::LessEqual(LessEqual { lhs, rhs })
+            | Self::GreaterThan(GreaterThan { lhs, rhs })
+            | Self::GreaterEqual(GreaterEqual { lhs, rhs })
+            | Self::Equals(Equals { lhs, rhs })
+            | Self::NotEqual(NotEqual { lhs, rhs })
+            | Self::And(And { lhs, rhs })
+            | Self::Or(Or { lhs, rhs }) => (lhs, rhs),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Exponetiation<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -78,15 +125,19 @@ impl<'chunk> Exponetiation<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Negation<'chunk>(pub &'chunk Expression<'chunk>);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Not<'chunk>(pub &'chunk Expression<'chunk>);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Length<'chunk>(pub &'chunk Expression<'chunk>);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct BitNot<'chunk>(pub &'chunk Expression<'chunk>);
 
@@ -110,8 +161,20 @@ pub(crate) fn parse_unary<'chunk>(
         return Exponetiation::parse(lexer, alloc);
     };
 
+    // `9223372036854775808` doesn't fit in an integer and lexes as a float, but
+    // its negation is the smallest integer.
+    let negates_min_integer = matches!(token.as_ref(), UnaryToken::Minus)
+        && lexer.peek().is_some_and(|next| {
+            matches!(next.token, Token::Int(_)) && is_min_integer_magnitude(next.src)
+        });
+
     Ok(
         Exponetiation::parse(lexer, alloc)?.map(|expr| match token.as_ref() {
+            UnaryToken::Minus if negates_min_integer && matches!(expr, Expression::Number(_)) => {
+                Expression::UnaryOp(UnaryOperator::Minus(Negation(
+                    alloc.alloc(Expression::Number(Number::Integer(i64::MIN))),
+                )))
+            }
             UnaryToken::KWnot => Expression::UnaryOp(UnaryOperator::Not(Not(alloc.alloc(expr)))),
             UnaryToken::Hashtag => {
                 Expression::UnaryOp(UnaryOperator::Length(Length(alloc.alloc(expr))))
@@ -126,24 +189,36 @@ pub(crate) fn parse_unary<'chunk>(
     )
 }
 
+/// Checks if a decimal integer literal is `2^63`, allowing for leading zeros.
+fn is_min_integer_magnitude(src: &[u8]) -> bool {
+    std::str::from_utf8(src)
+        .ok()
+        .and_then(|digits| digits.parse::<u64>().ok())
+        == Some(i64::MIN.unsigned_abs())
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Times<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Divide<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct IDiv<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Modulo<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -174,12 +249,14 @@ pub(crate) fn parse_muldivmod<'chunk>(
     )
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Plus<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Minus<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -203,6 +280,7 @@ pub(crate) fn parse_addsub<'chunk>(
     )
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Concat<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -225,12 +303,14 @@ impl<'chunk> Concat<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct ShiftLeft<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct ShiftRight<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -263,6 +343,7 @@ pub(crate) fn parse_shift<'chunk>(
     )
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct BitAnd<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -284,6 +365,7 @@ impl<'chunk> BitAnd<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct BitXor<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -305,6 +387,7 @@ impl<'chunk> BitXor<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct BitOr<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -326,36 +409,42 @@ impl<'chunk> BitOr<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct LessThan<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct LessEqual<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct GreaterThan<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct GreaterEqual<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Equals<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
     pub rhs: &'chunk Expression<'chunk>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct NotEqual<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -405,6 +494,7 @@ pub(crate) fn parse_logical<'chunk>(
     )
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct And<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
@@ -426,6 +516,7 @@ impl<'chunk> And<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Or<'chunk> {
     pub lhs: &'chunk Expression<'chunk>,
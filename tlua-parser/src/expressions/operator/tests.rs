@@ -103,6 +103,34 @@ fn parses_unary_minus() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn parses_negated_min_integer() -> anyhow::Result<()> {
+    let src = "-9223372036854775808";
+
+    let alloc = ASTAllocator::default();
+    let mut strings = StringTable::default();
+    let expr = final_parser!((src.as_bytes(), &alloc, &mut strings) => Expression::parse)?;
+
+    assert_eq!(
+        expr,
+        (Expression::UnaryOp(UnaryOperator::Minus(Negation(&Expression::Number(
+            Number::Integer(i64::MIN)
+        )))))
+    );
+
+    let src = "-9223372036854775809";
+    let expr = final_parser!((src.as_bytes(), &alloc, &mut strings) => Expression::parse)?;
+
+    assert_eq!(
+        expr,
+        (Expression::UnaryOp(UnaryOperator::Minus(Negation(&Expression::Number(
+            Number::Float(9223372036854775809f64)
+        )))))
+    );
+
+    Ok(())
+}
+
 #[test]
 fn parses_unary_bitnot() -> anyhow::Result<()> {
     let src = "~1";
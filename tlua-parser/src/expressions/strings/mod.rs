@@ -13,8 +13,11 @@ use crate::{
     token_subset,
     ASTAllocator,
     ParseError,
+    ParseOptions,
     PeekableLexer,
     SyntaxError,
+    UnicodeEscapes,
+    UnknownEscapes,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -54,12 +57,13 @@ impl ConstantString {
         let remain = lexer.remainder();
         let mut string_lexer = Lexer::<StringToken>::new(remain);
 
+        let options = lexer.options;
         let string = match token.as_ref() {
             StringStart::SingleQuotedStringStart => {
-                internal_parse(&mut string_lexer, Delim::SingleQuote)
+                internal_parse(&mut string_lexer, Delim::SingleQuote, options)
             }
             StringStart::DoubleQuotedStringStart => {
-                internal_parse(&mut string_lexer, Delim::DoubleQuote)
+                internal_parse(&mut string_lexer, Delim::DoubleQuote, options)
             }
         }
         .map_err(|ParseError { error, location }| ParseError {
@@ -137,6 +141,7 @@ enum StringToken {
 fn internal_parse(
     string_lexer: &mut Lexer<StringToken>,
     delim: Delim,
+    options: ParseOptions,
 ) -> Result<BString, ParseError> {
     let mut string = BString::default();
 
@@ -164,7 +169,7 @@ fn internal_parse(
                 string.extend_from_slice(string_lexer.slice());
             }
             StringToken::HexLiteral => {
-                if let [hex1, hex2] = string_lexer.slice()[..] {
+                if let [hex1, hex2] = string_lexer.slice()[2..] {
                     let high = char::from(hex1).to_digit(16).expect("Is ascii hex digit") as u8;
                     let low = char::from(hex2).to_digit(16).expect("Is ascii hex digit") as u8;
 
@@ -211,13 +216,17 @@ fn internal_parse(
             }
             StringToken::UnicodeEscape => {
                 let seq = string_lexer.slice();
-                match encode_utf8_raw(&seq[3..seq.len() - 1]) {
+                let encoded = match options.unicode_escapes {
+                    UnicodeEscapes::Lenient => encode_utf8_raw(&seq[3..seq.len() - 1]),
+                    UnicodeEscapes::Strict => encode_utf8_strict(&seq[3..seq.len() - 1]),
+                };
+                match encoded {
                     Ok((len, bytes)) => {
                         string.extend_from_slice(&bytes[..len]);
                     }
-                    Err(_) => {
+                    Err(error) => {
                         return Err(ParseError {
-                            error: SyntaxError::Utf8ValueTooLarge,
+                            error,
                             location: string_lexer.span().into(),
                         });
                     }
@@ -226,12 +235,17 @@ fn internal_parse(
             StringToken::LineContinuation => {
                 string.push(b'\n');
             }
-            StringToken::UnknownEscapeSequence => {
-                return Err(ParseError {
-                    error: SyntaxError::InvalidEscapeSequence,
-                    location: string_lexer.span().into(),
-                });
-            }
+            StringToken::UnknownEscapeSequence => match options.unknown_escapes {
+                UnknownEscapes::Error => {
+                    return Err(ParseError {
+                        error: SyntaxError::InvalidEscapeSequence,
+                        location: string_lexer.span().into(),
+                    });
+                }
+                UnknownEscapes::PassThrough => {
+                    string.extend_from_slice(string_lexer.slice());
+                }
+            },
             StringToken::EscapeSeqBell => {
                 string.push(b'\x07');
             }
@@ -266,114 +280,69 @@ fn internal_parse(
         }
     }
 
+    // The input ended before the closing delimiter.
+    let end = string_lexer.span().end;
     Err(ParseError {
         error: SyntaxError::UnclosedString,
-        location: string_lexer.span().into(),
+        location: (end..end).into(),
     })
 }
 
-/// Encodes a a 4-byte sequence of hex characters into a (potentially invalid -
-/// per spec) utf8 byte sequence.
-fn encode_utf8_raw(span: &[u8]) -> Result<(usize, [u8; 6]), ()> {
-    let (val, _) = u32::from_radix_16_checked(span);
-    let val = val.ok_or(())?;
-
-    #[rustfmt::skip]
-    mod tag {
-    pub const CONT: u8    = 0b10000000;
-    pub const TWO_B: u8   = 0b11000000;
-    pub const THREE_B: u8 = 0b11100000;
-    pub const FOUR_B: u8  = 0b11110000;
-    pub const FIVE_B: u8  = 0b11111000;
-    pub const SIX_B: u8   = 0b11111100;
-    }
-
-    #[rustfmt::skip]
-    mod mask {
-    pub const CONT: u32    = 0b00111111;
-    pub const TWO_B: u32   = 0b00011111;
-    pub const THREE_B: u32 = 0b00001111;
-    pub const FOUR_B: u32  = 0b00000111;
-    pub const FIVE_B: u32  = 0b00000011;
-    pub const SIX_B: u32   = 0b00000001;
-    }
-
-    // These groupings are based on the layout of utf8 encoding, not bytes.
-    #[allow(clippy::unusual_byte_groupings)]
-    #[rustfmt::skip]
-    mod max {
-    pub const ONE_B: u32   = 0b10000000;
-    pub const TWO_B: u32   = 0b00100000__000000;
-    pub const THREE_B: u32 = 0b00010000__000000__000000;
-    pub const FOUR_B: u32  = 0b00001000__000000__000000__000000;
-    pub const FIVE_B: u32  = 0b00000100__000000__000000__000000__000000;
-    pub const SIX_B: u32   = 0b00000010__000000__000000__000000__000000__000000;
-    }
-
-    let len = if val < max::ONE_B {
-        1
-    } else if val < max::TWO_B {
-        2
-    } else if val < max::THREE_B {
-        3
-    } else if val < max::FOUR_B {
-        4
-    } else if val < max::FIVE_B {
-        5
-    } else if val < max::SIX_B {
-        6
-    } else {
-        return Err(());
+/// Finds the end of the string opened by `start` in `remain` without
+/// interpreting its contents.
+///
+/// Returns the length of the string body including its closing delimiter, or
+/// the length of the unterminated portion if the string is never closed.
+pub(crate) fn scan_string(start: Token, remain: &[u8]) -> Result<usize, usize> {
+    let delim = match start {
+        Token::SingleQuotedStringStart => Delim::SingleQuote,
+        Token::DoubleQuotedStringStart => Delim::DoubleQuote,
+        Token::MultilineStringStart(tag_len) => {
+            return multiline_strings::scan_string(remain, tag_len);
+        }
+        _ => return Err(0),
     };
 
-    let bytes = match len {
-        1 => [val as u8, 0, 0, 0, 0, 0],
-        2 => [
-            (val >> 6 & mask::TWO_B) as u8 | tag::TWO_B,
-            (val & mask::CONT) as u8 | tag::CONT,
-            0,
-            0,
-            0,
-            0,
-        ],
-        3 => [
-            (val >> 12 & mask::THREE_B) as u8 | tag::THREE_B,
-            (val >> 6 & mask::CONT) as u8 | tag::CONT,
-            (val & mask::CONT) as u8 | tag::CONT,
-            0,
-            0,
-            0,
-        ],
-        4 => [
-            (val >> 18 & mask::FOUR_B) as u8 | tag::FOUR_B,
-            (val >> 12 & mask::CONT) as u8 | tag::CONT,
-            (val >> 6 & mask::CONT) as u8 | tag::CONT,
-            (val & mask::CONT) as u8 | tag::CONT,
-            0,
-            0,
-        ],
-        5 => [
-            (val >> 24 & mask::FIVE_B) as u8 | tag::FIVE_B,
-            (val >> 18 & mask::CONT) as u8 | tag::CONT,
-            (val >> 12 & mask::CONT) as u8 | tag::CONT,
-            (val >> 6 & mask::CONT) as u8 | tag::CONT,
-            (val & mask::CONT) as u8 | tag::CONT,
-            0,
-        ],
-        6 => [
-            (val >> 30 & mask::SIX_B) as u8 | tag::SIX_B,
-            (val >> 24 & mask::CONT) as u8 | tag::CONT,
-            (val >> 18 & mask::CONT) as u8 | tag::CONT,
-            (val >> 12 & mask::CONT) as u8 | tag::CONT,
-            (val >> 6 & mask::CONT) as u8 | tag::CONT,
-            (val & mask::CONT) as u8 | tag::CONT,
-        ],
-        _ => unreachable!(),
-    };
+    let mut string_lexer = Lexer::<StringToken>::new(remain);
+    while let Some(token) = string_lexer.next() {
+        match (token, &delim) {
+            (StringToken::SingleQuote, Delim::SingleQuote)
+            | (StringToken::DoubleQuote, Delim::DoubleQuote) => {
+                return Ok(string_lexer.span().end);
+            }
+            (StringToken::Error | StringToken::EndOfLine, _) => {
+                return Err(string_lexer.span().start);
+            }
+            _ => (),
+        }
+    }
+
+    Err(remain.len())
+}
+
+/// Encodes a sequence of hex characters into a utf8 byte sequence, rejecting
+/// values which aren't unicode scalar values.
+fn encode_utf8_strict(span: &[u8]) -> Result<(usize, [u8; 6]), SyntaxError> {
+    let (val, _) = u32::from_radix_16_checked(span);
+    let val = val
+        .filter(|&val| val <= 0x7FFFFFFF)
+        .ok_or(SyntaxError::Utf8ValueTooLarge)?;
 
+    let c = char::from_u32(val).ok_or(SyntaxError::InvalidUnicodeScalar)?;
+
+    let mut bytes = [0; 6];
+    let len = c.encode_utf8(&mut bytes).len();
     Ok((len, bytes))
 }
 
+/// Encodes a a 4-byte sequence of hex characters into a (potentially invalid -
+/// per spec) utf8 byte sequence.
+fn encode_utf8_raw(span: &[u8]) -> Result<(usize, [u8; 6]), SyntaxError> {
+    let (val, _) = u32::from_radix_16_checked(span);
+    val.and_then(tlua_strings::encode_utf8_raw)
+        .ok_or(SyntaxError::Utf8ValueTooLarge)
+}
+
 #[cfg(test)]
 mod tests {
     use bstr::ByteSlice;
@@ -382,8 +351,16 @@ mod tests {
     use crate::{
         expressions::strings::ConstantString,
         final_parser,
+        parse_chunk_with_options,
         ASTAllocator,
+        ChunkParseError,
+        ParseError,
+        ParseOptions,
+        SourceSpan,
         StringTable,
+        SyntaxError,
+        UnicodeEscapes,
+        UnknownEscapes,
     };
 
     #[test]
@@ -711,6 +688,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn escape_hex() -> anyhow::Result<()> {
+        let src = r#""\x41\xff""#;
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => ConstantString::try_parse)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some([b'A', 0xff].as_bstr())
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn escape_unicode() -> anyhow::Result<()> {
         let src = r#""\u{2764}""#;
@@ -818,4 +813,149 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn unknown_escape_error() {
+        let src = r#"return "\q""#;
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = parse_chunk_with_options(src, &alloc, &mut strings, ParseOptions::default());
+
+        assert!(matches!(
+            result,
+            Err(ChunkParseError {
+                error: ParseError {
+                    error: SyntaxError::InvalidEscapeSequence,
+                    ..
+                },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn unknown_escape_pass_through() -> anyhow::Result<()> {
+        let src = r#"return "a\qb""#;
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        parse_chunk_with_options(
+            src,
+            &alloc,
+            &mut strings,
+            ParseOptions {
+                unknown_escapes: UnknownEscapes::PassThrough,
+                ..ParseOptions::default()
+            },
+        )?;
+
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(br"a\qb".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    fn parse_strict(src: &str, strings: &mut StringTable) -> Result<(), ChunkParseError> {
+        let alloc = ASTAllocator::default();
+        parse_chunk_with_options(
+            src,
+            &alloc,
+            strings,
+            ParseOptions {
+                unicode_escapes: UnicodeEscapes::Strict,
+                ..ParseOptions::default()
+            },
+        )
+        .map(|_| ())
+    }
+
+    #[test]
+    fn escape_unicode_strict() -> anyhow::Result<()> {
+        let mut strings = StringTable::default();
+        parse_strict(r#"return "\u{2764}\u{10FFFF}""#, &mut strings)?;
+
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some("\u{2764}\u{10FFFF}".as_bytes().as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn escape_unicode_strict_rejects_invalid() {
+        for src in [
+            r#"return "\u{D800}""#,
+            r#"return "\u{DFFF}""#,
+            r#"return "\u{110000}""#,
+            r#"return "\u{7FFFFFFF}""#,
+        ] {
+            let mut strings = StringTable::default();
+            let result = parse_strict(src, &mut strings);
+
+            assert!(
+                matches!(
+                    result,
+                    Err(ChunkParseError {
+                        error: ParseError {
+                            error: SyntaxError::InvalidUnicodeScalar,
+                            ..
+                        },
+                        ..
+                    })
+                ),
+                "{src} was accepted"
+            );
+        }
+
+        let mut strings = StringTable::default();
+        assert!(matches!(
+            parse_strict(r#"return "\u{80000000}""#, &mut strings),
+            Err(ChunkParseError {
+                error: ParseError {
+                    error: SyntaxError::Utf8ValueTooLarge,
+                    ..
+                },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn escape_error_locations() {
+        for (src, expected_error, expected_location) in [
+            (
+                r#"return "ab\256""#,
+                SyntaxError::DecimalEscapeTooLarge,
+                10..14,
+            ),
+            (
+                r#"return "\u{80000000}""#,
+                SyntaxError::Utf8ValueTooLarge,
+                8..20,
+            ),
+            ("return 'a\nb'", SyntaxError::UnclosedString, 9..10),
+            (r#"return "abc"#, SyntaxError::UnclosedString, 11..11),
+            ("return [==[abc", SyntaxError::UnclosedString, 7..11),
+        ] {
+            let alloc = ASTAllocator::default();
+            let mut strings = StringTable::default();
+            let result =
+                parse_chunk_with_options(src, &alloc, &mut strings, ParseOptions::default());
+
+            match result {
+                Err(ChunkParseError {
+                    error: ParseError { error, location },
+                    ..
+                }) => {
+                    assert_eq!(error, expected_error, "{src}");
+                    assert_eq!(location, SourceSpan::from(expected_location), "{src}");
+                }
+                Ok(_) => panic!("{src} was accepted"),
+            }
+        }
+    }
 }
@@ -48,10 +48,12 @@ pub(crate) fn parse_string(
     let remain = lexer.remainder();
     let mut string_lexer = Lexer::<StringToken>::new(remain);
 
+    // Like reference Lua, an unclosed string is reported where it starts, as its
+    // end could be anywhere in the rest of the input.
     let string = internal_parse(&mut string_lexer, end_tag_len).map_err(
-        |ParseError { error, location }| ParseError {
+        |ParseError { error, .. }| ParseError {
             error,
-            location: location.translate(base_span),
+            location: base_span,
         },
     )?;
 
@@ -59,6 +61,14 @@ pub(crate) fn parse_string(
     Ok(Some(lexer.strings.add_string(string)))
 }
 
+pub(super) fn scan_string(remain: &[u8], end_tag_len: usize) -> Result<usize, usize> {
+    let mut string_lexer = Lexer::<StringToken>::new(remain);
+
+    internal_parse(&mut string_lexer, end_tag_len)
+        .map(|_| remain.len() - string_lexer.remainder().len())
+        .map_err(|_| remain.len())
+}
+
 fn internal_parse(
     string_lexer: &mut Lexer<StringToken>,
     end_tag_len: usize,
@@ -96,9 +106,11 @@ fn internal_parse(
         first_line = false;
     }
 
+    // The input ended before the closing delimiter.
+    let end = string_lexer.span().end;
     Err(ParseError {
         error: SyntaxError::UnclosedString,
-        location: string_lexer.span().into(),
+        location: (end..end).into(),
     })
 }
 
@@ -109,8 +121,12 @@ mod tests {
     use crate::{
         expressions::strings::ConstantString,
         final_parser,
+        lexer::Token,
         ASTAllocator,
+        ParseError,
+        SpannedTokenStream,
         StringTable,
+        SyntaxError,
     };
 
     #[test]
@@ -202,4 +218,139 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn raw_string_extended_empty() -> anyhow::Result<()> {
+        let src = "[==[]==]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => ConstantString::try_parse)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b"".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn raw_string_trailing_bracket() -> anyhow::Result<()> {
+        let src = "[=[a]]=]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => ConstantString::try_parse)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b"a]".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn raw_string_closes_at_first_closer() -> anyhow::Result<()> {
+        // The first `]]` closes the string, so the last `]` isn't part of it.
+        let src = "[[]]]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let mut lexer = SpannedTokenStream::new(src.as_bytes(), &mut strings);
+        let result = ConstantString::try_parse(&mut lexer, &alloc)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(lexer.next().map(|token| token.token), Some(Token::RBracket));
+        assert_eq!(lexer.next().map(|token| token.token), None);
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b"".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn raw_string_leading_newline_dropped() -> anyhow::Result<()> {
+        let src = "[[\nhello]]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => ConstantString::try_parse)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b"hello".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn raw_string_ignores_other_levels() -> anyhow::Result<()> {
+        let src = "[==[ a ]=] b ]===] ]==]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => ConstantString::try_parse)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b" a ]=] b ]===] ".as_bstr())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn raw_string_unclosed() {
+        for (src, opening) in [("[[abc", 0..2), ("[==[ a ]=]\n", 0..4)] {
+            let alloc = ASTAllocator::default();
+            let mut strings = StringTable::default();
+            let mut lexer = SpannedTokenStream::new(src.as_bytes(), &mut strings);
+            let result = ConstantString::try_parse(&mut lexer, &alloc);
+
+            assert_eq!(
+                result,
+                Err(ParseError {
+                    error: SyntaxError::UnclosedString,
+                    location: opening.into(),
+                }),
+                "{src}"
+            );
+        }
+    }
+
+    #[test]
+    pub fn raw_string_followed_by_token() -> anyhow::Result<()> {
+        let src = "[[a]]b";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let mut lexer = SpannedTokenStream::new(src.as_bytes(), &mut strings);
+        let result = ConstantString::try_parse(&mut lexer, &alloc)?;
+
+        assert_eq!(result, Some(ConstantString(0)));
+        assert_eq!(
+            lexer.next().map(|token| (token.token, token.src)),
+            Some((Token::Ident, b"b".as_slice()))
+        );
+        assert_eq!(lexer.next().map(|token| token.token), None);
+        assert_eq!(
+            strings.strings.get_index(0).map(|s| s.as_bstr()),
+            Some(b"a".as_bstr())
+        );
+
+        Ok(())
+    }
 }
@@ -27,6 +27,7 @@ use crate::{
 /// {[1] = 13, 10, 11}
 /// ```
 /// Your final table will always contain `{10, 11}` as of Lua 5.4
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum Field<'chunk> {
     /// `{ 'Name' ='Exp' }`
@@ -93,6 +94,7 @@ impl<'chunk> Field<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct TableConstructor<'chunk> {
     pub fields: List<'chunk, Field<'chunk>>,
@@ -0,0 +1,124 @@
+//! A small fuzzing harness for the parser. Inputs are random bytes & random
+//! mutations of valid lua, and the only property checked is that parsing
+//! returns instead of panicking.
+
+use std::panic::{
+    catch_unwind,
+    AssertUnwindSafe,
+};
+
+use crate::{
+    parse_chunk,
+    ASTAllocator,
+    StringTable,
+};
+
+const SEEDS: &[&str] = &[
+    "local a, b = 1, 2 return a + b * 3",
+    "for i = 1, 10, 2 do print(i) end",
+    "while x < 0x1p4 do x = x + 1.5e2 end",
+    "local t = { 1, 2, x = 'a\\u{7FFFFFFF}', [3] = \"\\x41\\65\\z  b\" }",
+    "return [==[long\nstring]==] .. [[x]]",
+    "function t.a.b:c(...) return ... end",
+    "if a then goto l elseif b then return else ::l:: end",
+    "repeat local x = #t until ~x // 2 >= 3 ~= 4",
+    "-- comment\n--[[ long\ncomment ]] return 0x7FFFFFFFFFFFFFFF, 9223372036854775808",
+    "return 'é', '\\xFF', 0xA.8p0, .5, 3.",
+];
+
+/// A fuzz target in the style of `cargo fuzz`: any input is accepted, and
+/// finding a panic is a failure.
+fn fuzz_target(data: &[u8]) {
+    let alloc = ASTAllocator::default();
+    let mut strings = StringTable::default();
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = parse_chunk(data, &alloc, &mut strings);
+    }));
+
+    if result.is_err() {
+        panic!("Parsing panicked on input: {:?}", bstr::BString::from(data));
+    }
+}
+
+/// A xorshift generator, so that failures are reproducible without pulling in
+/// a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn byte(&mut self) -> u8 {
+        // Favor bytes which are meaningful to the lexer so that inputs get past the
+        // first token more often.
+        const INTERESTING: &[u8] = b"\\'\"[]=-.0xXpPeE9{}()\n\xC3\xA9\xFF\x80";
+        if self.next() & 1 == 0 {
+            INTERESTING[self.below(INTERESTING.len())]
+        } else {
+            self.next() as u8
+        }
+    }
+}
+
+#[test]
+pub fn arbitrary_bytes_never_panic() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+
+    for _ in 0..5000 {
+        let len = rng.below(64);
+        let data = (0..len).map(|_| rng.byte()).collect::<Vec<_>>();
+        fuzz_target(&data);
+    }
+}
+
+#[test]
+pub fn mutated_sources_never_panic() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+    for _ in 0..5000 {
+        let mut data = SEEDS[rng.below(SEEDS.len())].as_bytes().to_vec();
+
+        for _ in 0..=rng.below(4) {
+            let at = rng.below(data.len() + 1);
+            match rng.below(4) {
+                0 => data.truncate(at),
+                1 => data.insert(at, rng.byte()),
+                2 if at < data.len() => data[at] = rng.byte(),
+                _ => {
+                    data.drain(at..(at + rng.below(8)).min(data.len()));
+                }
+            }
+        }
+
+        fuzz_target(&data);
+    }
+}
+
+#[test]
+pub fn invalid_utf8_never_panics() {
+    for data in [
+        &b"\xFF"[..],
+        b"\x80",
+        b"return \xC3",
+        b"return '\xC3'",
+        b"return '\\u{",
+        b"return '\\u{7FFF",
+        b"return '\\xF",
+        b"return [[\xF0\x9F",
+        b"local \xE2\x82 = 1",
+        b"return 1\xCC\x81",
+        b"return 0x\xFF",
+        b"return \xD9\xA3",
+    ] {
+        fuzz_target(data);
+    }
+}
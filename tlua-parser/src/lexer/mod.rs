@@ -78,28 +78,31 @@ pub(crate) enum Token {
     #[strum(to_string = "hexidecimal float")]
     #[regex(
         br#"0[xX][0-9A-Fa-f]+(:?\.[0-9A-Fa-f]*)?[pP][-+]?[0-9A-Fa-f]+"#,
-        parse_hex_float
+        |lex| parse_numeral(lex, parse_hex_float)
     )]
     HexFloat(LexedNumber),
 
     /// Exactly:
     /// [-] 0x<hex digits>.<hex digits?>
     #[strum(to_string = "hexidecimal float")]
-    #[regex(br#"0[xX][0-9A-Fa-f]+\.[0-9A-Fa-f]*"#, parse_hex_float_no_power)]
+    #[regex(br#"0[xX][0-9A-Fa-f]+\.[0-9A-Fa-f]*"#, |lex| parse_numeral(lex, parse_hex_float_no_power))]
     HexFloatNoPower(LexedNumber),
 
     /// Exactly
     /// [-] 0x<hex digits>
+    ///
+    /// A `0x` prefix without any digits is lexed as a malformed hex integer.
     #[strum(to_string = "hexidecimal integer")]
-    #[regex(br#"0[xX][0-9A-Fa-f]+"#, parse_hex_int)]
+    #[regex(br#"0[xX][0-9A-Fa-f]+"#, |lex| parse_numeral(lex, parse_hex_int))]
+    #[regex(br#"0[xX]"#, |lex| parse_numeral(lex, |_| LexedNumber::MalformedNumber))]
     HexInt(LexedNumber),
 
     #[strum(to_string = "float")]
-    #[regex(br#"\d+(:?\.\d*(:?[eE][-+]?\d+)?|[eE][-+]?\d+)"#, parse_float)]
+    #[regex(br#"[0-9]+(:?\.[0-9]*(:?[eE][-+]?[0-9]+)?|[eE][-+]?[0-9]+)"#, |lex| parse_numeral(lex, parse_float))]
     Float(LexedNumber),
 
     #[strum(to_string = "integer")]
-    #[regex(br#"\d+"#, parse_int)]
+    #[regex(br#"[0-9]+"#, |lex| parse_numeral(lex, parse_int))]
     Int(LexedNumber),
 
     #[strum(to_string = "boolean")]
@@ -355,10 +358,35 @@ fn bump_to_end_of_multiline_comment(
     MultilineComment::Unclosed
 }
 
+/// Parses a numeric literal using `parse`. Like reference Lua, a literal which
+/// runs directly into an identifier (e.g. `123abc` or `0xfg`) is a single
+/// malformed number rather than a number followed by an identifier.
+fn parse_numeral(
+    lexer: &mut Lexer<Token>,
+    parse: impl FnOnce(&mut Lexer<Token>) -> LexedNumber,
+) -> LexedNumber {
+    let number = parse(lexer);
+
+    let trailing = lexer
+        .remainder()
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+        .count();
+
+    if trailing == 0 {
+        number
+    } else {
+        lexer.bump(trailing);
+        LexedNumber::MalformedNumber
+    }
+}
+
 fn parse_float(lexer: &mut Lexer<Token>) -> LexedNumber {
     let span = lexer.slice();
 
-    // SAFETY: We know that we only have <digits>/eE/-+
+    debug_assert!(span.is_ascii());
+    // SAFETY: The regexes for integers & floats only match the ASCII characters
+    // 0-9, `.`, `:`, e/E, and -/+, so the span is valid UTF-8.
     unsafe { std::str::from_utf8_unchecked(span) }
         .parse()
         .map(LexedNumber::Float)
@@ -368,7 +396,9 @@ fn parse_float(lexer: &mut Lexer<Token>) -> LexedNumber {
 fn parse_hex_float(lexer: &mut Lexer<Token>) -> LexedNumber {
     let span = lexer.slice();
 
-    // SAFETY: We know that we only have 0x/<hex digits>/pP/-+
+    debug_assert!(span.is_ascii());
+    // SAFETY: The regex for hex floats only matches the ASCII characters 0-9,
+    // a-f/A-F, x/X, `.`, `:`, p/P, and -/+, so the span is valid UTF-8.
     parse_hexf64(unsafe { std::str::from_utf8_unchecked(span) }, false)
         .map(LexedNumber::Float)
         .unwrap_or(LexedNumber::MalformedNumber)
@@ -386,7 +416,10 @@ fn parse_hex_float_no_power(lexer: &mut Lexer<Token>) -> LexedNumber {
     let mut string = BString::from(span);
     string.extend_from_slice(b"p0");
 
-    // SAFETY: We know that we only have 0x/<hex digits>/pP/-+
+    debug_assert!(string.is_ascii());
+    // SAFETY: The regex for hex floats without a power only matches the ASCII
+    // characters 0-9, a-f/A-F, x/X, and `.`, and only `p0` was appended, so the
+    // string is valid UTF-8.
     parse_hexf64(unsafe { std::str::from_utf8_unchecked(&string) }, false)
         .map(LexedNumber::Float)
         .unwrap_or(LexedNumber::MalformedNumber)
@@ -258,6 +258,65 @@ fn lexes_integer_constant() {
     assert_eq!(lexer.next(), None);
 }
 
+#[test]
+fn lexes_hex_prefix_without_digits_malformed() {
+    for src in ["0x", "0X"] {
+        let mut lexer = Lexer::new(src.as_bytes());
+
+        assert_eq!(
+            lexer.next(),
+            Some(Token::HexInt(LexedNumber::MalformedNumber))
+        );
+        assert_eq!(lexer.span(), 0..2);
+
+        assert_eq!(lexer.next(), None);
+    }
+}
+
+#[test]
+fn lexes_hex_prefix_before_ident_malformed() {
+    let src = "0xg + 1";
+
+    let mut lexer = Lexer::new(src.as_bytes());
+
+    assert_eq!(
+        lexer.next(),
+        Some(Token::HexInt(LexedNumber::MalformedNumber))
+    );
+    assert_eq!(lexer.slice(), b"0xg");
+
+    assert_eq!(lexer.next(), Some(Token::Whitespace));
+    assert_eq!(lexer.next(), Some(Token::Plus));
+}
+
+#[test]
+fn lexes_number_before_ident_malformed() {
+    for (src, token) in [
+        ("123abc", Token::Int(LexedNumber::MalformedNumber)),
+        ("1.5e3x", Token::Float(LexedNumber::MalformedNumber)),
+        ("0xffz_1", Token::HexInt(LexedNumber::MalformedNumber)),
+    ] {
+        let mut lexer = Lexer::new(src.as_bytes());
+
+        assert_eq!(lexer.next(), Some(token), "{}", src);
+        assert_eq!(lexer.slice(), src.as_bytes());
+
+        assert_eq!(lexer.next(), None);
+    }
+}
+
+#[test]
+fn lexes_number_before_space_ident() {
+    let src = "123 abc";
+
+    let mut lexer = Lexer::new(src.as_bytes());
+
+    assert_eq!(lexer.next(), Some(Token::Int(LexedNumber::Int(123))));
+    assert_eq!(lexer.next(), Some(Token::Whitespace));
+    assert_eq!(lexer.next(), Some(Token::Ident));
+    assert_eq!(lexer.next(), None);
+}
+
 #[test]
 fn lexes_hex_constant_wrapping() {
     let src = "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFFFFFFFFFFF";
@@ -348,3 +407,17 @@ fn parses_ident_alphanum() {
     assert_eq!(lexer.slice(), src.as_bytes());
     assert_eq!(lexer.next(), None);
 }
+
+#[test]
+fn non_ascii_digits_are_not_numbers() {
+    // ARABIC-INDIC DIGIT THREE
+    let src = "\u{663}1";
+
+    let mut lexer = Lexer::new(src.as_bytes());
+
+    assert_eq!(lexer.next(), Some(Token::Error));
+    assert_eq!(lexer.next(), Some(Token::Error));
+    assert_eq!(lexer.next(), Some(Token::Int(LexedNumber::Int(1))));
+    assert_eq!(lexer.span(), 2..3);
+    assert_eq!(lexer.next(), None);
+}
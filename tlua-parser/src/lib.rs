@@ -1,3 +1,5 @@
+use std::mem::MaybeUninit;
+
 use bstr::{
     BStr,
     BString,
@@ -9,31 +11,62 @@ use tlua_strings::LuaString;
 
 pub mod block;
 mod combinators;
+mod completion;
+mod delimiters;
 pub mod errors;
 pub mod expressions;
+#[cfg(test)]
+mod fuzz_tests;
 pub mod identifiers;
 mod lexer;
+mod line_index;
 pub mod list;
 pub mod prefix_expression;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod statement;
+pub mod tokens;
 
 pub(crate) use combinators::*;
+pub use completion::{
+    parse_for_completion,
+    AccessKind,
+    PartialAccess,
+};
+pub use delimiters::{
+    check_delimiters,
+    DelimiterError,
+};
 pub use errors::ChunkParseError;
 pub(crate) use errors::{
     ParseError,
     SyntaxError,
 };
+pub use line_index::{
+    LineIndex,
+    Position,
+    DEFAULT_TAB_WIDTH,
+};
+pub use tokens::{
+    tokenize,
+    PublicToken,
+};
 
 use crate::{
     block::Block,
-    expressions::strings::ConstantString,
+    expressions::{
+        strings::ConstantString,
+        Expression,
+    },
     identifiers::Ident,
     lexer::{
         SpannedToken,
         Token,
     },
+    statement::Statement,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SourceSpan {
     start: usize,
@@ -60,25 +93,87 @@ impl std::fmt::Display for SourceSpan {
 }
 
 impl SourceSpan {
-    /// Relocate this span to be relative to a `base` span.
+    /// The byte offset of the start of this span.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset one past the end of this span.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Relocate a span within the source immediately following the `base`
+    /// span, e.g. the body of a string after its opening delimiter, to be
+    /// relative to the source containing `base`.
     pub(crate) fn translate(&self, base: Self) -> Self {
         let SourceSpan { start, end } = self;
         Self {
-            start: base.start + start,
-            end: base.start + end,
+            start: base.end + start,
+            end: base.end + end,
         }
     }
 }
 
-pub fn parse_chunk<'src, 'strings, 'chunk>(
-    input: &'src str,
+/// Options controlling how source code is interpreted by the parser.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// How escape sequences in string literals which aren't part of the
+    /// language, e.g. `\q`, are handled.
+    pub unknown_escapes: UnknownEscapes,
+    /// Which values are accepted in `\u{XXX}` escape sequences.
+    pub unicode_escapes: UnicodeEscapes,
+}
+
+/// The handling of unrecognized escape sequences in string literals.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownEscapes {
+    /// Reject the string with a syntax error, as the reference implementation
+    /// does.
+    #[default]
+    Error,
+    /// Keep the backslash and the character following it in the string.
+    PassThrough,
+}
+
+/// The values accepted in `\u{XXX}` escape sequences in string literals.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeEscapes {
+    /// Accept any value below 2^31, as the reference implementation does.
+    /// Values which aren't unicode scalar values are encoded with the
+    /// original (up to 6 byte) UTF-8 scheme, producing invalid UTF-8.
+    #[default]
+    Lenient,
+    /// Only accept unicode scalar values, so that escapes always produce valid
+    /// UTF-8. Surrogates and values above `0x10FFFF` are a syntax error.
+    Strict,
+}
+
+/// Parses `input` as a chunk of lua source.
+///
+/// The input may be any sequence of bytes, since lua strings aren't required
+/// to be valid UTF-8. Input which isn't valid lua is reported as an error, and
+/// never causes a panic.
+pub fn parse_chunk<'chunk>(
+    input: &(impl AsRef<[u8]> + ?Sized),
+    alloc: &'chunk ASTAllocator,
+    strings: &mut StringTable,
+) -> Result<Block<'chunk>, ChunkParseError> {
+    parse_chunk_with_options(input, alloc, strings, ParseOptions::default())
+}
+
+pub fn parse_chunk_with_options<'chunk>(
+    input: &(impl AsRef<[u8]> + ?Sized),
     alloc: &'chunk ASTAllocator,
-    strings: &'strings mut StringTable,
+    strings: &mut StringTable,
+    options: ParseOptions,
 ) -> Result<Block<'chunk>, ChunkParseError> {
+    let input = input.as_ref();
     if input.is_empty() {
         Ok(Block::default())
     } else {
-        let mut token_stream = SpannedTokenStream::new(input.as_bytes(), strings);
+        let mut token_stream = SpannedTokenStream::new(input, strings);
+        token_stream.options = options;
         Block::parse(&mut token_stream, alloc)
             .and_then(|val| match token_stream.peek() {
                 None => Ok(val),
@@ -87,10 +182,85 @@ pub fn parse_chunk<'src, 'strings, 'chunk>(
                     location: token.span,
                 }),
             })
-            .map_err(ChunkParseError::from)
+            .map_err(|error| ChunkParseError::new(error, input))
     }
 }
 
+/// Parses `input` as a single expression, e.g. for evaluating the input of a
+/// REPL. Anything following the expression is an error.
+pub fn parse_expression<'chunk>(
+    input: &str,
+    alloc: &'chunk ASTAllocator,
+    strings: &mut StringTable,
+) -> Result<Expression<'chunk>, ChunkParseError> {
+    final_parser!((input.as_bytes(), alloc, strings) => Expression::parse)
+        .map_err(|error| ChunkParseError::new(error, input.as_bytes()))
+}
+
+/// Parses the single statement starting at byte `offset` of `input`, and
+/// returns it along with the offset just past its end.
+///
+/// Hosts which re-parse source as it is edited can use this to parse only the
+/// statements which changed, continuing from the end of the previous
+/// statement. `offset` must not be inside of a token, although this is only
+/// checked for the tokens immediately around it - an offset inside of a string
+/// or comment may parse its remaining contents as code.
+pub fn parse_statement_at<'chunk>(
+    input: &str,
+    offset: usize,
+    alloc: &'chunk ASTAllocator,
+    strings: &mut StringTable,
+) -> Result<(Statement<'chunk>, usize), ParseError> {
+    if !starts_token(input, offset) {
+        return Err(ParseError {
+            error: SyntaxError::OffsetInsideToken,
+            location: SourceSpan {
+                start: offset,
+                end: offset,
+            },
+        });
+    }
+
+    let src = input.as_bytes();
+    let mut token_stream = SpannedTokenStream::new(src, strings);
+    token_stream.set_source_loc(&src[offset..]);
+
+    match Statement::try_parse(&mut token_stream, alloc)? {
+        Some(statement) => Ok((statement, token_stream.previous_end)),
+        None => Err(ParseError::from_here(
+            &mut token_stream,
+            SyntaxError::ExpectedStatement,
+        )),
+    }
+}
+
+/// Checks that `offset` doesn't split a token of `input`, without lexing
+/// everything before it.
+fn starts_token(input: &str, offset: usize) -> bool {
+    if offset == 0 || offset == input.len() {
+        return true;
+    }
+    if !input.is_char_boundary(offset) {
+        return false;
+    }
+
+    // If the token containing the preceding byte continues past it, the offset
+    // is inside of that token. Splitting whitespace is harmless.
+    let mut lexer = Lexer::<Token>::new(&input.as_bytes()[offset - 1..]);
+    matches!(lexer.next(), Some(Token::Whitespace)) || lexer.span().end <= 1
+}
+
+/// The identifiers & string constants of one or more chunks.
+///
+/// Each distinct sequence of bytes is stored once, and identifiers share
+/// entries with strings which have the same bytes. Ids are only meaningful to
+/// the table which produced them.
+///
+/// Strings created while running a chunk, e.g. by `string.sub`, aren't added
+/// to the table. The runtime always compares strings by their bytes, so they
+/// are equal to, and index the same table entries as, constants with the same
+/// bytes. [`StringTable::intern`] is available to hosts which need an id for
+/// such a string.
 #[derive(Debug, Default, Clone)]
 pub struct StringTable {
     strings: IndexSet<LuaString>,
@@ -120,20 +290,75 @@ impl StringTable {
     pub fn add_string(&mut self, string: BString) -> ConstantString {
         ConstantString(self.strings.insert_full(string.into()).0)
     }
+
+    /// Finds the id of the string with the bytes of `string`, adding it to the
+    /// table if it isn't already present. Interning the same bytes always
+    /// produces the same id.
+    pub fn intern(&mut self, string: &[u8]) -> ConstantString {
+        match self.strings.get_index_of(<&BStr>::from(string)) {
+            Some(id) => ConstantString(id),
+            None => ConstantString(self.strings.insert_full(string.into()).0),
+        }
+    }
 }
 
+/// The arena which parsed trees are allocated in. Every tree borrows the
+/// allocator it was parsed with, so the arena can only be reset once all of
+/// them have been dropped.
 #[derive(Debug)]
 pub struct ASTAllocator(Bump);
 
 impl ASTAllocator {
+    /// The number of bytes in the chunks which the arena has allocated,
+    /// including space which hasn't been used yet.
     pub fn allocated_bytes(&self) -> usize {
         self.0.allocated_bytes()
     }
 
+    /// The number of bytes remaining in the arena's current chunk before it
+    /// needs to allocate another.
+    pub fn chunk_capacity(&self) -> usize {
+        self.0.chunk_capacity()
+    }
+
+    /// Iterates over the memory used by each chunk of the arena, starting with
+    /// the most recently allocated one.
+    ///
+    /// The contents of the chunks are the parsed trees, including any padding
+    /// between their nodes, so they are only of use for inspecting how the
+    /// arena is used.
+    pub fn iter_allocated_chunks(&mut self) -> impl Iterator<Item = &[MaybeUninit<u8>]> + '_ {
+        self.0.iter_allocated_chunks()
+    }
+
+    /// Frees every tree allocated in the arena so that its memory can be reused
+    /// for parsing another chunk. Only the most recently allocated chunk is
+    /// kept.
+    ///
+    /// Resetting requires that no trees parsed with the allocator are still
+    /// alive, which the borrow checker enforces:
+    ///
+    /// ```compile_fail
+    /// # use tlua_parser::{parse_chunk, ASTAllocator, StringTable};
+    /// let mut alloc = ASTAllocator::default();
+    /// let mut strings = StringTable::default();
+    /// let block = parse_chunk("return 1", &alloc, &mut strings).unwrap();
+    ///
+    /// alloc.reset();
+    /// drop(block);
+    /// ```
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
     #[allow(clippy::mut_from_ref)] // I think bumpalo knows what it's doing
     pub fn alloc<T>(&self, val: T) -> &mut T {
         self.0.alloc(val)
     }
+
+    pub(crate) fn alloc_slice<T: Copy>(&self, vals: &[T]) -> &[T] {
+        self.0.alloc_slice_copy(vals)
+    }
 }
 
 impl Default for ASTAllocator {
@@ -146,7 +371,10 @@ pub(crate) struct SpannedTokenStream<'src, 'strings> {
     src: &'src [u8],
     lexer: Lexer<'src, Token>,
     peeked: Option<SpannedToken<'src>>,
+    /// The end of the most recently consumed token.
+    previous_end: usize,
     pub(crate) strings: &'strings mut StringTable,
+    pub(crate) options: ParseOptions,
 }
 
 impl SpannedTokenStream<'_, '_> {
@@ -158,25 +386,33 @@ impl SpannedTokenStream<'_, '_> {
             src,
             lexer: Lexer::new(src),
             peeked: None,
+            previous_end: 0,
             strings,
+            options: ParseOptions::default(),
         }
     }
 }
 
 impl<'src> SpannedTokenStream<'src, '_> {
     fn next(&mut self) -> Option<SpannedToken<'src>> {
-        self.peeked.take().or_else(|| {
-            while let Some(token) = self.lexer.next() {
-                if !Token::is_whitespace(&token) {
-                    return Some(SpannedToken {
-                        token,
-                        span: self.lexer.span().into(),
-                        src: self.lexer.slice(),
-                    });
-                }
+        let token = self.peeked.take().or_else(|| self.lex_next());
+        if let Some(token) = token {
+            self.previous_end = token.span.end;
+        }
+        token
+    }
+
+    fn lex_next(&mut self) -> Option<SpannedToken<'src>> {
+        while let Some(token) = self.lexer.next() {
+            if !Token::is_whitespace(&token) {
+                return Some(SpannedToken {
+                    token,
+                    span: self.lexer.span().into(),
+                    src: self.lexer.slice(),
+                });
             }
-            None
-        })
+        }
+        None
     }
 
     fn current_span(&mut self) -> SourceSpan {
@@ -190,7 +426,7 @@ impl<'src> SpannedTokenStream<'src, '_> {
 
     fn peek(&mut self) -> Option<SpannedToken<'src>> {
         if self.peeked.is_none() {
-            self.peeked = self.next();
+            self.peeked = self.lex_next();
         }
         self.peeked
     }
@@ -200,7 +436,7 @@ impl<'src> SpannedTokenStream<'src, '_> {
         filter: impl FnOnce(&SpannedToken<'src>) -> bool,
     ) -> Option<SpannedToken<'src>> {
         if self.peek().filter(filter).is_some() {
-            self.peeked.take()
+            self.next()
         } else {
             None
         }
@@ -246,13 +482,13 @@ impl<'src> SpannedTokenStream<'src, '_> {
         self.lexer = Lexer::new(self.src);
         let offset = src.as_ptr() as usize - self.src.as_ptr() as usize;
         self.lexer.bump(offset);
+        self.previous_end = offset;
         let _peek = self.peek();
     }
 }
 
 type PeekableLexer<'src, 'strings> = SpannedTokenStream<'src, 'strings>;
 
-#[cfg(test)]
 macro_rules! final_parser {
     (($input:expr, $alloc:expr, $strings:expr) => $parser:expr) => {{
         let mut token_stream = $crate::SpannedTokenStream::new($input, $strings);
@@ -268,7 +504,6 @@ macro_rules! final_parser {
     }};
 }
 
-#[cfg(test)]
 pub(crate) use final_parser;
 
 macro_rules! token_subset {
@@ -329,11 +564,25 @@ pub(crate) use token_subset;
 
 #[cfg(test)]
 mod tests {
+    use pretty_assertions::assert_eq;
+
     use crate::{
         block::Block,
+        expressions::{
+            number::Number,
+            operator::{
+                BinaryOperator,
+                Plus,
+            },
+            Expression,
+        },
         parse_chunk,
+        parse_expression,
+        parse_statement_at,
+        statement::Statement,
         ASTAllocator,
         StringTable,
+        SyntaxError,
     };
 
     #[test]
@@ -348,6 +597,67 @@ mod tests {
             result,
             Block {
                 statements: Default::default(),
+                spans: &[],
+                ret: None
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn parses_whitespace_chunk() -> anyhow::Result<()> {
+        let src = "   \n\t\r\n  ";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = parse_chunk(src, &alloc, &mut strings)?;
+
+        assert_eq!(
+            result,
+            Block {
+                statements: Default::default(),
+                spans: &[],
+                ret: None
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn parses_comment_chunk() -> anyhow::Result<()> {
+        let src = "-- comment";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = parse_chunk(src, &alloc, &mut strings)?;
+
+        assert_eq!(
+            result,
+            Block {
+                statements: Default::default(),
+                spans: &[],
+                ret: None
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn parses_whitespace_comment_chunk() -> anyhow::Result<()> {
+        let src = "   \n-- comment\n\t--[[ multiline\n comment ]]\n";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = parse_chunk(src, &alloc, &mut strings)?;
+
+        assert_eq!(
+            result,
+            Block {
+                statements: Default::default(),
+                spans: &[],
                 ret: None
             }
         );
@@ -355,6 +665,119 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn errors_include_found_token() {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let err = parse_chunk("return (1", &alloc, &mut strings).unwrap_err();
+        assert_eq!(err.found, None);
+        assert_eq!(
+            err.to_string(),
+            "Errors parsing chunk: Error: Expected ) at bytes: 9..9, found end of file"
+        );
+
+        let err = parse_chunk("return (1 end", &alloc, &mut strings).unwrap_err();
+        assert_eq!(err.found.as_deref(), Some("end"));
+        assert_eq!(
+            err.to_string(),
+            "Errors parsing chunk: Error: Expected ) at bytes: 10..13, found 'end'"
+        );
+    }
+
+    #[test]
+    pub fn parses_statements_from_offsets() -> anyhow::Result<()> {
+        let src = "local a = 1\nfoo(a) -- call\n";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let (statement, end) = parse_statement_at(src, 0, &alloc, &mut strings)?;
+        assert!(matches!(statement, Statement::LocalVarList(_)));
+        assert_eq!(&src[..end], "local a = 1");
+
+        let (statement, end) = parse_statement_at(src, end, &alloc, &mut strings)?;
+        assert!(matches!(statement, Statement::Call(_)));
+        assert_eq!(&src[..end], "local a = 1\nfoo(a)");
+
+        let err = parse_statement_at(src, end, &alloc, &mut strings).unwrap_err();
+        assert_eq!(err.error, SyntaxError::ExpectedStatement);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn statement_offset_inside_token() {
+        let src = "local abc = 1 == 2";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        for offset in [3, 7, 15] {
+            let err = parse_statement_at(src, offset, &alloc, &mut strings).unwrap_err();
+            assert_eq!(err.error, SyntaxError::OffsetInsideToken, "offset {offset}");
+        }
+    }
+
+    #[test]
+    pub fn parses_expressions() -> anyhow::Result<()> {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        assert_eq!(
+            parse_expression("10", &alloc, &mut strings)?,
+            Expression::Number(Number::Integer(10))
+        );
+
+        assert_eq!(
+            parse_expression("1 + 2", &alloc, &mut strings)?,
+            Expression::BinaryOp(BinaryOperator::Plus(Plus {
+                lhs: &Expression::Number(Number::Integer(1)),
+                rhs: &Expression::Number(Number::Integer(2)),
+            }))
+        );
+
+        assert!(matches!(
+            parse_expression("f(a, b)", &alloc, &mut strings)?,
+            Expression::FunctionCall(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn expression_trailing_tokens() {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let err = parse_expression("1 + 2 end", &alloc, &mut strings).unwrap_err();
+        assert_eq!(err.found.as_deref(), Some("end"));
+        assert!(matches!(err.error.error, SyntaxError::ExpectedEOF(_)));
+
+        let err = parse_expression("", &alloc, &mut strings).unwrap_err();
+        assert_eq!(err.error.error, SyntaxError::ExpectedExpression);
+    }
+
+    #[test]
+    pub fn reuses_reset_allocator() -> anyhow::Result<()> {
+        let mut alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let src = "local a, b = 1, 2 return a + b * 3";
+        let expected = format!("{:?}", parse_chunk(src, &alloc, &mut strings)?);
+        let allocated = alloc.allocated_bytes();
+        assert!(alloc.iter_allocated_chunks().count() > 0);
+
+        alloc.reset();
+        assert!(alloc.chunk_capacity() > 0);
+
+        let reparsed = format!("{:?}", parse_chunk(src, &alloc, &mut strings)?);
+        assert_eq!(reparsed, expected);
+        assert!(alloc.allocated_bytes() <= allocated);
+
+        Ok(())
+    }
+
     #[cfg(feature = "rendered-errors")]
     #[test]
     #[ignore = "just for interacting with error output"]
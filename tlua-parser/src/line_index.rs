@@ -0,0 +1,192 @@
+/// The tab width used by [`LineIndex::new`].
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// A line & column in source text, as used by editors. Both are 0-based.
+///
+/// Columns count characters rather than bytes, except that a tab advances the
+/// column to the next multiple of the tab width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Converts between byte offsets into source text and [`Position`]s.
+///
+/// The start of each line is computed up front, so finding the line for an
+/// offset is a binary search and only the text of that one line is scanned to
+/// find the column.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'src> {
+    src: &'src str,
+    tab_width: usize,
+    /// The byte offset at which each line begins.
+    line_starts: Vec<usize>,
+}
+
+impl<'src> LineIndex<'src> {
+    pub fn new(src: &'src str) -> Self {
+        Self::with_tab_width(src, DEFAULT_TAB_WIDTH)
+    }
+
+    /// Builds an index where tabs advance to the next multiple of `tab_width`
+    /// columns. A width of 0 is treated as 1.
+    pub fn with_tab_width(src: &'src str, tab_width: usize) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        Self {
+            src,
+            tab_width: tab_width.max(1),
+            line_starts,
+        }
+    }
+
+    /// The number of lines in the source. Text after the last newline, even
+    /// if empty, is a line of its own.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Finds the position of the character starting at `offset`. The end of
+    /// the source is also a valid offset.
+    ///
+    /// Returns `None` if `offset` is past the end of the source or in the
+    /// middle of a character.
+    pub fn offset_to_position(&self, offset: usize) -> Option<Position> {
+        if !self.src.is_char_boundary(offset) {
+            return None;
+        }
+
+        let line = self.line_of(offset)?;
+
+        let prefix = &self.src[self.line_starts[line]..offset];
+        let column = prefix
+            .chars()
+            .fold(0, |column, c| self.advance_column(column, c));
+
+        Some(Position { line, column })
+    }
+
+    /// Finds the line containing `offset` without computing its column.
+    ///
+    /// Returns `None` if `offset` is past the end of the source.
+    pub fn line_of(&self, offset: usize) -> Option<usize> {
+        if offset > self.src.len() {
+            return None;
+        }
+
+        self.line_starts
+            .partition_point(|&start| start <= offset)
+            .checked_sub(1)
+    }
+
+    /// Finds the byte offset of the character at `position`. The column just
+    /// past the last character of a line is the offset of its line break.
+    /// Columns inside of a tab refer to the tab.
+    ///
+    /// Returns `None` if the line doesn't exist or the column is past the end
+    /// of the line.
+    pub fn position_to_offset(&self, position: Position) -> Option<usize> {
+        let start = *self.line_starts.get(position.line)?;
+        let end = self
+            .line_starts
+            .get(position.line + 1)
+            .map_or(self.src.len(), |&next| next - 1);
+
+        let mut column = 0;
+        for (offset, c) in self.src[start..end].char_indices() {
+            let next = self.advance_column(column, c);
+            if position.column < next {
+                return Some(start + offset);
+            }
+            column = next;
+        }
+
+        (position.column == column).then_some(end)
+    }
+
+    fn advance_column(&self, column: usize, c: char) -> usize {
+        if c == '\t' {
+            (column / self.tab_width + 1) * self.tab_width
+        } else {
+            column + 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        LineIndex,
+        Position,
+    };
+
+    #[test]
+    fn columns_expand_tabs() {
+        let src = "local a = 1\n\tif a then\n\t\treturn 'é'\n\tend\n";
+        let index = LineIndex::with_tab_width(src, 4);
+
+        let offset = src.find("return").unwrap();
+        let position = Position { line: 2, column: 8 };
+        assert_eq!(index.offset_to_position(offset), Some(position));
+        assert_eq!(index.position_to_offset(position), Some(offset));
+
+        // Columns count characters, not bytes.
+        let quote = src.rfind('\'').unwrap();
+        let position = Position {
+            line: 2,
+            column: 17,
+        };
+        assert_eq!(index.offset_to_position(quote), Some(position));
+        assert_eq!(index.position_to_offset(position), Some(quote));
+        assert_eq!(index.offset_to_position(quote - 1), None);
+
+        // A tab stop is reached even after text which doesn't fill it.
+        let index = LineIndex::with_tab_width("ab\tc", 4);
+        assert_eq!(
+            index.offset_to_position(3),
+            Some(Position { line: 0, column: 4 })
+        );
+        assert_eq!(
+            index.position_to_offset(Position { line: 0, column: 3 }),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn positions_at_line_ends() {
+        let src = "a\nbc\n";
+        let index = LineIndex::new(src);
+
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(
+            index.offset_to_position(4),
+            Some(Position { line: 1, column: 2 })
+        );
+        assert_eq!(
+            index.offset_to_position(src.len()),
+            Some(Position { line: 2, column: 0 })
+        );
+        assert_eq!(index.offset_to_position(src.len() + 1), None);
+        assert_eq!(index.line_of(1), Some(0));
+        assert_eq!(index.line_of(src.len()), Some(2));
+        assert_eq!(index.line_of(src.len() + 1), None);
+
+        assert_eq!(
+            index.position_to_offset(Position { line: 1, column: 2 }),
+            Some(4)
+        );
+        assert_eq!(
+            index.position_to_offset(Position { line: 1, column: 3 }),
+            None
+        );
+        assert_eq!(
+            index.position_to_offset(Position { line: 3, column: 0 }),
+            None
+        );
+    }
+}
@@ -23,7 +23,7 @@ impl<'list, T> ListNode<'list, T> {
         &'s mut self,
         next: &'list mut ListNode<'list, T>,
     ) -> &'s mut ListNode<'list, T> {
-        *self.next.insert(next)
+        self.next.insert(next)
     }
 }
 
@@ -85,6 +85,9 @@ impl<'c, 'list, T> CursorMut<'c, 'list, T> {
     }
 }
 
+/// A singly linked list whose nodes are allocated in the [`ASTAllocator`]
+/// while parsing. Iterating the list only follows references into the arena,
+/// so it never allocates.
 pub struct List<'list, T> {
     len: usize,
     head: Option<&'list mut ListNode<'list, T>>,
@@ -203,10 +206,7 @@ impl<T> Copy for Iter<'_, '_, T> {}
 
 impl<T> Clone for Iter<'_, '_, T> {
     fn clone(&self) -> Self {
-        Self {
-            remain: self.remain,
-            current: self.current,
-        }
+        *self
     }
 }
 
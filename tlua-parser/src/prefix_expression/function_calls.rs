@@ -13,6 +13,7 @@ use crate::{
     SyntaxError,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum FnArgs<'chunk> {
     Expressions(List<'chunk, Expression<'chunk>>),
@@ -147,4 +148,21 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn parses_multiline_string_args() -> anyhow::Result<()> {
+        let src = "[==[\narg]]\n]==]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result = final_parser!((src.as_bytes(), &alloc, &mut strings) => FnArgs::parse)?;
+
+        assert_eq!(result, FnArgs::String(ConstantString(0)));
+        assert_eq!(
+            strings.get_string(ConstantString(0)).map(|s| s.as_slice()),
+            Some(&b"arg]]\n"[..])
+        );
+
+        Ok(())
+    }
 }
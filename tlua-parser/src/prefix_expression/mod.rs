@@ -14,6 +14,7 @@ use crate::{
 pub mod function_calls;
 use self::function_calls::FnArgs;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum HeadAtom<'chunk> {
     Name(Ident),
@@ -49,18 +50,21 @@ impl<'chunk> HeadAtom<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum VarAtom<'chunk> {
     Name(Ident),
     IndexOp(Expression<'chunk>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum FunctionAtom<'chunk> {
     Call(FnArgs<'chunk>),
     MethodCall { name: Ident, args: FnArgs<'chunk> },
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum PrefixAtom<'chunk> {
     Var(VarAtom<'chunk>),
@@ -106,6 +110,7 @@ impl<'chunk> PrefixAtom<'chunk> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum VarPrefixExpression<'chunk> {
     Name(Ident),
@@ -116,6 +121,7 @@ pub enum VarPrefixExpression<'chunk> {
     },
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum FnCallPrefixExpression<'chunk> {
     Call {
@@ -211,6 +217,7 @@ mod tests {
 
     use super::PrefixExpression;
     use crate::{
+        errors::SyntaxError,
         expressions::{
             strings::ConstantString,
             tables::TableConstructor,
@@ -218,6 +225,7 @@ mod tests {
         },
         final_parser,
         identifiers::Ident,
+        lexer::Token,
         list::{
             List,
             ListNode,
@@ -232,6 +240,8 @@ mod tests {
             VarPrefixExpression,
         },
         ASTAllocator,
+        ParseError,
+        SourceSpan,
         StringTable,
     };
 
@@ -448,6 +458,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn parses_fn_call_multiline_str() -> anyhow::Result<()> {
+        let src = "print[[hello]]";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => PrefixExpression::try_parse)?;
+
+        assert_eq!(
+            result,
+            Some(PrefixExpression::FnCall(FnCallPrefixExpression::Call {
+                head: HeadAtom::Name(Ident(0)),
+                args: FunctionAtom::Call(FnArgs::String(ConstantString(1)))
+            }))
+        );
+        assert_eq!(
+            strings.get_string(ConstantString(1)).map(|s| s.as_slice()),
+            Some(&b"hello"[..])
+        );
+
+        Ok(())
+    }
+
     #[test]
     pub fn parses_named_fn_call() -> anyhow::Result<()> {
         let src = "a:foo()";
@@ -518,4 +552,52 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn method_call_requires_name() {
+        let src = "a:()";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => PrefixExpression::try_parse);
+
+        assert_eq!(
+            result,
+            Err(ParseError {
+                error: SyntaxError::ExpectedToken(Token::Ident),
+                location: SourceSpan { start: 2, end: 3 },
+            })
+        );
+    }
+
+    #[test]
+    pub fn method_call_requires_args() {
+        let src = "a:b + 1";
+
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => PrefixExpression::try_parse);
+
+        assert_eq!(
+            result,
+            Err(ParseError {
+                error: SyntaxError::ExpectedFnArgs,
+                location: SourceSpan { start: 4, end: 5 },
+            })
+        );
+
+        let src = "a:b";
+        let result =
+            final_parser!((src.as_bytes(), &alloc, &mut strings) => PrefixExpression::try_parse);
+
+        assert_eq!(
+            result,
+            Err(ParseError {
+                error: SyntaxError::ExpectedFnArgs,
+                location: SourceSpan { start: 3, end: 3 },
+            })
+        );
+    }
 }
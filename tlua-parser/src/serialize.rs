@@ -0,0 +1,151 @@
+//! Serialization of the AST, enabled by the `serde` feature.
+//!
+//! Identifiers and string constants serialize as their index in the
+//! [`StringTable`] they were interned in. Serializing through
+//! [`WithStrings`] also includes the string each index refers to.
+
+use std::cell::Cell;
+
+use serde::{
+    ser::{
+        SerializeSeq,
+        SerializeStruct,
+    },
+    Serialize,
+    Serializer,
+};
+
+use crate::{
+    expressions::strings::ConstantString,
+    identifiers::Ident,
+    list::List,
+    StringTable,
+};
+
+thread_local! {
+    /// The strings used by the [`WithStrings`] being serialized on this thread,
+    /// if any.
+    static STRINGS: Cell<Option<*const StringTable>> = const { Cell::new(None) };
+}
+
+/// Serializes `value` with the text of each identifier and string constant in
+/// addition to its index in `strings`.
+pub struct WithStrings<'s, T> {
+    pub value: T,
+    pub strings: &'s StringTable,
+}
+
+impl<'s, T> WithStrings<'s, T> {
+    pub fn new(value: T, strings: &'s StringTable) -> Self {
+        Self { value, strings }
+    }
+}
+
+impl<T: Serialize> Serialize for WithStrings<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// Restores the previous strings even if serialization panics.
+        struct Restore(Option<*const StringTable>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                STRINGS.with(|strings| strings.set(self.0));
+            }
+        }
+
+        let _restore = Restore(STRINGS.with(|strings| strings.replace(Some(self.strings))));
+        self.value.serialize(serializer)
+    }
+}
+
+fn serialize_interned<S: Serializer>(
+    name: &'static str,
+    index: usize,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let string = STRINGS.with(Cell::get).and_then(|strings| {
+        // SAFETY: The pointer is only set while the `WithStrings` which borrows the
+        // table is being serialized.
+        let strings = unsafe { &*strings };
+        strings
+            .strings
+            .get_index(index)
+            .map(|string| String::from_utf8_lossy(string.as_slice()).into_owned())
+    });
+
+    let mut state = serializer.serialize_struct(name, 1 + usize::from(string.is_some()))?;
+    state.serialize_field("index", &index)?;
+    match string {
+        Some(string) => state.serialize_field("string", &string)?,
+        None => state.skip_field("string")?,
+    }
+    state.end()
+}
+
+impl Serialize for Ident {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_interned("Ident", self.0, serializer)
+    }
+}
+
+impl Serialize for ConstantString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_interned("ConstantString", self.0, serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for List<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self.iter() {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::WithStrings;
+    use crate::{
+        parse_expression,
+        ASTAllocator,
+        StringTable,
+    };
+
+    #[test]
+    fn serializes_tree() -> anyhow::Result<()> {
+        let alloc = ASTAllocator::default();
+        let mut strings = StringTable::default();
+
+        let expr = parse_expression("a + 'b'", &alloc, &mut strings)?;
+
+        assert_eq!(
+            serde_json::to_value(&expr)?,
+            json!({
+                "BinaryOp": {
+                    "Plus": {
+                        "lhs": { "Variable": { "Name": { "index": 0 } } },
+                        "rhs": { "String": { "index": 1 } },
+                    }
+                }
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(WithStrings::new(&expr, &strings))?,
+            json!({
+                "BinaryOp": {
+                    "Plus": {
+                        "lhs": { "Variable": { "Name": { "index": 0, "string": "a" } } },
+                        "rhs": { "String": { "index": 1, "string": "b" } },
+                    }
+                }
+            })
+        );
+
+        Ok(())
+    }
+}
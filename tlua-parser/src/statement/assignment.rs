@@ -13,6 +13,7 @@ use crate::{
     SyntaxError,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Assignment<'chunk> {
     pub varlist: List<'chunk, VarPrefixExpression<'chunk>>,
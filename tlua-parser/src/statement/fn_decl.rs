@@ -6,6 +6,7 @@ use crate::{
     identifiers::Ident,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum FnDecl<'chunk> {
     Function {
@@ -8,6 +8,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct ForLoop<'chunk> {
     pub var: Ident,
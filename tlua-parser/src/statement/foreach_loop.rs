@@ -9,6 +9,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct ForEachLoop<'chunk> {
     pub vars: List<'chunk, Ident>,
@@ -9,6 +9,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct If<'chunk> {
     pub cond: Expression<'chunk>,
@@ -17,6 +18,7 @@ pub struct If<'chunk> {
     pub else_final: Option<Block<'chunk>>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct ElseIf<'chunk> {
     pub cond: Expression<'chunk>,
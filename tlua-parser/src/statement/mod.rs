@@ -45,12 +45,15 @@ pub mod repeat_loop;
 pub mod variables;
 pub mod while_loop;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Empty;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Break;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Label(pub Ident);
 
@@ -67,9 +70,11 @@ impl Label {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct Goto(pub Ident);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub enum Statement<'chunk> {
     Empty(Empty),
@@ -400,6 +405,7 @@ mod test {
             stat,
             Some(Statement::Do(&Block {
                 statements: Default::default(),
+                spans: &[],
                 ret: None
             }))
         );
@@ -7,6 +7,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct RepeatLoop<'chunk> {
     pub body: Block<'chunk>,
@@ -10,18 +10,21 @@ use crate::{
     SyntaxError,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Attribute {
     Const,
     Close,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct LocalVar {
     pub name: Ident,
     pub attribute: Option<Attribute>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct LocalVarList<'chunk> {
     pub vars: List<'chunk, LocalVar>,
@@ -6,6 +6,7 @@ use crate::{
     PeekableLexer,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Debug, PartialEq)]
 pub struct WhileLoop<'chunk> {
     pub cond: Expression<'chunk>,
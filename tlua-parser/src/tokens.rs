@@ -0,0 +1,306 @@
+use logos::Lexer;
+
+use crate::{
+    expressions::strings,
+    lexer::{
+        LexedNumber,
+        MultilineComment,
+        Token,
+    },
+    SourceSpan,
+};
+
+/// The kind of a token produced by [`tokenize`].
+///
+/// This is intentionally coarser than the parser's internal token set so that
+/// it can remain stable as the parser evolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PublicToken {
+    /// Any run of whitespace, including newlines.
+    Whitespace,
+    /// A single line or multiline comment.
+    Comment,
+    /// A reserved word, including `true`, `false`, `nil`, `and`, `or`, and
+    /// `not`.
+    Keyword,
+    /// A name which isn't a reserved word.
+    Identifier,
+    /// An integer or float literal in any base.
+    Number,
+    /// A quoted or multiline string literal, including its delimiters.
+    String,
+    /// An arithmetic, bitwise, comparison, concatenation, length, or
+    /// assignment operator.
+    Operator,
+    /// Brackets, separators, and the remaining symbols (`.`, `:`, `::`,
+    /// `...`).
+    Punctuation,
+    /// Input which could not be lexed. The span covers the whole of the
+    /// invalid token, and lexing continues after it.
+    Error(TokenError),
+}
+
+/// Why a token produced by [`tokenize`] is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenError {
+    /// A numeric literal which isn't a valid integer or float, such as `0x` or
+    /// `123abc`.
+    MalformedNumber,
+    /// A string literal which reaches the end of its line or of the input
+    /// before its closing delimiter.
+    UnclosedString,
+    /// A multiline comment which reaches the end of the input before its
+    /// closing delimiter.
+    UnclosedComment,
+    /// A character which doesn't start any token.
+    Unknown,
+}
+
+impl From<Token> for PublicToken {
+    fn from(token: Token) -> Self {
+        match token {
+            Token::Whitespace => Self::Whitespace,
+            Token::SinglelineComment | Token::MultilineComment(MultilineComment::Valid) => {
+                Self::Comment
+            }
+            Token::MultilineComment(MultilineComment::Unclosed) => {
+                Self::Error(TokenError::UnclosedComment)
+            }
+            Token::Ident => Self::Identifier,
+            Token::SingleQuotedStringStart
+            | Token::DoubleQuotedStringStart
+            | Token::MultilineStringStart(_) => Self::String,
+            Token::HexFloat(LexedNumber::MalformedNumber)
+            | Token::HexFloatNoPower(LexedNumber::MalformedNumber)
+            | Token::HexInt(LexedNumber::MalformedNumber)
+            | Token::Float(LexedNumber::MalformedNumber)
+            | Token::Int(LexedNumber::MalformedNumber) => Self::Error(TokenError::MalformedNumber),
+            Token::HexFloat(_)
+            | Token::HexFloatNoPower(_)
+            | Token::HexInt(_)
+            | Token::Float(_)
+            | Token::Int(_) => Self::Number,
+            Token::Boolean(_)
+            | Token::Nil
+            | Token::KWand
+            | Token::KWbreak
+            | Token::KWdo
+            | Token::KWelse
+            | Token::KWelseif
+            | Token::KWend
+            | Token::KWfor
+            | Token::KWfunction
+            | Token::KWgoto
+            | Token::KWif
+            | Token::KWin
+            | Token::KWlocal
+            | Token::KWnot
+            | Token::KWor
+            | Token::KWrepeat
+            | Token::KWreturn
+            | Token::KWthen
+            | Token::KWuntil
+            | Token::KWwhile => Self::Keyword,
+            Token::Plus
+            | Token::Minus
+            | Token::Star
+            | Token::Slash
+            | Token::DoubleSlash
+            | Token::Caret
+            | Token::Percent
+            | Token::Ampersand
+            | Token::Tilde
+            | Token::Pipe
+            | Token::DoubleLeftAngle
+            | Token::DoubleRightAngle
+            | Token::DoublePeriod
+            | Token::LeftAngle
+            | Token::RightAngle
+            | Token::LeftAngleEquals
+            | Token::RightAngleEquals
+            | Token::TildeEquals
+            | Token::Hashtag
+            | Token::Equals
+            | Token::DoubleEquals => Self::Operator,
+            Token::LBracket
+            | Token::RBracket
+            | Token::LBrace
+            | Token::RBrace
+            | Token::LParen
+            | Token::RParen
+            | Token::Colon
+            | Token::DoubleColon
+            | Token::Period
+            | Token::Ellipses
+            | Token::Semicolon
+            | Token::Comma => Self::Punctuation,
+            Token::Error => Self::Error(TokenError::Unknown),
+        }
+    }
+}
+
+/// Splits `src` into a lossless stream of tokens.
+///
+/// Unlike the parser, this does not discard whitespace or comments, so the
+/// spans of the produced tokens cover the entire input. Strings and multiline
+/// comments are produced as a single token spanning their delimiters.
+pub fn tokenize(src: &str) -> impl Iterator<Item = (SourceSpan, PublicToken)> + '_ {
+    lossless_tokens(src).map(|(span, _, token)| (span, token))
+}
+
+/// The tokens produced by [`tokenize`], along with the parser's token for
+/// each. Strings are produced as a single token, identified by the token
+/// which starts them.
+pub(crate) fn lossless_tokens(
+    src: &str,
+) -> impl Iterator<Item = (SourceSpan, Token, PublicToken)> + '_ {
+    let mut lexer = Lexer::<Token>::new(src.as_bytes());
+
+    std::iter::from_fn(move || {
+        let token = lexer.next()?;
+
+        let public = match token {
+            Token::SingleQuotedStringStart
+            | Token::DoubleQuotedStringStart
+            | Token::MultilineStringStart(_) => {
+                match strings::scan_string(token, lexer.remainder()) {
+                    Ok(len) => {
+                        lexer.bump(len);
+                        PublicToken::String
+                    }
+                    Err(len) => {
+                        lexer.bump(len);
+                        PublicToken::Error(TokenError::UnclosedString)
+                    }
+                }
+            }
+            token => PublicToken::from(token),
+        };
+
+        Some((lexer.span().into(), token, public))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        tokenize,
+        PublicToken,
+        TokenError,
+    };
+
+    fn tokens(src: &str) -> Vec<(&str, PublicToken)> {
+        tokenize(src)
+            .map(|(span, token)| (&src[span.start()..span.end()], token))
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_empty() {
+        assert_eq!(tokens(""), vec![]);
+    }
+
+    #[test]
+    fn tokenizes_statement() {
+        let src = "local a = 10 -- comment\nreturn a..'b'";
+
+        assert_eq!(
+            tokens(src),
+            vec![
+                ("local", PublicToken::Keyword),
+                (" ", PublicToken::Whitespace),
+                ("a", PublicToken::Identifier),
+                (" ", PublicToken::Whitespace),
+                ("=", PublicToken::Operator),
+                (" ", PublicToken::Whitespace),
+                ("10", PublicToken::Number),
+                (" ", PublicToken::Whitespace),
+                ("-- comment", PublicToken::Comment),
+                ("\n", PublicToken::Whitespace),
+                ("return", PublicToken::Keyword),
+                (" ", PublicToken::Whitespace),
+                ("a", PublicToken::Identifier),
+                ("..", PublicToken::Operator),
+                ("'b'", PublicToken::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_multiline_tokens() {
+        let src = "--[==[ a\nb ]==]f([[c\n]] , \"d\\\"e\")";
+
+        assert_eq!(
+            tokens(src),
+            vec![
+                ("--[==[ a\nb ]==]", PublicToken::Comment),
+                ("f", PublicToken::Identifier),
+                ("(", PublicToken::Punctuation),
+                ("[[c\n]]", PublicToken::String),
+                (" ", PublicToken::Whitespace),
+                (",", PublicToken::Punctuation),
+                (" ", PublicToken::Whitespace),
+                ("\"d\\\"e\"", PublicToken::String),
+                (")", PublicToken::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_unclosed() {
+        let src = "'abc\nx --[[ d";
+
+        assert_eq!(
+            tokens(src),
+            vec![
+                ("'abc", PublicToken::Error(TokenError::UnclosedString)),
+                ("\n", PublicToken::Whitespace),
+                ("x", PublicToken::Identifier),
+                (" ", PublicToken::Whitespace),
+                ("--[[ d", PublicToken::Error(TokenError::UnclosedComment)),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_errors() {
+        let src = "x = 0x + 12abc $ 1.5e\n\"a\\\" .. 3";
+
+        assert_eq!(
+            tokens(src),
+            vec![
+                ("x", PublicToken::Identifier),
+                (" ", PublicToken::Whitespace),
+                ("=", PublicToken::Operator),
+                (" ", PublicToken::Whitespace),
+                ("0x", PublicToken::Error(TokenError::MalformedNumber)),
+                (" ", PublicToken::Whitespace),
+                ("+", PublicToken::Operator),
+                (" ", PublicToken::Whitespace),
+                ("12abc", PublicToken::Error(TokenError::MalformedNumber)),
+                (" ", PublicToken::Whitespace),
+                ("$", PublicToken::Error(TokenError::Unknown)),
+                (" ", PublicToken::Whitespace),
+                ("1.5e", PublicToken::Error(TokenError::MalformedNumber)),
+                ("\n", PublicToken::Whitespace),
+                (
+                    "\"a\\\" .. 3",
+                    PublicToken::Error(TokenError::UnclosedString)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_lossless() {
+        let src = "for i = 0x1p4, 3.5e1, -1 do t[#t + 1] = i ~= nil end ::lbl:: goto lbl";
+
+        let rebuilt = tokenize(src)
+            .map(|(span, _)| &src[span.start()..span.end()])
+            .collect::<String>();
+
+        assert_eq!(rebuilt, src);
+    }
+}
@@ -9,6 +9,16 @@ use derive_more::{
     Into,
 };
 
+mod utf8;
+
+pub use self::utf8::{
+    decode_utf8_raw,
+    encode_utf8_raw,
+};
+
+// Strings hash like their bytes, so hashed collections of them must be searched
+// with `[u8]` rather than `str` keys.
+#[allow(clippy::impl_hash_borrow_with_str_and_bytes)]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deref, DerefMut, From, Into)]
 pub struct LuaString(BString);
 
@@ -54,9 +64,65 @@ impl From<&[u8]> for LuaString {
     }
 }
 
+/// Formats floats the same way as Lua, i.e. using `%.14g`, with `.0` appended
+/// to values which would otherwise look like an integer.
 impl From<f64> for LuaString {
     fn from(f: f64) -> Self {
-        Self(f.to_string().into())
+        let mut formatted = format_float(f);
+        if formatted.bytes().all(|c| c.is_ascii_digit() || c == b'-') {
+            formatted.push_str(".0");
+        }
+
+        Self(formatted.into())
+    }
+}
+
+/// The number of significant digits used when formatting floats.
+const FLOAT_PRECISION: i32 = 14;
+
+/// Formats a float like C's `printf("%.14g", f)`.
+fn format_float(f: f64) -> String {
+    if f.is_nan() {
+        return if f.is_sign_negative() { "-nan" } else { "nan" }.to_string();
+    }
+    if f.is_infinite() {
+        return if f.is_sign_negative() { "-inf" } else { "inf" }.to_string();
+    }
+    if f == 0.0 {
+        return if f.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+
+    // Rounding to the precision first determines the exponent, which may be one
+    // more than that of the unrounded value, e.g. for 99999999999999.9.
+    let scientific = format!("{:.*e}", (FLOAT_PRECISION - 1) as usize, f);
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("Scientific notation has an exponent");
+    let exponent = exponent
+        .parse::<i32>()
+        .expect("Exponents are valid integers");
+
+    if !(-4..FLOAT_PRECISION).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!(
+            "{}e{}{:02}",
+            trim_fraction_zeros(mantissa),
+            sign,
+            exponent.abs()
+        )
+    } else {
+        let decimals = (FLOAT_PRECISION - 1 - exponent) as usize;
+        trim_fraction_zeros(&format!("{:.*}", decimals, f)).to_string()
+    }
+}
+
+/// Removes trailing zeros after the decimal point, and the decimal point
+/// itself if nothing follows it.
+fn trim_fraction_zeros(digits: &str) -> &str {
+    if digits.contains('.') {
+        digits.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        digits
     }
 }
 
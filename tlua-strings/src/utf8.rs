@@ -0,0 +1,144 @@
+/// Encodes `val` as a utf8 byte sequence, returning the bytes and the number of
+/// them which are used.
+///
+/// This follows the original utf8 scheme of up to 6 byte sequences, so any
+/// value below 2^31 is accepted, including surrogates and values past
+/// `0x10FFFF` which are invalid per spec. Larger values produce `None`.
+pub fn encode_utf8_raw(val: u32) -> Option<(usize, [u8; 6])> {
+    #[rustfmt::skip]
+    mod tag {
+    pub const CONT: u8    = 0b10000000;
+    pub const TWO_B: u8   = 0b11000000;
+    pub const THREE_B: u8 = 0b11100000;
+    pub const FOUR_B: u8  = 0b11110000;
+    pub const FIVE_B: u8  = 0b11111000;
+    pub const SIX_B: u8   = 0b11111100;
+    }
+
+    #[rustfmt::skip]
+    mod mask {
+    pub const CONT: u32    = 0b00111111;
+    pub const TWO_B: u32   = 0b00011111;
+    pub const THREE_B: u32 = 0b00001111;
+    pub const FOUR_B: u32  = 0b00000111;
+    pub const FIVE_B: u32  = 0b00000011;
+    pub const SIX_B: u32   = 0b00000001;
+    }
+
+    // These groupings are based on the layout of utf8 encoding, not bytes.
+    #[allow(clippy::unusual_byte_groupings)]
+    #[rustfmt::skip]
+    mod max {
+    pub const ONE_B: u32   = 0b10000000;
+    pub const TWO_B: u32   = 0b00100000__000000;
+    pub const THREE_B: u32 = 0b00010000__000000__000000;
+    pub const FOUR_B: u32  = 0b00001000__000000__000000__000000;
+    pub const FIVE_B: u32  = 0b00000100__000000__000000__000000__000000;
+    pub const SIX_B: u32   = 0b00000010__000000__000000__000000__000000__000000;
+    }
+
+    let len = if val < max::ONE_B {
+        1
+    } else if val < max::TWO_B {
+        2
+    } else if val < max::THREE_B {
+        3
+    } else if val < max::FOUR_B {
+        4
+    } else if val < max::FIVE_B {
+        5
+    } else if val < max::SIX_B {
+        6
+    } else {
+        return None;
+    };
+
+    let bytes = match len {
+        1 => [val as u8, 0, 0, 0, 0, 0],
+        2 => [
+            (val >> 6 & mask::TWO_B) as u8 | tag::TWO_B,
+            (val & mask::CONT) as u8 | tag::CONT,
+            0,
+            0,
+            0,
+            0,
+        ],
+        3 => [
+            (val >> 12 & mask::THREE_B) as u8 | tag::THREE_B,
+            (val >> 6 & mask::CONT) as u8 | tag::CONT,
+            (val & mask::CONT) as u8 | tag::CONT,
+            0,
+            0,
+            0,
+        ],
+        4 => [
+            (val >> 18 & mask::FOUR_B) as u8 | tag::FOUR_B,
+            (val >> 12 & mask::CONT) as u8 | tag::CONT,
+            (val >> 6 & mask::CONT) as u8 | tag::CONT,
+            (val & mask::CONT) as u8 | tag::CONT,
+            0,
+            0,
+        ],
+        5 => [
+            (val >> 24 & mask::FIVE_B) as u8 | tag::FIVE_B,
+            (val >> 18 & mask::CONT) as u8 | tag::CONT,
+            (val >> 12 & mask::CONT) as u8 | tag::CONT,
+            (val >> 6 & mask::CONT) as u8 | tag::CONT,
+            (val & mask::CONT) as u8 | tag::CONT,
+            0,
+        ],
+        6 => [
+            (val >> 30 & mask::SIX_B) as u8 | tag::SIX_B,
+            (val >> 24 & mask::CONT) as u8 | tag::CONT,
+            (val >> 18 & mask::CONT) as u8 | tag::CONT,
+            (val >> 12 & mask::CONT) as u8 | tag::CONT,
+            (val >> 6 & mask::CONT) as u8 | tag::CONT,
+            (val & mask::CONT) as u8 | tag::CONT,
+        ],
+        _ => unreachable!(),
+    };
+
+    Some((len, bytes))
+}
+
+/// Decodes the utf8 sequence at the start of `bytes`, returning the value and
+/// the length of the sequence.
+///
+/// Sequences of up to 6 bytes are accepted, matching [`encode_utf8_raw`]. If
+/// `strict` is set, values which aren't unicode scalar values (surrogates and
+/// values past `0x10FFFF`) are rejected. Overlong encodings are always
+/// rejected.
+pub fn decode_utf8_raw(bytes: &[u8], strict: bool) -> Option<(u32, usize)> {
+    // The smallest value which needs a sequence of each length, indexed by the
+    // number of continuation bytes.
+    const LIMITS: [u32; 6] = [0, 0x80, 0x800, 0x10000, 0x200000, 0x4000000];
+
+    let &lead = bytes.first()?;
+    if lead < 0x80 {
+        return Some((u32::from(lead), 1));
+    }
+
+    // The number of leading one bits is the length of the sequence, and a
+    // continuation byte can't start one.
+    let len = lead.leading_ones() as usize;
+    if !(2..=6).contains(&len) {
+        return None;
+    }
+
+    let mut val = u32::from(lead) & (0x7F >> len);
+    for &cont in bytes.get(1..len)? {
+        if cont & 0xC0 != 0x80 {
+            return None;
+        }
+        val = (val << 6) | u32::from(cont & 0x3F);
+    }
+
+    if val < LIMITS[len - 1] || val > 0x7FFFFFFF {
+        return None;
+    }
+    if strict && char::from_u32(val).is_none() {
+        return None;
+    }
+
+    Some((val, len))
+}
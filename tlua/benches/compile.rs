@@ -0,0 +1,66 @@
+use criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    Criterion,
+};
+use indoc::formatdoc;
+use tlua::compile;
+use tlua_parser::{
+    parse_chunk,
+    ASTAllocator,
+    StringTable,
+};
+
+/// Builds a script with many functions, each containing the kinds of lists the
+/// parser produces: parameters, arguments, table fields, and statements.
+fn generate_script(functions: usize) -> String {
+    let mut src = String::new();
+
+    for i in 0..functions {
+        src.push_str(&formatdoc! {"
+            local function f{i}(a, b, c, ...)
+                local t = {{ a, b, c, x = {i}, y = 'name{i}', [a] = b * {i} }}
+                local sum = 0
+                for j = 1, #t do
+                    if t[j] and t[j] > {i} then
+                        sum = sum + t[j] * 2
+                    elseif t[j] then
+                        sum = sum - t[j]
+                    else
+                        sum = sum .. ''
+                    end
+                end
+                return sum, t.x, t.y, ...
+            end
+            local r{i} = f{i}(1, 2, 3, 4, 5)
+        "});
+    }
+
+    src
+}
+
+fn parse_large_script(c: &mut Criterion) {
+    let src = generate_script(200);
+
+    c.bench_function("parse_large_script", |b| {
+        b.iter(|| {
+            let alloc = ASTAllocator::default();
+            let mut strings = StringTable::default();
+            parse_chunk(black_box(&src), &alloc, &mut strings)
+                .map(|block| block.statements.len())
+                .expect("Valid benchmark source")
+        })
+    });
+}
+
+fn compile_large_script(c: &mut Criterion) {
+    let src = generate_script(200);
+
+    c.bench_function("compile_large_script", |b| {
+        b.iter(|| compile(black_box(&src)).expect("Valid benchmark source"))
+    });
+}
+
+criterion_group!(benches, parse_large_script, compile_large_script);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use std::rc::Rc;
+
+use criterion::{
+    criterion_group,
+    criterion_main,
+    Criterion,
+};
+use indoc::indoc;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+fn concat_chain(c: &mut Criterion) {
+    let src = indoc! {"
+        local a, b, c, d, e = 'alpha', 'beta', 'gamma', 'delta', 'epsilon'
+        local s
+        local i = 1
+        while i <= 1000 do
+            s = a .. b .. c .. d .. e .. i .. a .. b .. c .. d
+            i = i + 1
+        end
+        return s
+    "};
+
+    let chunk = Rc::new(compile(src).expect("Valid benchmark source"));
+
+    c.bench_function("concat_chain", |b| {
+        b.iter(|| {
+            let mut rt = Runtime::default();
+            rt.execute(Rc::clone(&chunk)).expect("Benchmark executes")
+        })
+    });
+}
+
+criterion_group!(benches, concat_chain);
+criterion_main!(benches);
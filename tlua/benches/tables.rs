@@ -0,0 +1,67 @@
+use std::rc::Rc;
+
+use criterion::{
+    criterion_group,
+    criterion_main,
+    Criterion,
+};
+use indoc::indoc;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+fn sequence_access(c: &mut Criterion) {
+    let src = indoc! {"
+        local t = {}
+        local i = 1
+        while i <= 10000 do
+            t[#t + 1] = i
+            i = i + 1
+        end
+
+        local sum = 0
+        i = 1
+        while i <= #t do
+            sum = sum + t[i]
+            i = i + 1
+        end
+        return sum
+    "};
+
+    let chunk = Rc::new(compile(src).expect("Valid benchmark source"));
+
+    c.bench_function("sequence_access", |b| {
+        b.iter(|| {
+            let mut rt = Runtime::default();
+            rt.execute(Rc::clone(&chunk)).expect("Benchmark executes")
+        })
+    });
+}
+
+fn sequence_hole(c: &mut Criterion) {
+    let src = indoc! {"
+        local t = {}
+        for i = 1, 20000 do
+            t[i] = i
+        end
+
+        for i = 2, 100 do
+            t[i] = nil
+            t[i] = i
+        end
+        return #t
+    "};
+
+    let chunk = Rc::new(compile(src).expect("Valid benchmark source"));
+
+    c.bench_function("sequence_hole", |b| {
+        b.iter(|| {
+            let mut rt = Runtime::default();
+            rt.execute(Rc::clone(&chunk)).expect("Benchmark executes")
+        })
+    });
+}
+
+criterion_group!(benches, sequence_access, sequence_hole);
+criterion_main!(benches);
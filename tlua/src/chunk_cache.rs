@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    hash::{
+        Hash,
+        Hasher,
+    },
+    num::NonZeroUsize,
+    rc::Rc,
+};
+
+use tlua_compiler::{
+    compile,
+    Chunk,
+    CompileError,
+};
+
+/// A cache of compiled chunks, keyed by their source code, for hosts which
+/// execute the same scripts repeatedly.
+///
+/// At most `capacity` chunks are kept. Once the cache is full, compiling a new
+/// source evicts the chunk which was used least recently.
+#[derive(Debug)]
+pub struct ChunkCache {
+    capacity: NonZeroUsize,
+    entries: HashMap<u64, CacheEntry>,
+    /// Incremented on every access to order entries by their last use.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    // Kept to detect hash collisions between different sources.
+    source: Box<str>,
+    chunk: Rc<Chunk>,
+    last_used: u64,
+}
+
+impl ChunkCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity.get()),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the compiled chunk for `src`, compiling it if it isn't already
+    /// cached. Sources which fail to compile aren't cached.
+    pub fn get_or_compile(&mut self, src: &str) -> Result<Rc<Chunk>, CompileError> {
+        self.clock += 1;
+        let key = source_hash(src);
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            if &*entry.source == src {
+                self.hits += 1;
+                entry.last_used = self.clock;
+                return Ok(entry.chunk.clone());
+            }
+        }
+
+        self.misses += 1;
+        let chunk = Rc::new(compile(src)?);
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity.get() {
+            self.evict_least_recently_used();
+        }
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                source: src.into(),
+                chunk: chunk.clone(),
+                last_used: self.clock,
+            },
+        );
+
+        Ok(chunk)
+    }
+
+    /// The number of chunks currently cached.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every cached chunk. Chunks which are still referenced elsewhere
+    /// stay alive.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The number of lookups which found an already compiled chunk.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// The number of lookups which had to compile their source.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        // Caches are expected to be small, so a scan is cheaper than maintaining a
+        // separate recency list.
+        if let Some(&key) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key)
+        {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+fn source_hash(src: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::default();
+    src.hash(&mut hasher);
+    hasher.finish()
+}
@@ -2,24 +2,74 @@ use thiserror::Error;
 pub use tlua_bytecode::OpError;
 use tlua_parser::ChunkParseError;
 
+mod chunk_cache;
 pub mod vm;
 
 pub use tlua_compiler::{
     compile,
+    compile_named,
+    compile_with,
+    compile_with_diagnostics,
+    compile_with_options,
     Chunk,
+    CompilerOptions,
+    Diagnostic,
+    DiagnosticKind,
+    FuncId,
+    FunctionBuilder,
+    FunctionInfo,
+    Label,
+    LineTableEntry,
+    ParseOptions,
+    SourceLocation,
+    StringTable,
+    UnicodeEscapes,
+    UnknownEscapes,
 };
 
+pub use crate::chunk_cache::ChunkCache;
+use crate::vm::runtime::execution_context::ErrorLocation;
+
 #[derive(Debug, Error, Clone, PartialEq)]
 pub enum LuaError {
     #[error("syntax error: {0}")]
     SyntaxError(String),
-    #[error("vm execution error")]
-    ExecutionError(OpError),
+    #[error("vm execution error{}: {error}", error_site(.location, .chunk_name))]
+    ExecutionError {
+        error: OpError,
+        /// The statement which was executing when the error was raised, if it
+        /// is known.
+        location: Option<SourceLocation>,
+        /// The name of the chunk containing `location`, if it was given one.
+        chunk_name: Option<String>,
+    },
+}
+
+impl LuaError {
+    pub(crate) fn execution_error(error: OpError, location: Option<ErrorLocation>) -> Self {
+        let (location, chunk_name) = location.map_or((None, None), |(location, chunk_name)| {
+            (Some(location), chunk_name)
+        });
+
+        Self::ExecutionError {
+            error,
+            location,
+            chunk_name,
+        }
+    }
+}
+
+fn error_site(location: &Option<SourceLocation>, chunk_name: &Option<String>) -> String {
+    match (location, chunk_name) {
+        (Some(location), Some(chunk_name)) => format!(" at {}:{}", chunk_name, location.line),
+        (Some(location), None) => format!(" at line {}", location.line),
+        (None, _) => String::new(),
+    }
 }
 
 impl From<OpError> for LuaError {
-    fn from(err: OpError) -> Self {
-        Self::ExecutionError(err)
+    fn from(error: OpError) -> Self {
+        Self::execution_error(error, None)
     }
 }
 
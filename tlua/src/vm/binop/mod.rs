@@ -14,6 +14,7 @@ use tlua_bytecode::{
         Concat,
     },
     ImmediateRegister,
+    Number,
     OpError,
 };
 use tlua_strings::LuaString;
@@ -52,8 +53,11 @@ pub(crate) fn fp_op<Op: NumericOpEval + FloatBinop + OpName>(
     rhs: ImmediateRegister,
     registers: &Immediates,
 ) -> Result<Value, OpError> {
-    match registers[lhs] {
-        Value::Number(n) => Ok(Value::Number(Op::evaluate(&n, &registers[rhs])?)),
+    match (
+        arith_operand(&registers[lhs]),
+        arith_operand(&registers[rhs]),
+    ) {
+        (Some(lhs), Some(rhs)) => Ok(Value::Number(Op::evaluate(&lhs, &rhs)?)),
         _ => Err(OpError::InvalidType { op: Op::NAME }),
     }
 }
@@ -63,18 +67,32 @@ pub(crate) fn int_op<Op: NumericOpEval + IntBinop + OpName>(
     rhs: ImmediateRegister,
     registers: &Immediates,
 ) -> Result<Value, OpError> {
-    match registers[lhs] {
-        Value::Number(lhs) => Ok(Value::Number(Op::evaluate(&lhs, &registers[rhs])?)),
-        _ => Err(OpError::InvalidType { op: Op::NAME }),
+    let (lhs, rhs) = (&registers[lhs], &registers[rhs]);
+    match (arith_operand(lhs), arith_operand(rhs)) {
+        (Some(lhs), Some(rhs)) => Ok(Value::Number(Op::evaluate(&lhs, &rhs)?)),
+        (Some(_), _) => Err(OpError::BitwiseOnNonNumber {
+            ty: rhs.type_name(),
+        }),
+        _ => Err(OpError::BitwiseOnNonNumber {
+            ty: lhs.type_name(),
+        }),
     }
 }
 
-pub(crate) fn concat_op(
-    lhs: ImmediateRegister,
-    rhs: ImmediateRegister,
-    registers: &Immediates,
-) -> Result<Value, OpError> {
-    match (&registers[lhs], &registers[rhs]) {
+/// Converts an operand of an arithmetic or bitwise operator to a number,
+/// coercing strings which hold a numeral.
+fn arith_operand(value: &Value) -> Option<Number> {
+    match value {
+        Value::Number(n) => Some(*n),
+        Value::String(s) => Number::parse_lua(&s.borrow()),
+        _ => None,
+    }
+}
+
+/// Concatenates a pair of strings or numbers. Any other operands are an error,
+/// which the caller may handle with a `__concat` metamethod.
+pub(crate) fn concat_op(lhs: &Value, rhs: &Value) -> Result<Value, OpError> {
+    match (lhs, rhs) {
         (Value::Number(lhs), Value::Number(rhs)) => {
             Ok(Concat::evaluate(LuaString::from(lhs), LuaString::from(rhs)))
         }
@@ -90,3 +108,27 @@ pub(crate) fn concat_op(
         _ => Err(OpError::InvalidType { op: Concat::NAME }),
     }
 }
+
+/// Concatenates every operand into a single string, rather than building an
+/// intermediate string for each pair as repeated [`concat_op`]s would.
+pub(crate) fn concat_n_op(operands: &[Value]) -> Result<Value, OpError> {
+    let len = operands
+        .iter()
+        .map(|operand| match operand {
+            Value::String(s) => s.borrow().len(),
+            _ => 0,
+        })
+        .sum();
+
+    let mut res = LuaString::default();
+    res.reserve_exact(len);
+    for operand in operands {
+        match operand {
+            Value::Number(num) => res.extend_from_slice(&LuaString::from(num)),
+            Value::String(s) => res.extend_from_slice(&s.borrow()),
+            _ => return Err(OpError::InvalidType { op: Concat::NAME }),
+        }
+    }
+
+    Ok(res.into())
+}
@@ -0,0 +1,408 @@
+use tlua_bytecode::{
+    NumLike,
+    Number,
+    OpError,
+};
+use tlua_compiler::compile;
+use tlua_strings::LuaString;
+
+use crate::vm::runtime::{
+    collect_garbage,
+    execution_context::Context,
+    memory,
+    Gc,
+    NativeFunction,
+    Table,
+    Value,
+};
+
+/// The metatable field which hides a metatable from `getmetatable` and
+/// prevents `setmetatable` from replacing it.
+const METATABLE_FIELD: &str = "__metatable";
+
+/// The signature used by precompiled chunks, which aren't supported.
+const BINARY_CHUNK_SIGNATURE: &[u8] = b"\x1bLua";
+
+/// `collectgarbage([opt [, arg]])`
+///
+/// With `"collect"`, the default, frees every unreachable value and returns 0.
+/// With `"count"`, returns the approximate memory used by tables and functions
+/// in kilobytes. Collection isn't incremental, so `"step"` performs a full
+/// collection, ignoring `arg`, and returns true to report that a cycle
+/// finished. Any other option does nothing and returns 0.
+pub(super) fn collectgarbage(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let option = match args.first() {
+        None | Some(Value::Nil) => LuaString::from("collect"),
+        Some(Value::String(option)) => option.borrow().clone(),
+        _ => {
+            return Err(OpError::InvalidType {
+                op: "collectgarbage",
+            })
+        }
+    };
+
+    match option.as_slice() {
+        b"collect" => {
+            collect_garbage();
+            Ok(vec![0.into()])
+        }
+        b"count" => Ok(vec![(memory::live_bytes() as f64 / 1024.0).into()]),
+        b"step" => {
+            collect_garbage();
+            Ok(vec![true.into()])
+        }
+        _ => Ok(vec![0.into()]),
+    }
+}
+
+/// `getmetatable(v)`
+///
+/// Returns the `__metatable` field of `v`'s metatable if it has one, otherwise
+/// the metatable itself.
+pub(super) fn getmetatable(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let metatable = match args.into_iter().next() {
+        Some(Value::Table(t)) => t.borrow().metatable.clone(),
+        _ => None,
+    };
+
+    let result = match metatable {
+        Some(metatable) => match metatable.borrow().get(&METATABLE_FIELD.into()) {
+            Value::Nil => Value::Table(metatable.clone()),
+            protected => protected,
+        },
+        None => Value::Nil,
+    };
+
+    Ok(vec![result])
+}
+
+/// `ipairs(t)`
+///
+/// Returns an iterator over `t[1]`, `t[2]`, ... up to the first nil value,
+/// along with `t` and the initial index. Like any other access, each read
+/// consults `__index`, so the iteration can continue past the end of the
+/// table's own sequence.
+pub(super) fn ipairs(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let table = match args.into_iter().next() {
+        Some(table @ Value::Table(_)) => table,
+        _ => return Err(OpError::InvalidType { op: "ipairs" }),
+    };
+
+    Ok(vec![
+        NativeFunction::new("ipairs_next", ipairs_next).into(),
+        table,
+        0.into(),
+    ])
+}
+
+fn ipairs_next(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let table = args.next().unwrap_or_default();
+    let index = (&args.next().unwrap_or_default())
+        .as_int()
+        .and_then(|index| index.checked_add(1))
+        .ok_or(OpError::InvalidType { op: "ipairs" })?;
+
+    match context.index(table, index.into())? {
+        Value::Nil => Ok(vec![Value::Nil]),
+        value => Ok(vec![index.into(), value]),
+    }
+}
+
+/// `load(chunk)`
+///
+/// Compiles `chunk`, which is either a string or a function returning pieces
+/// of the source until it returns nil or an empty string. Returns the compiled
+/// chunk as a function, or `nil` and an error message if it could not be
+/// compiled.
+pub(super) fn load(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let source = match args.into_iter().next().unwrap_or_default() {
+        Value::String(s) => s.borrow().clone(),
+        reader @ (Value::Function(_) | Value::NativeFunction(_)) => {
+            let mut source = LuaString::default();
+            loop {
+                match context.call(&reader, vec![])?.into_iter().next() {
+                    None | Some(Value::Nil) => break,
+                    Some(Value::String(piece)) if piece.borrow().is_empty() => break,
+                    Some(Value::String(piece)) => source.extend_from_slice(&piece.borrow()),
+                    Some(_) => return Ok(load_error("reader function must return a string")),
+                }
+            }
+            source
+        }
+        _ => return Err(OpError::InvalidType { op: "load" }),
+    };
+
+    if source.starts_with(BINARY_CHUNK_SIGNATURE) {
+        return Ok(load_error("attempt to load a binary chunk"));
+    }
+
+    let source = match std::str::from_utf8(&source) {
+        Ok(source) => source,
+        Err(_) => return Ok(load_error("chunk is not valid utf-8")),
+    };
+
+    match compile(source) {
+        Ok(chunk) => Ok(vec![context.load_chunk(chunk)]),
+        Err(err) => Ok(load_error(&err.to_string())),
+    }
+}
+
+fn load_error(msg: &str) -> Vec<Value> {
+    vec![Value::Nil, msg.into()]
+}
+
+/// `next(t [, k])`
+///
+/// Returns the entry following `k` when traversing `t`, or its first entry if
+/// `k` is nil. Returns nil once every entry has been visited. The sequence is
+/// visited in order, followed by the remaining entries in the order they were
+/// added.
+pub(super) fn next(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let table = table_arg(args.next(), "next")?;
+    let key = args.next().unwrap_or_default();
+
+    let entry = table.borrow().next(&key)?;
+    match entry {
+        Some((key, value)) => Ok(vec![key, value]),
+        None => Ok(vec![Value::Nil]),
+    }
+}
+
+/// `pairs(t)`
+///
+/// Returns `next`, `t`, and nil, so that a generic for loop visits every entry
+/// of `t`.
+pub(super) fn pairs(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let table = match args.into_iter().next() {
+        Some(table @ Value::Table(_)) => table,
+        _ => return Err(OpError::InvalidType { op: "pairs" }),
+    };
+
+    Ok(vec![
+        NativeFunction::new("next", next).into(),
+        table,
+        Value::Nil,
+    ])
+}
+
+/// `pcall(f, ...)`
+///
+/// Calls `f` with the remaining arguments. If the call succeeds, returns true
+/// followed by its results. Otherwise returns false and the error message.
+pub(super) fn pcall(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let func = args.next().unwrap_or_default();
+
+    match context.protected_call(&func, args.collect()) {
+        Ok(results) => Ok(std::iter::once(Value::Bool(true)).chain(results).collect()),
+        Err(error) => Ok(vec![Value::Bool(false), error]),
+    }
+}
+
+/// `rawequal(a, b)`
+///
+/// Compares `a` and `b` without consulting `__eq`. Tables and functions are
+/// only equal to themselves.
+pub(super) fn rawequal(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let (lhs, rhs) = (
+        args.next().unwrap_or_default(),
+        args.next().unwrap_or_default(),
+    );
+
+    let equal = match (&lhs, &rhs) {
+        (Value::Table(l), Value::Table(r)) => std::ptr::eq(&*l.borrow(), &*r.borrow()),
+        (Value::Function(l), Value::Function(r)) => std::ptr::eq(&*l.borrow(), &*r.borrow()),
+        _ => lhs == rhs,
+    };
+
+    Ok(vec![equal.into()])
+}
+
+/// `rawget(t, k)`
+///
+/// Reads `t[k]` without consulting `__index`.
+pub(super) fn rawget(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let table = table_arg(args.next(), "rawget")?;
+    let key = args.next().unwrap_or_default();
+
+    let value = table.borrow().get(&key);
+    Ok(vec![value])
+}
+
+/// `rawlen(v)`
+///
+/// Returns the length of a table or string without consulting `__len`.
+pub(super) fn rawlen(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    match args.into_iter().next().unwrap_or_default() {
+        Value::Table(t) => Ok(vec![t.borrow().border().into()]),
+        Value::String(s) => i64::try_from(s.borrow().len())
+            .map_err(|_| OpError::StringLengthOutOfBounds)
+            .map(|len| vec![len.into()]),
+        _ => Err(OpError::InvalidType { op: "rawlen" }),
+    }
+}
+
+/// `rawset(t, k, v)`
+///
+/// Assigns `t[k] = v` without consulting `__newindex`, returning `t`.
+pub(super) fn rawset(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let table = table_arg(args.next(), "rawset")?;
+    let key = args.next().unwrap_or_default();
+    let value = args.next().unwrap_or_default();
+
+    table.borrow_mut().set(key, value)?;
+    Ok(vec![Value::Table(table)])
+}
+
+/// `select(n, ...)`
+///
+/// Returns all arguments after argument `n`, counting from the end if `n` is
+/// negative, or the number of extra arguments if `n` is `'#'`.
+pub(super) fn select(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let index = args.next().unwrap_or_default();
+    let rest = args.as_slice();
+
+    if let Value::String(s) = &index {
+        if s.borrow().as_slice() == b"#" {
+            return i64::try_from(rest.len())
+                .map_err(|_| OpError::InvalidType { op: "select" })
+                .map(|count| vec![count.into()]);
+        }
+    }
+
+    let start = match (&index).as_int() {
+        Some(index) if index > 0 => usize::try_from(index - 1).unwrap_or(usize::MAX),
+        Some(index) if index < 0 => usize::try_from(index.unsigned_abs())
+            .ok()
+            .and_then(|from_end| rest.len().checked_sub(from_end))
+            .ok_or(OpError::InvalidType { op: "select" })?,
+        _ => return Err(OpError::InvalidType { op: "select" }),
+    };
+
+    Ok(rest.iter().skip(start).cloned().collect())
+}
+
+/// `setmetatable(t, mt)`
+///
+/// Sets the metatable of `t` to `mt`, or removes it if `mt` is nil. Returns
+/// `t`.
+pub(super) fn setmetatable(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let table = table_arg(args.next(), "setmetatable")?;
+    let metatable = match args.next() {
+        Some(Value::Table(mt)) => Some(mt),
+        Some(Value::Nil) => None,
+        _ => return Err(OpError::InvalidType { op: "setmetatable" }),
+    };
+
+    if let Some(current) = &table.borrow().metatable {
+        if current.borrow().get(&METATABLE_FIELD.into()) != Value::Nil {
+            return Err(OpError::ProtectedMetatable);
+        }
+    }
+
+    Table::set_metatable(&table, metatable);
+    Ok(vec![Value::Table(table)])
+}
+
+/// `tonumber(e [, base])`
+///
+/// Converts `e` to a number, returning `nil` if it isn't a number or a string
+/// holding a numeral. With a `base` between 2 and 36, `e` must be a string
+/// holding an integer written in that base, where letters stand for the
+/// digits from 10 upwards.
+pub(super) fn tonumber(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let number = match (args.first(), args.get(1)) {
+        (Some(Value::Number(n)), None | Some(Value::Nil)) => Some(*n),
+        (Some(Value::String(s)), None | Some(Value::Nil)) => Number::parse_lua(&s.borrow()),
+        (Some(_), None | Some(Value::Nil)) => None,
+        (Some(Value::String(s)), Some(base)) => {
+            let base = base
+                .as_int()
+                .and_then(|base| u32::try_from(base).ok())
+                .filter(|base| (2..=36).contains(base))
+                .ok_or(OpError::InvalidType { op: "tonumber" })?;
+
+            parse_int_in_base(&s.borrow(), base).map(Number::Integer)
+        }
+        _ => return Err(OpError::InvalidType { op: "tonumber" }),
+    };
+
+    Ok(vec![number.map_or(Value::Nil, Value::Number)])
+}
+
+/// `tostring(v)`
+///
+/// Converts `v` to a string. Numbers are rendered the same way as when they
+/// are concatenated, and tables & functions by their address.
+pub(super) fn tostring(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let string = match args.first() {
+        Some(Value::String(s)) => s.borrow().clone(),
+        Some(Value::Number(n)) => n.to_lua_string(),
+        Some(value) => LuaString::from(value.to_string().as_str()),
+        None => return Err(OpError::InvalidType { op: "tostring" }),
+    };
+
+    Ok(vec![string.into()])
+}
+
+/// `xpcall(f, handler, ...)`
+///
+/// Like `pcall`, but if the call fails, `handler` is called with the error
+/// message and false is returned along with the first result of the handler.
+/// An error raised by the handler replaces the original error.
+pub(super) fn xpcall(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+    let func = args.next().unwrap_or_default();
+    let handler = args.next().unwrap_or_default();
+
+    match context.protected_call(&func, args.collect()) {
+        Ok(results) => Ok(std::iter::once(Value::Bool(true)).chain(results).collect()),
+        Err(error) => {
+            let handled = context.protected_call(&handler, vec![error]).map_or_else(
+                |error| error,
+                |results| results.into_iter().next().unwrap_or_default(),
+            );
+
+            Ok(vec![Value::Bool(false), handled])
+        }
+    }
+}
+
+/// Parses an integer written in `base`, optionally negated and surrounded by
+/// whitespace. Like LUA, values which don't fit wrap around.
+fn parse_int_in_base(src: &[u8], base: u32) -> Option<i64> {
+    let src = src.trim_ascii();
+    let (negative, digits) = match src.split_first() {
+        Some((b'-', rest)) => (true, rest),
+        _ => (false, src),
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    let value = digits.iter().try_fold(0i64, |value, &digit| {
+        let digit = char::from(digit).to_digit(base)?;
+        Some(value.wrapping_mul(base.into()).wrapping_add(digit.into()))
+    })?;
+
+    Some(if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    })
+}
+
+fn table_arg(arg: Option<Value>, op: &'static str) -> Result<Gc<Table>, OpError> {
+    match arg {
+        Some(Value::Table(t)) => Ok(t),
+        _ => Err(OpError::InvalidType { op }),
+    }
+}
@@ -0,0 +1,44 @@
+use tlua_bytecode::OpError;
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Gc,
+    Value,
+};
+
+/// `io.write(...)`
+///
+/// Writes each argument, which must be a string or a number, to the runtime's
+/// output without any separators. Numbers are formatted the same way as by
+/// `tostring`. Returns the `io` table so that calls may be chained with
+/// `io.write(...):write(...)`.
+pub(super) fn write(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let io = context.load_global("io").unwrap_or_default();
+
+    // A chained call passes the `io` table as its first argument.
+    let args = match (args.split_first(), &io) {
+        (Some((Value::Table(receiver), rest)), Value::Table(io)) if Gc::ptr_eq(receiver, io) => {
+            rest
+        }
+        _ => &args[..],
+    };
+
+    for arg in args {
+        let result = match arg {
+            Value::String(s) => context.stdout().write_all(s.borrow().as_slice()),
+            Value::Number(_) => context.stdout().write_all(arg.to_string().as_bytes()),
+            _ => return Err(OpError::InvalidType { op: "write" }),
+        };
+
+        result.map_err(|err| OpError::WriteFailed { kind: err.kind() })?;
+    }
+
+    Ok(vec![io])
+}
+
+/// `io.read(...)`
+///
+/// Input isn't supported, so this always returns `nil`.
+pub(super) fn read(_: &mut Context, _: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    Ok(vec![Value::Nil])
+}
@@ -0,0 +1,142 @@
+use tlua_bytecode::{
+    binop::f64inbounds,
+    NumLike,
+    Number,
+    OpError,
+};
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    random::RandomState,
+    Value,
+};
+
+/// `math.random([m [, n]])`
+///
+/// With no arguments, returns a float uniformly distributed in `[0, 1)`. With
+/// two arguments, returns an integer uniformly distributed in `[m, n]`; a
+/// single argument is the same as `math.random(1, m)`. `math.random(0)`
+/// returns an integer with all bits random.
+pub(super) fn random(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let rng = context.rng();
+
+    let (low, high) = match args.as_slice() {
+        [] => return Ok(vec![rng.next_float().into()]),
+        [high] => match int_arg(high)? {
+            0 => return Ok(vec![(rng.next_u64() as i64).into()]),
+            high => (1, high),
+        },
+        [low, high, ..] => (int_arg(low)?, int_arg(high)?),
+    };
+
+    if low > high {
+        return Err(OpError::BadArgument {
+            arg: 1,
+            func: "random",
+            msg: "interval is empty",
+        });
+    }
+
+    Ok(vec![rng.next_in_range(low, high).into()])
+}
+
+/// `math.randomseed([x [, y]])`
+///
+/// Seeds the generator used by `math.random` with `x` and `y`, so that the
+/// same seed always produces the same sequence. Without arguments the seed is
+/// taken from the current time, or a fixed default without the `os` feature.
+pub(super) fn randomseed(context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let (seed, extra) = match args.as_slice() {
+        [] => (default_seed(), 0),
+        [seed] => (seed_arg(seed)?, 0),
+        [seed, extra, ..] => (seed_arg(seed)?, int_arg(extra)?),
+    };
+
+    *context.rng() = RandomState::with_seed(seed, extra);
+
+    Ok(vec![])
+}
+
+/// `math.fmod(x, y)`
+///
+/// Returns the remainder of dividing `x` by `y`, rounding the quotient towards
+/// zero. Unlike `x % y`, the result has the sign of `x`. The result is an
+/// integer if both arguments are integers, in which case `y` can't be 0.
+pub(super) fn fmod(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let (lhs, rhs) = match (args.first(), args.get(1)) {
+        (Some(Value::Number(lhs)), Some(Value::Number(rhs))) => (*lhs, *rhs),
+        _ => return Err(OpError::InvalidType { op: "fmod" }),
+    };
+
+    let rem = match (lhs, rhs) {
+        (Number::Integer(_), Number::Integer(0)) => {
+            return Err(OpError::BadArgument {
+                arg: 2,
+                func: "fmod",
+                msg: "zero",
+            });
+        }
+        (Number::Integer(lhs), Number::Integer(rhs)) => Number::Integer(lhs.wrapping_rem(rhs)),
+        (lhs, rhs) => Number::Float(
+            (&lhs).as_float().unwrap_or_default() % (&rhs).as_float().unwrap_or_default(),
+        ),
+    };
+
+    Ok(vec![Value::Number(rem)])
+}
+
+/// `math.tointeger(x)`
+///
+/// Returns `x` as an integer if it is a number with an integral value that
+/// fits in an integer, otherwise `nil`.
+pub(super) fn tointeger(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let int = match args.first() {
+        Some(&Value::Number(Number::Integer(i))) => i.into(),
+        Some(&Value::Number(Number::Float(f))) => f64inbounds(f).map_or(Value::Nil, Value::from),
+        Some(_) => Value::Nil,
+        None => return Err(OpError::InvalidType { op: "tointeger" }),
+    };
+
+    Ok(vec![int])
+}
+
+/// `math.type(x)`
+///
+/// Returns `"integer"` or `"float"` depending on the subtype of the number
+/// `x`, or `nil` if `x` isn't a number. Strings aren't converted.
+pub(super) fn type_(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let subtype = match args.first() {
+        Some(Value::Number(Number::Integer(_))) => "integer".into(),
+        Some(Value::Number(Number::Float(_))) => "float".into(),
+        Some(_) => Value::Nil,
+        None => return Err(OpError::InvalidType { op: "type" }),
+    };
+
+    Ok(vec![subtype])
+}
+
+fn int_arg(value: &Value) -> Result<i64, OpError> {
+    value.as_int().ok_or(OpError::InvalidType { op: "random" })
+}
+
+fn seed_arg(value: &Value) -> Result<i64, OpError> {
+    match value {
+        // Any number is accepted as a seed, not only those with an integer value.
+        Value::Number(_) => Ok(value
+            .as_int()
+            .unwrap_or_else(|| value.as_float().unwrap_or_default().to_bits() as i64)),
+        _ => Err(OpError::InvalidType { op: "randomseed" }),
+    }
+}
+
+#[cfg(feature = "os")]
+fn default_seed() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as i64)
+}
+
+#[cfg(not(feature = "os"))]
+fn default_seed() -> i64 {
+    crate::vm::runtime::random::DEFAULT_SEED
+}
@@ -0,0 +1,110 @@
+use tlua_bytecode::OpError;
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Gc,
+    NativeFunction,
+    Runtime,
+    Table,
+    Value,
+};
+
+mod base;
+#[cfg(feature = "io")]
+mod io;
+mod math;
+#[cfg(feature = "os")]
+mod os;
+mod string;
+mod table;
+mod utf8;
+
+type Builtin = for<'call> fn(&mut Context<'call>, Vec<Value>) -> Result<Vec<Value>, OpError>;
+
+const BASE_FUNCTIONS: &[(&str, Builtin)] = &[
+    ("collectgarbage", base::collectgarbage),
+    ("getmetatable", base::getmetatable),
+    ("ipairs", base::ipairs),
+    ("load", base::load),
+    ("loadstring", base::load),
+    ("next", base::next),
+    ("pairs", base::pairs),
+    ("pcall", base::pcall),
+    ("rawequal", base::rawequal),
+    ("rawget", base::rawget),
+    ("rawlen", base::rawlen),
+    ("rawset", base::rawset),
+    ("select", base::select),
+    ("setmetatable", base::setmetatable),
+    ("tonumber", base::tonumber),
+    ("tostring", base::tostring),
+    ("xpcall", base::xpcall),
+];
+
+#[cfg(feature = "io")]
+const IO_FUNCTIONS: &[(&str, Builtin)] = &[("read", io::read), ("write", io::write)];
+
+const MATH_FUNCTIONS: &[(&str, Builtin)] = &[
+    ("fmod", math::fmod),
+    ("random", math::random),
+    ("randomseed", math::randomseed),
+    ("tointeger", math::tointeger),
+    ("type", math::type_),
+];
+
+#[cfg(feature = "os")]
+const OS_FUNCTIONS: &[(&str, Builtin)] = &[
+    ("date", os::date),
+    ("difftime", os::difftime),
+    ("time", os::time),
+];
+
+const STRING_FUNCTIONS: &[(&str, Builtin)] = &[
+    ("byte", string::byte),
+    ("char", string::char),
+    ("sub", string::sub),
+];
+
+const TABLE_FUNCTIONS: &[(&str, Builtin)] = &[("pack", table::pack)];
+
+const UTF8_FUNCTIONS: &[(&str, Builtin)] = &[
+    ("char", utf8::char),
+    ("codepoint", utf8::codepoint),
+    ("len", utf8::len),
+    ("offset", utf8::offset),
+];
+
+/// Registers the standard library functions which are available to every
+/// chunk executed by `runtime`.
+pub(crate) fn register(runtime: &mut Runtime) {
+    for &(name, func) in BASE_FUNCTIONS {
+        runtime.register_global(name, NativeFunction::new(name, func));
+    }
+
+    #[cfg(feature = "io")]
+    runtime.register_global("io", library(IO_FUNCTIONS));
+
+    runtime.register_global("math", library(MATH_FUNCTIONS));
+
+    #[cfg(feature = "os")]
+    runtime.register_global("os", library(OS_FUNCTIONS));
+
+    runtime.register_global("string", library(STRING_FUNCTIONS));
+
+    runtime.register_global("table", library(TABLE_FUNCTIONS));
+
+    runtime.register_global("utf8", library(UTF8_FUNCTIONS));
+}
+
+/// Builds the table through which the members of a library are accessed, e.g.
+/// `os.time`.
+fn library(functions: &[(&'static str, Builtin)]) -> Value {
+    let mut table = Table::default();
+    for &(name, func) in functions {
+        table
+            .set(name.into(), NativeFunction::new(name, func).into())
+            .expect("Library function names are valid keys");
+    }
+
+    Value::Table(Gc::new(table))
+}
@@ -0,0 +1,140 @@
+use chrono::{
+    format::{
+        Item,
+        StrftimeItems,
+    },
+    DateTime,
+    Datelike,
+    Local,
+    TimeZone,
+    Timelike,
+    Utc,
+};
+use tlua_bytecode::{
+    NumLike,
+    OpError,
+};
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Gc,
+    Table,
+    Value,
+};
+
+/// The format used by `os.date` when none is provided.
+const DEFAULT_DATE_FORMAT: &str = "%c";
+
+/// `os.date([format [, time]])`
+///
+/// Formats `time` (defaulting to the current time) in the local time zone, or
+/// in UTC if `format` starts with `!`. A format of `*t` produces a table with
+/// the fields `year`, `month`, `day`, `hour`, `min`, `sec`, `wday`, `yday`
+/// and `isdst`; any other format is interpreted like `strftime`.
+pub(super) fn date(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+
+    let format = match args.next().unwrap_or_default() {
+        Value::Nil => DEFAULT_DATE_FORMAT.to_string(),
+        Value::String(s) => String::from_utf8(s.borrow().to_vec())
+            .map_err(|_| OpError::InvalidType { op: "date" })?,
+        _ => return Err(OpError::InvalidType { op: "date" }),
+    };
+
+    let time = match args.next().unwrap_or_default() {
+        Value::Nil => Utc::now().timestamp(),
+        time => (&time)
+            .as_int()
+            .ok_or(OpError::InvalidType { op: "date" })?,
+    };
+
+    let result = match format.strip_prefix('!') {
+        Some(format) => format_date(
+            Utc.timestamp_opt(time, 0)
+                .single()
+                .ok_or(OpError::InvalidType { op: "date" })?,
+            format,
+        )?,
+        None => format_date(
+            Local
+                .timestamp_opt(time, 0)
+                .single()
+                .ok_or(OpError::InvalidType { op: "date" })?,
+            &format,
+        )?,
+    };
+
+    Ok(vec![result])
+}
+
+fn format_date<Tz: TimeZone>(date: DateTime<Tz>, format: &str) -> Result<Value, OpError>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if format == "*t" {
+        return Ok(Value::Table(Gc::new(date_table(&date)?)));
+    }
+
+    let items = StrftimeItems::new(format).collect::<Vec<_>>();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(OpError::InvalidType { op: "date" });
+    }
+
+    Ok(date
+        .format_with_items(items.into_iter())
+        .to_string()
+        .as_str()
+        .into())
+}
+
+fn date_table<Tz: TimeZone>(date: &DateTime<Tz>) -> Result<Table, OpError> {
+    let mut table = Table::default();
+
+    let fields = [
+        ("year", i64::from(date.year())),
+        ("month", i64::from(date.month())),
+        ("day", i64::from(date.day())),
+        ("hour", i64::from(date.hour())),
+        ("min", i64::from(date.minute())),
+        ("sec", i64::from(date.second())),
+        ("wday", i64::from(date.weekday().number_from_sunday())),
+        ("yday", i64::from(date.ordinal())),
+    ];
+
+    for (name, value) in fields {
+        table.set(name.into(), value.into())?;
+    }
+    // The time zone database isn't consulted for daylight saving information.
+    table.set("isdst".into(), false.into())?;
+
+    Ok(table)
+}
+
+/// `os.difftime(t2 [, t1])`
+///
+/// Returns the number of seconds from `t1` (defaulting to 0) to `t2`.
+pub(super) fn difftime(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut args = args.into_iter();
+
+    let end = (&args.next().unwrap_or_default())
+        .as_float()
+        .ok_or(OpError::InvalidType { op: "difftime" })?;
+    let start = match args.next().unwrap_or_default() {
+        Value::Nil => 0.0,
+        start => (&start)
+            .as_float()
+            .ok_or(OpError::InvalidType { op: "difftime" })?,
+    };
+
+    Ok(vec![(end - start).into()])
+}
+
+/// `os.time()`
+///
+/// Returns the current time as the number of seconds since the unix epoch.
+pub(super) fn time(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    match args.into_iter().next().unwrap_or_default() {
+        Value::Nil => Ok(vec![Utc::now().timestamp().into()]),
+        _ => Err(OpError::InvalidType { op: "time" }),
+    }
+}
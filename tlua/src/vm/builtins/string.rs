@@ -0,0 +1,95 @@
+use tlua_bytecode::{
+    NumLike,
+    OpError,
+};
+use tlua_strings::LuaString;
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Value,
+};
+
+/// `string.byte(s [, i [, j]])`
+///
+/// Returns the values of the bytes of `s` between the positions `i` and `j`,
+/// which may be negative to count from the end of the string. `i` defaults to
+/// 1 and `j` to `i`. Positions outside of the string are clamped to it.
+pub(super) fn byte(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let s = match args.first() {
+        Some(Value::String(s)) => s.borrow().clone(),
+        _ => return Err(OpError::InvalidType { op: "byte" }),
+    };
+    let len = s.len() as i64;
+
+    let start = relative_position(opt_int_arg(args.get(1), 1, "byte")?, len).max(1);
+    let end = relative_position(opt_int_arg(args.get(2), start, "byte")?, len).min(len);
+
+    if start > end {
+        return Ok(vec![]);
+    }
+
+    Ok(s[start as usize - 1..end as usize]
+        .iter()
+        .map(|&byte| i64::from(byte).into())
+        .collect())
+}
+
+/// `string.char(...)`
+///
+/// Returns a string containing one byte for each argument, each of which must
+/// be between 0 and 255.
+pub(super) fn char(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let bytes = args
+        .iter()
+        .map(|byte| {
+            byte.as_int()
+                .and_then(|byte| u8::try_from(byte).ok())
+                .ok_or(OpError::InvalidType { op: "char" })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(vec![LuaString::from(bytes.as_slice()).into()])
+}
+
+/// `string.sub(s, i [, j])`
+///
+/// Returns the substring of `s` between the positions `i` and `j`, which may
+/// be negative to count from the end of the string. `j` defaults to -1, the
+/// end of the string. Positions outside of the string are clamped to it.
+pub(super) fn sub(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let s = match args.first() {
+        Some(Value::String(s)) => s.borrow().clone(),
+        _ => return Err(OpError::InvalidType { op: "sub" }),
+    };
+    let len = s.len() as i64;
+
+    let start = relative_position(opt_int_arg(args.get(1), 1, "sub")?, len).max(1);
+    let end = relative_position(opt_int_arg(args.get(2), -1, "sub")?, len).min(len);
+
+    if start > end {
+        return Ok(vec!["".into()]);
+    }
+
+    Ok(vec![
+        LuaString::from(&s[start as usize - 1..end as usize]).into()
+    ])
+}
+
+/// Converts a 1-based position which counts from the end of the string if it
+/// is negative into one which counts from the start.
+fn relative_position(pos: i64, len: i64) -> i64 {
+    if pos >= 0 {
+        pos
+    } else if pos.unsigned_abs() > len as u64 {
+        0
+    } else {
+        len + pos + 1
+    }
+}
+
+fn opt_int_arg(arg: Option<&Value>, default: i64, op: &'static str) -> Result<i64, OpError> {
+    match arg {
+        None | Some(Value::Nil) => Ok(default),
+        Some(arg) => arg.as_int().ok_or(OpError::InvalidType { op }),
+    }
+}
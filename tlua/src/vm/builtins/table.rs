@@ -0,0 +1,25 @@
+use tlua_bytecode::OpError;
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Gc,
+    Table,
+    Value,
+};
+
+/// `table.pack(...)`
+///
+/// Returns a new table containing every argument at the indices 1, 2, ...,
+/// along with the number of arguments in the field `n`. Unlike `{...}`, the
+/// count is still available when some of the arguments are nil.
+pub(super) fn pack(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut table = Table::default();
+    let count = args.len() as i64;
+
+    for (index, value) in (1i64..).zip(args) {
+        table.set(index.into(), value)?;
+    }
+    table.set("n".into(), count.into())?;
+
+    Ok(vec![Value::Table(Gc::new(table))])
+}
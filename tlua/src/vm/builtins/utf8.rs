@@ -0,0 +1,195 @@
+use tlua_bytecode::{
+    NumLike,
+    OpError,
+    Truthy,
+};
+use tlua_strings::{
+    decode_utf8_raw,
+    encode_utf8_raw,
+    LuaString,
+};
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Value,
+};
+
+/// `utf8.char(...)`
+///
+/// Returns a string containing the utf8 encoding of each argument, which may
+/// be any value below 2^31.
+pub(super) fn char(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let mut string = LuaString::default();
+
+    for code in &args {
+        let (len, bytes) = int_arg(Some(code), "char")
+            .ok()
+            .and_then(|code| u32::try_from(code).ok())
+            .and_then(encode_utf8_raw)
+            .ok_or(OpError::InvalidType { op: "char" })?;
+
+        string.extend_from_slice(&bytes[..len]);
+    }
+
+    Ok(vec![string.into()])
+}
+
+/// `utf8.len(s [, i [, j [, lax]]])`
+///
+/// Returns the number of utf8 characters in `s` which start between the byte
+/// positions `i` and `j`, defaulting to the whole string. If an invalid byte
+/// sequence is found, returns `nil` and its position instead.
+pub(super) fn len(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let s = string_arg(args.first(), "len")?;
+    let bytes = s.as_slice();
+    let len = bytes.len() as i64;
+
+    let start = relative_position(opt_int_arg(args.get(1), 1, "len")?, len);
+    let end = relative_position(opt_int_arg(args.get(2), -1, "len")?, len);
+    let strict = !lax_arg(args.get(3));
+
+    if start < 1 || start - 1 > len || end > len {
+        return Err(OpError::InvalidType { op: "len" });
+    }
+
+    let mut pos = start - 1;
+    let mut count = 0i64;
+    while pos < end {
+        match decode_utf8_raw(&bytes[pos as usize..], strict) {
+            Some((_, seq_len)) => pos += seq_len as i64,
+            None => return Ok(vec![Value::Nil, (pos + 1).into()]),
+        }
+        count += 1;
+    }
+
+    Ok(vec![count.into()])
+}
+
+/// `utf8.codepoint(s [, i [, j [, lax]]])`
+///
+/// Returns the codepoints of the utf8 characters in `s` which start between
+/// the byte positions `i` and `j`. `i` defaults to 1 and `j` to `i`. Raises an
+/// error on an invalid byte sequence.
+pub(super) fn codepoint(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let s = string_arg(args.first(), "codepoint")?;
+    let bytes = s.as_slice();
+    let len = bytes.len() as i64;
+
+    let start = relative_position(opt_int_arg(args.get(1), 1, "codepoint")?, len);
+    let end = relative_position(opt_int_arg(args.get(2), start, "codepoint")?, len);
+    let strict = !lax_arg(args.get(3));
+
+    if start < 1 || end > len {
+        return Err(OpError::InvalidType { op: "codepoint" });
+    }
+
+    let mut codes = vec![];
+    let mut pos = start - 1;
+    while pos < end {
+        let (code, seq_len) =
+            decode_utf8_raw(&bytes[pos as usize..], strict).ok_or(OpError::InvalidUtf8Code)?;
+        codes.push(i64::from(code).into());
+        pos += seq_len as i64;
+    }
+
+    Ok(codes)
+}
+
+/// `utf8.offset(s, n [, i])`
+///
+/// Returns the byte position where the `n`th utf8 character of `s` starts,
+/// counting from byte position `i`. Negative values of `n` count backwards,
+/// and `n = 0` finds the start of the character containing byte `i`. `i`
+/// defaults to 1, or to the end of the string if `n` is negative. Returns
+/// `nil` if there is no such character.
+pub(super) fn offset(_: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+    let s = string_arg(args.first(), "offset")?;
+    let bytes = s.as_slice();
+    let len = bytes.len() as i64;
+
+    let mut n = int_arg(args.get(1), "offset")?;
+    let default_start = if n >= 0 { 1 } else { len + 1 };
+    let start = relative_position(opt_int_arg(args.get(2), default_start, "offset")?, len);
+
+    if start < 1 || start - 1 > len {
+        return Err(OpError::InvalidType { op: "offset" });
+    }
+
+    let is_continuation = |pos: i64| {
+        bytes
+            .get(pos as usize)
+            .is_some_and(|&byte| byte & 0xC0 == 0x80)
+    };
+
+    let mut pos = start - 1;
+    if n == 0 {
+        while pos > 0 && is_continuation(pos) {
+            pos -= 1;
+        }
+        return Ok(vec![(pos + 1).into()]);
+    }
+
+    if is_continuation(pos) {
+        return Err(OpError::InvalidUtf8Code);
+    }
+
+    if n < 0 {
+        while n < 0 && pos > 0 {
+            pos -= 1;
+            while pos > 0 && is_continuation(pos) {
+                pos -= 1;
+            }
+            n += 1;
+        }
+    } else {
+        n -= 1;
+        while n > 0 && pos < len {
+            pos += 1;
+            while is_continuation(pos) {
+                pos += 1;
+            }
+            n -= 1;
+        }
+    }
+
+    if n == 0 {
+        Ok(vec![(pos + 1).into()])
+    } else {
+        Ok(vec![Value::Nil])
+    }
+}
+
+/// Converts a 1-based position which counts from the end of the string if it
+/// is negative into one which counts from the start.
+fn relative_position(pos: i64, len: i64) -> i64 {
+    if pos >= 0 {
+        pos
+    } else if pos.unsigned_abs() > len as u64 {
+        0
+    } else {
+        len + pos + 1
+    }
+}
+
+fn string_arg(arg: Option<&Value>, op: &'static str) -> Result<LuaString, OpError> {
+    match arg {
+        Some(Value::String(s)) => Ok(s.borrow().clone()),
+        _ => Err(OpError::InvalidType { op }),
+    }
+}
+
+fn int_arg(arg: Option<&Value>, op: &'static str) -> Result<i64, OpError> {
+    arg.and_then(|arg| arg.as_int())
+        .ok_or(OpError::InvalidType { op })
+}
+
+fn opt_int_arg(arg: Option<&Value>, default: i64, op: &'static str) -> Result<i64, OpError> {
+    match arg {
+        None | Some(Value::Nil) => Ok(default),
+        arg => int_arg(arg, op),
+    }
+}
+
+fn lax_arg(arg: Option<&Value>) -> bool {
+    arg.is_some_and(|lax| lax.as_bool())
+}
@@ -1,3 +1,4 @@
 pub mod binop;
+pub(crate) mod builtins;
 
 pub mod runtime;
@@ -21,14 +21,17 @@ use tlua_bytecode::{
     },
     ByteCodeError,
     ImmediateRegister,
+    MappedRegister,
     OpError,
     PrimitiveType,
+    Register,
     Truthy,
     TypeId,
 };
 use tlua_compiler::{
     BuiltinType,
     Chunk,
+    SourceLocation,
 };
 use tracing_rc::rc::Gc;
 
@@ -36,13 +39,18 @@ use crate::vm::{
     binop::{
         bool_op,
         cmp_op,
+        concat_n_op,
         concat_op,
         fp_op,
         int_op,
     },
     runtime::{
+        output::OutputSink,
+        random::RandomState,
+        undefined_global,
         value::{
             function::{
+                FuncRef,
                 Scope,
                 ScopeSet,
             },
@@ -50,11 +58,23 @@ use crate::vm::{
             Function,
             Number,
         },
+        Globals,
+        Profile,
         Table,
         Value,
     },
 };
 
+/// The scope holding a chunk's global variables.
+const GLOBAL_SCOPE: u16 = 0;
+
+/// The most `__index` values which are followed for a single read before the
+/// chain is assumed to be a loop.
+const MAX_TAG_LOOP: usize = 2000;
+
+/// The location of an error in the source of the chunk with the given name.
+pub(crate) type ErrorLocation = (SourceLocation, Option<String>);
+
 #[derive(Debug, Deref, DerefMut, From)]
 pub(crate) struct Immediates(Vec<Value>);
 
@@ -77,24 +97,220 @@ pub struct Context<'call> {
     in_scope: ScopeSet,
     imm: Immediates,
 
-    chunk: &'call Chunk,
+    chunk: &'call Rc<Chunk>,
+    globals: &'call mut Globals,
+    /// The table holding globals in place of `globals`, if any.
+    env: Option<Gc<Table>>,
+    profile: Option<&'call mut Profile>,
+    /// The location of the innermost instruction which raised the error
+    /// currently unwinding the stack, if any.
+    error_location: &'call mut Option<ErrorLocation>,
+    rng: &'call mut RandomState,
+    stdout: &'call mut OutputSink,
+
+    func: FuncRef,
     instructions: &'call [Instruction],
     instruction_pointer: &'call [Instruction],
 }
 
 impl<'call> Context<'call> {
-    pub fn new(scopes: ScopeSet, chunk: &'call Chunk) -> Self {
+    pub(crate) fn new(
+        scopes: ScopeSet,
+        chunk: &'call Rc<Chunk>,
+        globals: &'call mut Globals,
+        profile: Option<&'call mut Profile>,
+        error_location: &'call mut Option<ErrorLocation>,
+        rng: &'call mut RandomState,
+        stdout: &'call mut OutputSink,
+    ) -> Self {
         Self {
             in_scope: scopes,
             imm: vec![Value::Nil; chunk.main.immediates].into(),
             chunk,
+            globals,
+            env: None,
+            profile,
+            error_location,
+            rng,
+            stdout,
+            func: FuncRef::Main,
             instructions: chunk.main.instructions.as_slice(),
             instruction_pointer: chunk.main.instructions.as_slice(),
         }
     }
+
+    /// Resolves globals through `env` in place of the runtime's globals.
+    pub(crate) fn with_env(mut self, env: Option<Gc<Table>>) -> Self {
+        self.env = env;
+        self
+    }
 }
 
 impl Context<'_> {
+    /// Reads `target[key]`, consulting `__index` if the key is absent.
+    ///
+    /// An `__index` function is called with the table and key, and any other
+    /// `__index` value is indexed in turn, up to a limit which stops cycles of
+    /// tables from running forever.
+    pub fn index(&mut self, mut target: Value, key: Value) -> Result<Value, OpError> {
+        for _ in 0..MAX_TAG_LOOP {
+            let handler = match &target {
+                Value::Table(t) => {
+                    let table = t.borrow();
+                    match table.get(&key) {
+                        Value::Nil => table.metamethod("__index"),
+                        value => return Ok(value),
+                    }
+                }
+                _ => return Err(OpError::InvalidType { op: "index" }),
+            };
+
+            match handler {
+                Value::Nil => return Ok(Value::Nil),
+                Value::Function(_) | Value::NativeFunction(_) => {
+                    return self
+                        .call(&handler, vec![target, key])
+                        .map(|results| results.into_iter().next().unwrap_or_default());
+                }
+                handler => target = handler,
+            }
+        }
+
+        Err(OpError::MetaChainTooLong { name: "__index" })
+    }
+
+    /// Assigns `target[key] = value`, consulting `__newindex` if the key is
+    /// absent.
+    ///
+    /// An `__newindex` function is called with the table, key & value, and
+    /// the assignment is repeated on any other `__newindex` value.
+    fn new_index(&mut self, mut target: Value, key: Value, value: Value) -> Result<(), OpError> {
+        for _ in 0..MAX_TAG_LOOP {
+            let handler = match &target {
+                Value::Table(t) => {
+                    let mut table = t.borrow_mut();
+                    match table.metamethod("__newindex") {
+                        Value::Nil => return table.set(key, value),
+                        _ if table.get(&key) != Value::Nil => return table.set(key, value),
+                        handler => handler,
+                    }
+                }
+                _ => return Err(OpError::InvalidType { op: "newindex" }),
+            };
+
+            match handler {
+                Value::Function(_) | Value::NativeFunction(_) => {
+                    return self.call(&handler, vec![target, key, value]).map(|_| ());
+                }
+                handler => target = handler,
+            }
+        }
+
+        Err(OpError::MetaChainTooLong { name: "__newindex" })
+    }
+
+    /// The name of the global variable in `register`, as a key of an
+    /// environment table.
+    fn global_name(&self, register: MappedRegister<Register>) -> Value {
+        let (&ident, _) = self
+            .chunk
+            .globals_map
+            .get_index(usize::from(register.offset))
+            .expect("Valid global register");
+
+        self.chunk
+            .strings
+            .get_ident(ident)
+            .expect("Valid ident")
+            .clone()
+            .into()
+    }
+
+    /// Calls `func` with the provided arguments and returns all of its
+    /// results.
+    pub fn call(&mut self, func: &Value, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+        match func {
+            Value::Function(f) => self.execute_call(&f.borrow(), 0..0, args),
+            Value::NativeFunction(f) => f.call(self, args),
+            _ => Err(OpError::InvalidType { op: "call" }),
+        }
+    }
+
+    /// Calls `func` like [`Context::call`], but catches any error it raises.
+    /// The error is returned as the value which LUA code sees, a message
+    /// prefixed by the location of the error when it is known.
+    pub(crate) fn protected_call(
+        &mut self,
+        func: &Value,
+        args: Vec<Value>,
+    ) -> Result<Vec<Value>, Value> {
+        self.call(func, args).map_err(|error| {
+            // The error stops here, so later errors must record their own location.
+            let message = match self.error_location.take() {
+                Some((location, Some(chunk_name))) => {
+                    format!("{}:{}: {}", chunk_name, location.line, error)
+                }
+                Some((location, None)) => format!("line {}: {}", location.line, error),
+                None => error.to_string(),
+            };
+
+            message.as_str().into()
+        })
+    }
+
+    /// Makes the main function of a separately compiled chunk available as a
+    /// function value. The chunk shares its global variables with every other
+    /// chunk in this execution.
+    pub fn load_chunk(&mut self, chunk: Chunk) -> Value {
+        let global_scope = self.globals.scope_for(&chunk);
+        Value::Function(Gc::new(Function::main(Rc::new(chunk), global_scope)))
+    }
+
+    /// The random number generator shared by everything executed by the
+    /// runtime.
+    pub(crate) fn rng(&mut self) -> &mut RandomState {
+        self.rng
+    }
+
+    /// The sink which LUA code writes its output to.
+    #[cfg_attr(not(feature = "io"), allow(dead_code))]
+    pub(crate) fn stdout(&mut self) -> &mut dyn std::io::Write {
+        self.stdout.writer()
+    }
+
+    /// Reads the value associated with a global variable.
+    #[cfg_attr(not(feature = "io"), allow(dead_code))]
+    pub(crate) fn load_global(&self, name: &str) -> Option<Value> {
+        self.globals.current(name.as_bytes())
+    }
+
+    /// Checks that the global in `register` has been declared or has a value,
+    /// for strict globals. Assigning a global from a chunk's top-level code
+    /// declares it.
+    fn check_global(
+        &mut self,
+        register: MappedRegister<Register>,
+        is_store: bool,
+    ) -> Result<(), OpError> {
+        let (&ident, _) = self
+            .chunk
+            .globals_map
+            .get_index(usize::from(register.offset))
+            .expect("Valid global register");
+        let name = self.chunk.strings.get_ident(ident).expect("Valid ident");
+
+        if is_store && self.func == FuncRef::Main {
+            self.globals.declare(name);
+            Ok(())
+        } else if matches!(self.in_scope.load(register), Value::Nil)
+            && !self.globals.is_declared(name)
+        {
+            Err(undefined_global(name))
+        } else {
+            Ok(())
+        }
+    }
+
     fn subcontext<'f, 's>(
         &'s mut self,
         func: &'f Function,
@@ -104,23 +320,52 @@ impl Context<'_> {
     where
         'f: 's,
     {
-        let func_def = &self.chunk.functions[usize::from(func.id)];
+        let func_def = func.definition();
 
         Context {
             in_scope: ScopeSet::new(func.referenced_scopes.clone(), new_scope, va_args),
             imm: vec![Value::Nil; func_def.immediates].into(),
 
-            chunk: self.chunk,
+            chunk: &func.chunk,
+            globals: &mut *self.globals,
+            env: func.env.clone(),
+            profile: self.profile.as_deref_mut(),
+            error_location: &mut *self.error_location,
+            rng: &mut *self.rng,
+            stdout: &mut *self.stdout,
+
+            func: func.id,
             instructions: func_def.instructions.as_slice(),
             instruction_pointer: func_def.instructions.as_slice(),
         }
     }
 
     pub fn execute(mut self) -> Result<Vec<Value>, OpError> {
-        while let Some((&instruction, next)) = self.instruction_pointer.split_first() {
+        let result = self.run();
+
+        // Errors are attributed to the innermost frame, which is the first to see them.
+        if result.is_err() && self.error_location.is_none() {
+            // The instruction pointer has already advanced past the failing instruction.
+            let offset = self.ip_index().saturating_sub(1);
+            *self.error_location = self
+                .func
+                .definition(self.chunk)
+                .location_of(offset)
+                .map(|location| (location, self.chunk.name.clone()));
+        }
+
+        result
+    }
+
+    fn run(&mut self) -> Result<Vec<Value>, OpError> {
+        while let Some((instruction, next)) = self.instruction_pointer.split_first() {
+            if self.profile.is_some() {
+                self.record_instruction();
+            }
+
             self.instruction_pointer = next;
 
-            match instruction {
+            match *instruction {
                 Op::Nop => (),
 
                 // Numeric operations
@@ -166,29 +411,13 @@ impl Context<'_> {
                     self.imm[dst] = match self.imm[src].clone() {
                         Value::Number(operand) => Value::Number(match operand {
                             Number::Float(f) => Number::Float(-f),
-                            Number::Integer(i) => Number::Integer(-i),
+                            Number::Integer(i) => Number::Integer(i.wrapping_neg()),
                         }),
                         _ => return Err(OpError::InvalidType { op: "unary minus" }),
                     };
                 }
                 Op::UnaryBitNot(UnaryBitNot { dst, src }) => {
-                    self.imm[dst] = match self.imm[src].clone() {
-                        Value::Number(operand) => Value::Number(match operand {
-                            Number::Float(f) => {
-                                if f.fract() == 0.0 {
-                                    Number::Integer(!f64inbounds(f)?)
-                                } else {
-                                    return Err(OpError::FloatToIntConversionFailed { f });
-                                }
-                            }
-                            Number::Integer(i) => Number::Integer(!i),
-                        }),
-                        _ => {
-                            return Err(OpError::InvalidType {
-                                op: "unary bit not",
-                            })
-                        }
-                    };
+                    self.imm[dst] = self.bit_not(self.imm[src].clone())?;
                 }
 
                 // Comparison operations
@@ -226,15 +455,26 @@ impl Context<'_> {
 
                 // String & Array operations
                 Op::Concat(Concat { lhs, rhs }) => {
-                    self.imm[lhs] = concat_op(lhs, rhs, &self.imm)?;
+                    self.imm[lhs] = self.concat(self.imm[lhs].clone(), self.imm[rhs].clone())?;
+                }
+                Op::ConcatN(ConcatN { dst, start, count }) => {
+                    let operands = &self.imm.0[start..start + count];
+                    self.imm[dst] = match concat_n_op(operands) {
+                        Ok(result) => result,
+                        Err(_) => {
+                            // Some operand needs a metamethod, so fall back to concatenating
+                            // pairs from the right, as `..` is right associative.
+                            let mut operands = operands.to_vec();
+                            let last = operands.pop().unwrap_or_default();
+                            operands
+                                .into_iter()
+                                .rev()
+                                .try_fold(last, |rhs, lhs| self.concat(lhs, rhs))?
+                        }
+                    };
                 }
                 Op::Length(Length { dst, src }) => {
-                    self.imm[dst] = match &self.imm[src] {
-                        Value::String(s) => i64::try_from(s.borrow().len())
-                            .map_err(|_| OpError::StringLengthOutOfBounds)
-                            .map(Value::from)?,
-                        _ => return Err(OpError::InvalidType { op: "length" }),
-                    };
+                    self.imm[dst] = self.length(self.imm[src].clone())?;
                 }
 
                 Op::Jump(Jump { target }) => {
@@ -247,6 +487,12 @@ impl Context<'_> {
                     }
                 }
 
+                Op::JumpIf(JumpIf { cond, target }) => {
+                    if self.imm[cond].as_bool() {
+                        self.instruction_pointer = self.instructions.split_at(target).1;
+                    }
+                }
+
                 Op::JumpNil(JumpNil { cond, target }) => {
                     if self.imm[cond] == Value::Nil {
                         self.instruction_pointer = self.instructions.split_at(target).1;
@@ -255,23 +501,14 @@ impl Context<'_> {
 
                 // Table operations
                 Op::Lookup(Lookup { dst, src, idx }) => {
-                    self.imm[dst] = match &self.imm[src] {
-                        Value::Table(t) => t
-                            .borrow()
-                            .entries
-                            .get(&TableKey::try_from(self.imm[idx].clone())?)
-                            .cloned()
-                            .unwrap_or_default(),
-                        _ => return Err(OpError::InvalidType { op: "index" }),
-                    };
+                    self.imm[dst] = self.index(self.imm[src].clone(), self.imm[idx].clone())?;
                 }
 
                 Op::SetProperty(SetProperty { dst, idx, src }) => {
                     match &self.imm[dst] {
-                        Value::Table(t) => t.borrow_mut().entries.insert(
-                            TableKey::try_from(self.imm[idx].clone())?,
-                            self.imm[src].clone(),
-                        ),
+                        Value::Table(t) => t
+                            .borrow_mut()
+                            .set(self.imm[idx].clone(), self.imm[src].clone())?,
                         _ => return Err(OpError::InvalidType { op: "newindex" }),
                     };
                 }
@@ -291,7 +528,12 @@ impl Context<'_> {
                         .collect::<Result<Vec<_>, _>>()?;
 
                     match &self.imm[dst] {
-                        Value::Table(t) => t.borrow_mut().entries.extend(entries),
+                        Value::Table(t) => {
+                            let mut table = t.borrow_mut();
+                            for (key, value) in entries {
+                                table.insert(key, value);
+                            }
+                        }
                         _ => return Err(OpError::InvalidType { op: "va tableinit" }),
                     };
                 }
@@ -322,15 +564,33 @@ impl Context<'_> {
                         *dst = va.next().cloned().unwrap_or_default();
                     }
                 }
-                Op::LoadRegister(LoadRegister { dst, src }) => {
-                    self.imm[dst] = self.in_scope.load(src);
-                }
+                Op::LoadRegister(LoadRegister { dst, src }) => match &self.env {
+                    Some(env) if src.scope == GLOBAL_SCOPE => {
+                        let name = self.global_name(src);
+                        self.imm[dst] = self.index(Value::Table(env.clone()), name)?;
+                    }
+                    _ => {
+                        self.imm[dst] = self.in_scope.load(src);
+                        if self.globals.strict && src.scope == GLOBAL_SCOPE {
+                            self.check_global(src, false)?;
+                        }
+                    }
+                },
                 Op::DuplicateRegister(DuplicateRegister { dst, src }) => {
                     self.imm[dst] = self.imm[src].clone();
                 }
-                Op::Store(Store { dst, src }) => {
-                    self.in_scope.store(dst, self.imm[src].clone());
-                }
+                Op::Store(Store { dst, src }) => match &self.env {
+                    Some(env) if dst.scope == GLOBAL_SCOPE => {
+                        let name = self.global_name(dst);
+                        self.new_index(Value::Table(env.clone()), name, self.imm[src].clone())?;
+                    }
+                    _ => {
+                        if self.globals.strict && dst.scope == GLOBAL_SCOPE {
+                            self.check_global(dst, true)?;
+                        }
+                        self.in_scope.store(dst, self.imm[src].clone());
+                    }
+                },
 
                 // Begin calling a function
                 Op::Call(Call {
@@ -364,9 +624,12 @@ impl Context<'_> {
                 // Allocate values
                 Op::Alloc(Alloc { dst, type_id }) => {
                     self.imm[dst] = match BuiltinType::try_from(type_id) {
-                        Ok(BuiltinType::Function(id)) => {
-                            Value::Function(Gc::new(Function::new(&self.in_scope, id)))
-                        }
+                        Ok(BuiltinType::Function(id)) => Value::Function(Gc::new(Function::new(
+                            &self.in_scope,
+                            self.env.clone(),
+                            self.chunk.clone(),
+                            id,
+                        ))),
                         Ok(BuiltinType::Table) => Value::Table(Gc::new(Table::default())),
                         _ => {
                             return Err(OpError::ByteCodeError {
@@ -398,7 +661,8 @@ impl Context<'_> {
                             match (BuiltinType::try_from(id), target) {
                                 (Ok(BuiltinType::Table), Value::Table(_)) => true,
                                 (Ok(BuiltinType::Function(id)), Value::Function(f)) => {
-                                    f.borrow().id == id
+                                    let f = f.borrow();
+                                    Rc::ptr_eq(&f.chunk, self.chunk) && f.id == FuncRef::Id(id)
                                 }
                                 (_, _) => false,
                             }
@@ -418,27 +682,28 @@ impl Context<'_> {
                 }
 
                 Op::Ret => {
-                    return Ok(self.in_scope.into_results().into());
+                    return Ok(self.in_scope.take_results().into());
                 }
 
                 Op::CopyRetFromVaAndRet => {
-                    let (mut results, va) = self.in_scope.into_results_and_va();
-                    results.extend(Vec::from(va).into_iter());
+                    let (mut results, va) = self.in_scope.take_results_and_va();
+                    results.extend(Vec::from(va));
                     return Ok(results.into());
                 }
 
                 // Stop execution by raising an error.
-                Op::Raise(Raise { err }) => return Err(err),
-                Op::RaiseIfNot(RaiseIfNot { src, err }) => {
+                Op::Raise(Raise { ref err }) => return Err(err.clone()),
+                Op::RaiseIfNot(RaiseIfNot { src, ref err }) => {
                     if !self.imm[src].as_bool() {
-                        return Err(err);
+                        return Err(err.clone());
                     }
                 }
 
                 Op::CallCopyRet(_)
                 | Op::ConsumeRetRange(_)
                 | Op::SetAllPropertiesFromRet(_)
-                | Op::CopyRetFromRetAndRet => {
+                | Op::CopyRetFromRetAndRet
+                | Op::TailCall => {
                     return Err(OpError::ByteCodeError {
                         err: ByteCodeError::UnexpectedCallInstruction,
                         offset: self.ip_index(),
@@ -447,7 +712,7 @@ impl Context<'_> {
             }
         }
 
-        Ok(self.in_scope.into_results().into())
+        Ok(self.in_scope.take_results().into())
     }
 
     fn start_call(
@@ -458,20 +723,45 @@ impl Context<'_> {
     ) -> Result<(), OpError> {
         let func = match &self.imm[target] {
             Value::Function(ptr) => ptr.clone(),
+            Value::NativeFunction(native) => {
+                let native = native.clone();
+                let mut args = arg_range
+                    .map(|idx| self.imm[idx.into()].clone())
+                    .collect::<Vec<_>>();
+                args.extend(extra_args);
+
+                let results = native.call(self, args)?;
+                return self.consume_call_results(results);
+            }
             _ => return Err(OpError::InvalidType { op: "call" }),
         };
 
+        // If the call is immediately followed by TailCall, we know its results are
+        // going to be our results, so the callee can take over this frame
+        // instead of running in a subcontext. This is only possible if the callee
+        // shares our chunk, since the frame borrows its instructions from it.
+        if let Some(Op::TailCall) = self.instruction_pointer.first() {
+            let func = func.borrow();
+            if Rc::ptr_eq(&func.chunk, self.chunk) {
+                self.enter_tail_call(&func, arg_range, extra_args);
+                return Ok(());
+            }
+        }
+
         let results = self.execute_call(&func.borrow(), arg_range, extra_args)?;
+        self.consume_call_results(results)
+    }
 
-        match self.instruction_pointer[0] {
+    fn consume_call_results(&mut self, results: Vec<Value>) -> Result<(), OpError> {
+        match self.instruction_pointer.first() {
             // We just performed a call, so if the very next instruction is StartCallExtending, we
             // know that we should include the results in that call directly rather than doing
             // normal result mapping.
-            Op::CallCopyRet(CallCopyRet {
+            Some(&Op::CallCopyRet(CallCopyRet {
                 target,
                 mapped_args_start,
                 mapped_args_count,
-            }) => {
+            })) => {
                 self.instruction_pointer = self
                     .instruction_pointer
                     .split_first()
@@ -486,8 +776,9 @@ impl Context<'_> {
             }
             // We just performed a call, so if the very next instruction is CopyRetFromRet, we know
             // we should copy over all of the results directly rather than doing normal result
-            // mapping.
-            Op::CopyRetFromRetAndRet => {
+            // mapping. A TailCall we couldn't honor by taking over the frame means the same
+            // thing.
+            Some(Op::CopyRetFromRetAndRet | Op::TailCall) => {
                 self.in_scope.extend_results(results);
                 self.instruction_pointer = &[];
 
@@ -497,13 +788,120 @@ impl Context<'_> {
         }
     }
 
+    /// Concatenates `lhs` and `rhs`, consulting the `__concat` metamethod of
+    /// either operand if they aren't both strings or numbers.
+    fn concat(&mut self, lhs: Value, rhs: Value) -> Result<Value, OpError> {
+        let err = match concat_op(&lhs, &rhs) {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+
+        let handler = [&lhs, &rhs]
+            .into_iter()
+            .find_map(|operand| match operand {
+                Value::Table(t) => match t.borrow().metamethod("__concat") {
+                    Value::Nil => None,
+                    handler => Some(handler),
+                },
+                _ => None,
+            })
+            .ok_or(err)?;
+
+        self.call(&handler, vec![lhs, rhs])
+            .map(|results| results.into_iter().next().unwrap_or_default())
+    }
+
+    /// Computes `~value`, consulting the `__bnot` metamethod of a table.
+    fn bit_not(&mut self, value: Value) -> Result<Value, OpError> {
+        let handler = match &value {
+            Value::Number(Number::Integer(i)) => return Ok(Value::from(!i)),
+            &Value::Number(Number::Float(f)) => {
+                return f64inbounds(f)
+                    .map(|i| Value::from(!i))
+                    .map_err(|_| OpError::NoIntegerRepresentation { f });
+            }
+            Value::Table(t) => t.borrow().metamethod("__bnot"),
+            _ => Value::Nil,
+        };
+
+        if let Value::Nil = handler {
+            return Err(OpError::BitwiseOnNonNumber {
+                ty: value.type_name(),
+            });
+        }
+
+        // Like binary operators, unary metamethods receive the operand twice.
+        self.call(&handler, vec![value.clone(), value])
+            .map(|results| results.into_iter().next().unwrap_or_default())
+    }
+
+    fn length(&mut self, value: Value) -> Result<Value, OpError> {
+        let len_fn = match &value {
+            Value::String(s) => {
+                return i64::try_from(s.borrow().len())
+                    .map_err(|_| OpError::StringLengthOutOfBounds)
+                    .map(Value::from);
+            }
+            Value::Table(t) => match t.borrow().metamethod("__len") {
+                Value::Nil => return Ok(t.borrow().border().into()),
+                len_fn => len_fn,
+            },
+            _ => return Err(OpError::InvalidType { op: "length" }),
+        };
+
+        match self.call(&len_fn, vec![value])?.into_iter().next() {
+            Some(Value::Number(Number::Integer(len))) => Ok(len.into()),
+            // Floats with an integral value are converted, any other result is an error.
+            Some(Value::Number(Number::Float(len))) => f64inbounds(len)
+                .map(Value::from)
+                .map_err(|_| OpError::LengthNotInteger),
+            _ => Err(OpError::LengthNotInteger),
+        }
+    }
+
     fn execute_call(
         &mut self,
         func: &Function,
         arg_range: Range<usize>,
-        mut extra_args: Vec<Value>,
+        extra_args: Vec<Value>,
     ) -> Result<Vec<Value>, OpError> {
-        let func_def = &self.chunk.functions[usize::from(func.id)];
+        let (subscope, va_args) = self.map_args(func, arg_range, extra_args);
+        self.subcontext(func, va_args, subscope).execute()
+    }
+
+    fn enter_tail_call(
+        &mut self,
+        func: &Function,
+        arg_range: Range<usize>,
+        extra_args: Vec<Value>,
+    ) {
+        let (subscope, va_args) = self.map_args(func, arg_range, extra_args);
+
+        let func_def = func.id.definition(self.chunk);
+
+        let pending = std::mem::replace(
+            &mut self.in_scope,
+            ScopeSet::new(func.referenced_scopes.clone(), subscope, va_args),
+        )
+        .take_results();
+        // The compiler only emits tail calls for lone calls in a return, but any
+        // results which are already pending still belong in front of the
+        // callee's.
+        self.in_scope.extend_results(Vec::from(pending));
+
+        self.imm = vec![Value::Nil; func_def.immediates].into();
+        self.func = func.id;
+        self.instructions = func_def.instructions.as_slice();
+        self.instruction_pointer = func_def.instructions.as_slice();
+    }
+
+    fn map_args(
+        &self,
+        func: &Function,
+        arg_range: Range<usize>,
+        mut extra_args: Vec<Value>,
+    ) -> (Scope, Vec<Value>) {
+        let func_def = func.definition();
         let desired_input_args = func_def.named_args;
         let subscope = Scope::new(func_def.local_registers);
 
@@ -532,17 +930,17 @@ impl Context<'_> {
         }
 
         va_args.extend(other_results);
-        self.subcontext(func, va_args, subscope).execute()
+        (subscope, va_args)
     }
 
     fn map_results(&mut self, results: Vec<Value>) -> Result<(), OpError> {
-        let (&isn, next) = if let Some(next) = self.instruction_pointer.split_first() {
+        let (isn, next) = if let Some(next) = self.instruction_pointer.split_first() {
             next
         } else {
             return Ok(());
         };
 
-        match isn {
+        match *isn {
             Op::ConsumeRetRange(ConsumeRetRange { dst_start, count }) => {
                 let mut results = results.into_iter();
                 for dst in self.imm.iter_mut().skip(dst_start).take(count) {
@@ -562,7 +960,7 @@ impl Context<'_> {
                                 .map(|key| (key, v))
                         }) {
                             let (k, v) = res?;
-                            table.entries.insert(k, v);
+                            table.insert(k, v);
                         }
                     }
                     _ => {
@@ -581,6 +979,13 @@ impl Context<'_> {
         Ok(())
     }
 
+    fn record_instruction(&mut self) {
+        let offset = self.ip_index();
+        if let Some(profile) = self.profile.as_deref_mut() {
+            *profile.entry((self.func.func_id(), offset)).or_default() += 1;
+        }
+    }
+
     fn ip_index(&self) -> usize {
         if self.instruction_pointer.is_empty() {
             self.instructions.len()
@@ -0,0 +1,23 @@
+use std::cell::Cell;
+
+thread_local! {
+    // Like the collector, this is shared by every runtime on the thread, as values don't know
+    // which runtime created them.
+    static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Records that a value has grown by `bytes`.
+pub(crate) fn allocated(bytes: usize) {
+    LIVE_BYTES.with(|live| live.set(live.get() + bytes));
+}
+
+/// Records that a value has shrunk or been freed, releasing `bytes`.
+pub(crate) fn freed(bytes: usize) {
+    LIVE_BYTES.with(|live| live.set(live.get().saturating_sub(bytes)));
+}
+
+/// The approximate number of bytes used by the tables and functions alive on
+/// this thread.
+pub(crate) fn live_bytes() -> usize {
+    LIVE_BYTES.with(Cell::get)
+}
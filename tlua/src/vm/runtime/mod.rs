@@ -1,38 +1,91 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    rc::Rc,
+};
 
-use tlua_compiler::Chunk;
+use tlua_bytecode::{
+    opcodes::{
+        Alloc,
+        Op,
+    },
+    OpError,
+};
+use tlua_compiler::{
+    BuiltinType,
+    Chunk,
+    FuncId,
+};
 use tlua_strings::LuaString;
 use tracing_rc::rc::collect_full;
 
 use crate::{
-    vm::runtime::value::function::{
-        Scope,
-        ScopeSet,
+    vm::{
+        builtins,
+        runtime::{
+            output::OutputSink,
+            random::RandomState,
+            value::function::{
+                Scope,
+                ScopeSet,
+            },
+        },
     },
     LuaError,
 };
 
 pub mod execution_context;
+pub(crate) mod memory;
+pub(crate) mod output;
+pub(crate) mod random;
 pub mod value;
 
 pub use tracing_rc::rc::Gc;
 
 pub use self::value::{
+    FormatOptions,
     Function,
+    NativeFunction,
+    OwnedValue,
+    OwnedValueError,
     Table,
     Value,
 };
 
-#[derive(Debug, Default)]
+/// Execution counts for each instruction, keyed by the function containing it
+/// and its offset within that function. The top-level code of a chunk is
+/// identified by [`FuncId::MAIN`].
+pub type Profile = HashMap<(FuncId, usize), u64>;
+
+#[derive(Debug)]
 pub struct Runtime {
-    globals: HashMap<LuaString, Value>,
+    globals: Globals,
+    profile: Option<Profile>,
+    rng: RandomState,
+    stdout: OutputSink,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        let mut runtime = Self {
+            globals: Default::default(),
+            profile: None,
+            rng: Default::default(),
+            stdout: Default::default(),
+        };
+        builtins::register(&mut runtime);
+        runtime
+    }
 }
 
 impl Runtime {
     /// Registers a value associated with a global variable which will be
     /// available to LUA code executed with this runtime.
     pub fn register_global(&mut self, name: &str, value: impl Into<Value>) {
-        self.globals.insert(name.into(), value.into());
+        self.globals.set(name.into(), value.into());
     }
 
     /// Reads the value associated with a global variable.
@@ -40,41 +93,376 @@ impl Runtime {
         self.globals.get(name.as_bytes())
     }
 
-    /// Execute the provided chunk & run it until it completes or returns an
-    /// error.
-    pub fn execute(&mut self, chunk: &Chunk) -> Result<Vec<Value>, LuaError> {
+    /// Iterates over the global variables which aren't `nil`, in no particular
+    /// order.
+    ///
+    /// Values are cloned out of the runtime, which only copies a reference for
+    /// tables and functions.
+    pub fn globals(&self) -> impl Iterator<Item = (&LuaString, Value)> + '_ {
+        self.globals
+            .cells
+            .iter()
+            .map(|(name, cell)| (name, cell.borrow().clone()))
+            .filter(|(_, value)| !matches!(value, Value::Nil))
+    }
+
+    /// Enables or disables strict globals, which catches misspelled global
+    /// names. Strict globals are disabled by default.
+    ///
+    /// While enabled, reading a global which is `nil` and was never declared,
+    /// or assigning to one from inside a function, raises
+    /// [`OpError::UndefinedGlobal`]. Globals are declared by registering them
+    /// with [`Runtime::register_global`], or by assigning them from the
+    /// top-level code of a chunk, even if the value assigned is `nil`.
+    pub fn set_strict_globals(&mut self, strict: bool) {
+        self.globals.strict = strict;
+    }
+
+    /// Sets a global variable to `nil`, as `name = nil` would from LUA.
+    pub fn remove_global(&mut self, name: &str) {
+        self.globals.remove(name.as_bytes());
+    }
+
+    /// Captures the current value of every global variable, so that they can
+    /// be rolled back with [`Runtime::restore`], e.g. after a line entered into
+    /// a REPL raises an error.
+    ///
+    /// Tables and functions are shared with the runtime rather than copied, so
+    /// changes to the contents of a table aren't rolled back.
+    pub fn snapshot(&self) -> GlobalsSnapshot {
+        GlobalsSnapshot {
+            values: self
+                .globals()
+                .map(|(name, value)| (name.clone(), value))
+                .collect(),
+        }
+    }
+
+    /// Sets every global variable back to its value when `snapshot` was taken.
+    /// Globals which didn't exist then are set to `nil`.
+    pub fn restore(&mut self, snapshot: &GlobalsSnapshot) {
+        // Existing cells are updated rather than replaced, as functions which were
+        // already loaded read their globals through them.
+        for (name, cell) in self.globals.cells.iter() {
+            *cell.borrow_mut() = snapshot.values.get(name).cloned().unwrap_or_default();
+        }
+
+        for (name, value) in snapshot.values.iter() {
+            self.globals.set(name.clone(), value.clone());
+        }
+
+        self.globals.sync();
+    }
+
+    /// Creates a callable value for the function `id` defined in `chunk`, or
+    /// `None` if the chunk has no such function or it isn't declared at the
+    /// top level of the chunk. [`FuncId::MAIN`] refers to the chunk's top-level
+    /// code.
+    ///
+    /// The function shares this runtime's global variables. The code which
+    /// declares it never runs, so each local of the chunk's top-level code
+    /// which it refers to as an upvalue is a fresh variable starting out as
+    /// `nil`. Those variables are shared by every call to the returned value,
+    /// and by any closures it creates.
+    pub fn load_function(&mut self, chunk: impl Into<Rc<Chunk>>, id: FuncId) -> Option<Value> {
+        let chunk = chunk.into();
+
+        // A function nested in another one relies on its enclosing function's locals,
+        // which only exist while that function runs.
+        if id != FuncId::MAIN && !declared_by_main(&chunk, id) {
+            return None;
+        }
+
+        self.function_value(chunk, id)
+    }
+
+    fn function_value(&mut self, chunk: Rc<Chunk>, id: FuncId) -> Option<Value> {
+        chunk.function(id)?;
+
+        let global_scope = self.globals.scope_for(&chunk);
+
+        let function = if id == FuncId::MAIN {
+            Function::main(chunk, global_scope)
+        } else {
+            Function::detached(chunk, global_scope, id)
+        };
+
+        Some(Value::Function(Gc::new(function)))
+    }
+
+    /// Runs the function `id` defined in `chunk` with the provided arguments
+    /// rather than the chunk's top-level code, e.g. to test one function in
+    /// isolation.
+    ///
+    /// Globals are resolved the same way as by [`Runtime::execute`]. Unlike
+    /// [`Runtime::load_function`], any function may be run, including one
+    /// nested in another function. The code which declares it never runs, so
+    /// each local of an enclosing function or block which it refers to as an
+    /// upvalue starts out as `nil`, and isn't kept between executions. Returns
+    /// `None` if the chunk has no such function.
+    pub fn execute_function(
+        &mut self,
+        chunk: impl Into<Rc<Chunk>>,
+        id: FuncId,
+        args: Vec<Value>,
+    ) -> Option<Result<Vec<Value>, LuaError>> {
+        let function = self.function_value(chunk.into(), id)?;
+        Some(self.call(&function, args))
+    }
+
+    /// Execute the provided chunk with `env` as its global environment in
+    /// place of this runtime's globals.
+    ///
+    /// Every global the chunk reads or assigns is a field of `env`, accessed
+    /// like any other field, so `__index` & `__newindex` are consulted. This
+    /// includes globals used by functions the chunk defines, whenever they're
+    /// called. [`Runtime::set_strict_globals`] doesn't apply to `env`.
+    pub fn execute_with_env(
+        &mut self,
+        chunk: impl Into<Rc<Chunk>>,
+        env: &Gc<Table>,
+    ) -> Result<Vec<Value>, LuaError> {
+        let chunk = chunk.into();
+
+        // The chunk's global registers are never accessed, so they don't need to be
+        // bound to this runtime's globals.
         let global_scope = Scope::new(chunk.globals_map.len());
+        self.execute_in(chunk, global_scope, Some(env.clone()))
+    }
 
-        for (ident, value) in self.globals.iter() {
-            if let Some(register) = chunk
-                .strings
-                .lookup_ident(ident)
-                .and_then(|ident| chunk.globals_map.get(&ident))
-            {
-                global_scope.registers[*register].replace(value.clone());
-            }
+    /// Calls `func` with the provided arguments & runs it until it completes
+    /// or returns an error.
+    pub fn call(&mut self, func: &Value, args: Vec<Value>) -> Result<Vec<Value>, LuaError> {
+        // The host has no code of its own, so the call is made from an empty chunk.
+        let chunk = Rc::new(Chunk::default());
+
+        let mut error_location = None;
+        let mut execution_context = execution_context::Context::new(
+            ScopeSet::new(vec![], Scope::default(), vec![]),
+            &chunk,
+            &mut self.globals,
+            self.profile.as_mut(),
+            &mut error_location,
+            &mut self.rng,
+            &mut self.stdout,
+        );
+
+        let result = execution_context.call(func, args);
+        self.globals.sync();
+
+        collect_garbage();
+
+        result.map_err(|error| LuaError::execution_error(error, error_location))
+    }
+
+    /// Calls the function stored in the global variable `name` with the
+    /// provided arguments. Calling a global which doesn't hold a function is
+    /// an error, as it is from LUA.
+    pub fn call_global(&mut self, name: &str, args: Vec<Value>) -> Result<Vec<Value>, LuaError> {
+        let func = self.load_global(name).cloned().unwrap_or_default();
+        self.call(&func, args)
+    }
+
+    /// The approximate number of bytes used by the tables and functions which
+    /// are alive. This includes values created by any runtime on the current
+    /// thread, and doesn't include strings.
+    pub fn memory_usage(&self) -> usize {
+        memory::live_bytes()
+    }
+
+    /// Sends the output written by LUA code, e.g. through `io.write`, to `sink`
+    /// rather than the process's standard output.
+    pub fn set_stdout(&mut self, sink: impl std::io::Write + 'static) {
+        self.stdout = OutputSink::new(sink);
+    }
+
+    /// Enables or disables counting how many times each instruction is
+    /// executed. Disabling profiling discards any counts which haven't been
+    /// taken.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        match (enabled, &self.profile) {
+            (true, None) => self.profile = Some(Profile::default()),
+            (false, _) => self.profile = None,
+            (true, Some(_)) => (),
         }
+    }
+
+    /// Returns the instruction counts accumulated since profiling was enabled
+    /// or the profile was last taken, and resets them.
+    pub fn take_profile(&mut self) -> Profile {
+        self.profile
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Execute the provided chunk & run it until it completes or returns an
+    /// error.
+    ///
+    /// Functions created by the chunk share it with the caller when it is
+    /// passed as an `Rc<Chunk>`, while a borrowed chunk is copied.
+    pub fn execute(&mut self, chunk: impl Into<Rc<Chunk>>) -> Result<Vec<Value>, LuaError> {
+        let chunk = chunk.into();
+        let global_scope = self.globals.scope_for(&chunk);
+        self.execute_in(chunk, global_scope, None)
+    }
 
+    fn execute_in(
+        &mut self,
+        chunk: Rc<Chunk>,
+        global_scope: Scope,
+        env: Option<Gc<Table>>,
+    ) -> Result<Vec<Value>, LuaError> {
         let current = Scope::new(chunk.main.local_registers);
 
-        let available_scope = vec![global_scope.clone()];
-        let execution_context =
-            execution_context::Context::new(ScopeSet::new(available_scope, current, vec![]), chunk);
+        let available_scope = vec![global_scope];
+        let mut error_location = None;
+        let execution_context = execution_context::Context::new(
+            ScopeSet::new(available_scope, current, vec![]),
+            &chunk,
+            &mut self.globals,
+            self.profile.as_mut(),
+            &mut error_location,
+            &mut self.rng,
+            &mut self.stdout,
+        )
+        .with_env(env);
+
+        let result = execution_context.execute();
+        self.globals.sync();
+
+        collect_garbage();
+
+        result.map_err(|error| LuaError::execution_error(error, error_location))
+    }
+}
+
+/// Checks whether the top-level code of `chunk` creates the function `id`.
+fn declared_by_main(chunk: &Chunk, id: FuncId) -> bool {
+    chunk.main.instructions.iter().any(|instruction| {
+        matches!(
+            instruction,
+            Op::Alloc(Alloc { type_id, .. })
+                if BuiltinType::try_from(*type_id) == Ok(BuiltinType::Function(id))
+        )
+    })
+}
+
+/// Frees every value which is no longer reachable, including those only
+/// referenced through weak tables.
+pub(crate) fn collect_garbage() {
+    collect_full();
+    value::table::sweep_weak_tables();
+}
+
+/// The values of a runtime's global variables at some point in time, taken by
+/// [`Runtime::snapshot`].
+#[derive(Debug, Clone, Default)]
+pub struct GlobalsSnapshot {
+    values: HashMap<LuaString, Value>,
+}
+
+impl GlobalsSnapshot {
+    /// Reads the value a global variable had when the snapshot was taken.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name.as_bytes())
+    }
+}
+
+/// The global variables of a runtime, shared by name between every chunk it
+/// executes or loads.
+#[derive(Debug, Default)]
+pub(crate) struct Globals {
+    cells: HashMap<LuaString, Rc<RefCell<Value>>>,
+    /// The value of every variable as of the last time the runtime finished
+    /// running code, which [`Runtime::load_global`] borrows from.
+    values: HashMap<LuaString, Value>,
+    /// Whether accessing globals which haven't been declared is an error, see
+    /// [`Runtime::set_strict_globals`].
+    pub(crate) strict: bool,
+    /// The globals which have been declared, in addition to every global with
+    /// a value other than `nil`.
+    declared: HashSet<LuaString>,
+}
+
+impl Globals {
+    /// Builds the global scope for `chunk`, binding each of its global
+    /// registers to the variable of the same name.
+    pub(crate) fn scope_for(&mut self, chunk: &Chunk) -> Scope {
+        // The map is ordered by register, so the cells can be collected directly.
+        Scope::from_cells(
+            chunk
+                .globals_map
+                .iter()
+                .enumerate()
+                .map(|(register, (&ident, &idx))| {
+                    debug_assert_eq!(register, idx);
+                    let name = chunk.strings.get_ident(ident).expect("Valid ident");
+                    self.cells.entry(name.clone()).or_default().clone()
+                })
+                .collect(),
+        )
+    }
+
+    fn get(&self, name: &[u8]) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Reads the current value of a variable, for code which is running.
+    pub(crate) fn current(&self, name: &[u8]) -> Option<Value> {
+        self.cells.get(name).map(|cell| cell.borrow().clone())
+    }
+
+    fn set(&mut self, name: LuaString, value: Value) {
+        self.declared.insert(name.clone());
+        self.values.insert(name.clone(), value.clone());
 
-        let result = execution_context.execute()?;
+        // The cell is updated in place so that scopes already bound to it see the new
+        // value.
+        self.cells.entry(name).or_default().replace(value);
+    }
+
+    pub(crate) fn declare(&mut self, name: &LuaString) {
+        if !self.declared.contains(name) {
+            self.declared.insert(name.clone());
+        }
+    }
+
+    pub(crate) fn is_declared(&self, name: &LuaString) -> bool {
+        self.declared.contains(name)
+    }
 
-        // TODO(perf): This can consume all the values if we force globals_map to be
-        // ordered (e.g. with indexmap).
-        let values = global_scope.into_values();
-        for (&ident, &idx) in chunk.globals_map.iter() {
-            self.globals.insert(
-                chunk.strings.get_ident(ident).expect("Valid ident").clone(),
-                values[idx].borrow().clone(),
-            );
+    fn remove(&mut self, name: &[u8]) {
+        self.declared.remove(name);
+
+        if let Some(cell) = self.cells.get(name) {
+            cell.replace(Value::Nil);
+
+            // The cell must be kept if it's bound to a scope, so that the scope sees the
+            // variable being assigned again.
+            if Rc::strong_count(cell) == 1 {
+                self.cells.remove(name);
+                self.values.remove(name);
+            } else if let Some(value) = self.values.get_mut(name) {
+                *value = Value::Nil;
+            }
         }
+    }
 
-        collect_full();
+    /// Updates the values read by [`Runtime::load_global`] to match the
+    /// variables, once code which may have assigned them has finished.
+    fn sync(&mut self) {
+        self.values = self
+            .cells
+            .iter()
+            .map(|(name, cell)| (name.clone(), cell.borrow().clone()))
+            .collect();
+    }
+}
 
-        Ok(result)
+/// Builds the error raised for accessing the undeclared global `name`.
+pub(crate) fn undefined_global(name: &LuaString) -> OpError {
+    OpError::UndefinedGlobal {
+        name: name.to_string().into_boxed_str(),
     }
 }
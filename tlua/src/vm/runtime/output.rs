@@ -0,0 +1,30 @@
+use std::io::Write;
+
+/// Where the output written by LUA code, e.g. through `io.write`, is sent.
+pub(crate) struct OutputSink {
+    sink: Box<dyn Write>,
+}
+
+impl Default for OutputSink {
+    fn default() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl std::fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputSink").finish_non_exhaustive()
+    }
+}
+
+impl OutputSink {
+    pub(crate) fn new(sink: impl Write + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+        }
+    }
+
+    pub(crate) fn writer(&mut self) -> &mut dyn Write {
+        &mut *self.sink
+    }
+}
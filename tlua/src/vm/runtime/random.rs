@@ -0,0 +1,78 @@
+/// The state of the pseudo-random number generator used by `math.random`.
+///
+/// This is the xoshiro256** generator, seeded the same way as in the reference
+/// implementation so that a given seed produces the same sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RandomState {
+    state: [u64; 4],
+}
+
+/// The seed used for new runtimes, so that scripts which never call
+/// `math.randomseed` behave the same on every run.
+pub(crate) const DEFAULT_SEED: i64 = 0;
+
+impl Default for RandomState {
+    fn default() -> Self {
+        Self::with_seed(DEFAULT_SEED, 0)
+    }
+}
+
+impl RandomState {
+    pub(crate) fn with_seed(seed: i64, extra: i64) -> Self {
+        let mut rng = Self {
+            state: [seed as u64, 0xff, extra as u64, 0],
+        };
+
+        // Discard the initial values to spread the seed over the whole state.
+        for _ in 0..16 {
+            rng.next_u64();
+        }
+
+        rng
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+
+    /// Produces a float uniformly distributed in `[0, 1)`.
+    pub(crate) fn next_float(&mut self) -> f64 {
+        // Only the top 53 bits fit in the mantissa.
+        (self.next_u64() >> 11) as f64 * 2f64.powi(-53)
+    }
+
+    /// Produces an integer uniformly distributed in `[low, high]`, which must
+    /// not be empty.
+    pub(crate) fn next_in_range(&mut self, low: i64, high: i64) -> i64 {
+        debug_assert!(low <= high);
+
+        let span = (high as u64).wrapping_sub(low as u64);
+        let mut value = self.next_u64();
+
+        if span & span.wrapping_add(1) != 0 {
+            // Not a power of two, so reject values outside of the span rather than biasing
+            // the result towards its lower end.
+            let mask = u64::MAX >> span.leading_zeros();
+            value &= mask;
+            while value > span {
+                value = self.next_u64() & mask;
+            }
+        } else {
+            value &= span;
+        }
+
+        value.wrapping_add(low as u64) as i64
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::Write;
+
+use tlua_bytecode::Number;
+use tlua_strings::LuaString;
+
+use crate::vm::runtime::{
+    value::DEFAULT_MAX_DEPTH,
+    Table,
+    Value,
+};
+
+/// Controls how [`Value::display_with`] renders values.
+///
+/// The default options match the output of Lua's `tostring`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of digits to render after the decimal point of floats. If
+    /// unset, floats are rendered the same way `tostring` would.
+    pub float_precision: Option<usize>,
+    /// Render integers as hexadecimal rather than decimal.
+    pub hex_integers: bool,
+    /// The number of levels of nested tables to expand. Tables beyond this
+    /// depth are rendered by address, just like `tostring`.
+    pub table_depth: usize,
+    /// The number of levels of nested tables which may be expanded,
+    /// regardless of `table_depth`. Tables beyond this depth are rendered as
+    /// `<...>`.
+    pub max_depth: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            float_precision: None,
+            hex_integers: false,
+            table_depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl Value {
+    /// Render this value as a string using the provided options.
+    ///
+    /// A table which contains itself is rendered as `<cycle>` where it would
+    /// otherwise be expanded again.
+    pub fn display_with(&self, opts: &FormatOptions) -> String {
+        let mut out = String::new();
+        self.write_with(&mut out, opts, &mut vec![])
+            .expect("Writing to a string should not fail");
+        out
+    }
+
+    /// Writes the value, where `path` holds the tables currently being
+    /// expanded.
+    fn write_with(
+        &self,
+        out: &mut String,
+        opts: &FormatOptions,
+        path: &mut Vec<*const Table>,
+    ) -> std::fmt::Result {
+        let depth = path.len();
+
+        match self {
+            Value::Nil => out.write_str("nil"),
+            Value::Bool(b) => write!(out, "{}", b),
+            Value::Number(Number::Integer(i)) => {
+                if opts.hex_integers {
+                    write!(out, "{:#x}", i)
+                } else {
+                    write!(out, "{}", i)
+                }
+            }
+            Value::Number(Number::Float(f)) => match opts.float_precision {
+                Some(precision) => write!(out, "{:.*}", precision, f),
+                None => write!(out, "{}", LuaString::from(*f)),
+            },
+            Value::String(s) => {
+                if depth == 0 {
+                    write!(out, "{}", s.borrow())
+                } else {
+                    write!(out, "{:?}", s.borrow())
+                }
+            }
+            Value::Table(t) => {
+                let table = t.borrow();
+                if depth >= opts.table_depth {
+                    return write!(out, "table: {:p}", &*table);
+                }
+                if depth >= opts.max_depth {
+                    return out.write_str("<...>");
+                }
+                if path.contains(&(&*table as *const Table)) {
+                    return out.write_str("<cycle>");
+                }
+
+                path.push(&*table);
+                let entries = table
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut entry = String::new();
+                        entry.push('[');
+                        key.write_with(&mut entry, opts, path)?;
+                        entry.push_str("] = ");
+                        value.write_with(&mut entry, opts, path)?;
+                        Ok(entry)
+                    })
+                    .collect::<Result<Vec<_>, std::fmt::Error>>();
+                path.pop();
+                let mut entries = entries?;
+
+                // Table iteration order is arbitrary, so sort the entries to keep the output
+                // stable.
+                entries.sort_unstable();
+                write!(out, "{{{}}}", entries.join(", "))
+            }
+            Value::Function(f) => write!(out, "function: {:p}", &*f.borrow()),
+            Value::NativeFunction(f) => write!(out, "function: {:p}", f.as_ptr()),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display_with(&FormatOptions::default()))
+    }
+}
@@ -10,39 +10,63 @@ use derive_more::{
     Into,
 };
 use tlua_bytecode::{
-    opcodes::ScopeDescriptor,
+    opcodes::{
+        LoadRegister,
+        Op,
+        ScopeDescriptor,
+        Store,
+    },
     MappedRegister,
     Register,
 };
-use tlua_compiler::FuncId;
+use tlua_compiler::{
+    Chunk,
+    FuncId,
+};
 use tracing_rc::{
     rc::Trace,
     Trace,
 };
 
-use crate::vm::runtime::Value;
+use crate::vm::runtime::{
+    memory,
+    Gc,
+    Table,
+    Value,
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct Scope {
-    pub registers: Rc<Vec<RefCell<Value>>>,
+    // Each register is individually shared so that the global scopes of separately compiled
+    // chunks can refer to the same variables.
+    pub registers: Rc<Vec<Rc<RefCell<Value>>>>,
 }
 
 impl Scope {
     pub fn new(size: usize) -> Self {
         Self {
-            registers: Rc::new(vec![RefCell::new(Value::Nil); size]),
+            registers: Rc::new((0..size).map(|_| Rc::default()).collect()),
         }
     }
 
-    pub fn into_values(mut self) -> Vec<RefCell<Value>> {
-        Rc::make_mut(&mut self.registers).drain(..).collect()
+    pub(crate) fn from_cells(cells: Vec<Rc<RefCell<Value>>>) -> Self {
+        Self {
+            registers: Rc::new(cells),
+        }
     }
 }
 
 impl Trace for Scope {
     fn visit_children(&self, visitor: &mut tracing_rc::rc::GcVisitor) {
         for v in self.registers.iter() {
-            v.visit_children(visitor);
+            // Cells which are shared with something other than this scope, like the
+            // runtime's global variables, keep their values alive without the collector
+            // seeing it, so their values must not look like they're only owned by the
+            // scope. Only global variables share their cells, since closures share the
+            // scope as a whole, so cycles through upvalues are still collected.
+            if Rc::strong_count(v) == 1 {
+                v.visit_children(visitor);
+            }
         }
     }
 }
@@ -91,12 +115,15 @@ impl ScopeSet {
         }
     }
 
-    pub fn into_results(self) -> Results {
-        self.results
+    pub fn take_results(&mut self) -> Results {
+        std::mem::take(&mut self.results)
     }
 
-    pub fn into_results_and_va(self) -> (Results, VaArgs) {
-        (self.results, self.va_args)
+    pub fn take_results_and_va(&mut self) -> (Results, VaArgs) {
+        (
+            std::mem::take(&mut self.results),
+            std::mem::take(&mut self.va_args),
+        )
     }
 
     pub fn push_scope(&mut self, descriptor: ScopeDescriptor) {
@@ -124,7 +151,7 @@ impl ScopeSet {
     }
 
     pub fn extend_results(&mut self, other: impl IntoIterator<Item = Value>) {
-        self.results.extend(other.into_iter());
+        self.results.extend(other);
     }
 
     // TODO(perf): This shouldn't be cloning its values.
@@ -153,30 +180,145 @@ impl ScopeSet {
     }
 }
 
+/// Identifies which of a chunk's functions is executed by a [`Function`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum FuncRef {
+    /// The chunk's top-level code, e.g. for chunks produced by `load`.
+    Main,
+    Id(FuncId),
+}
+
+impl FuncRef {
+    pub(crate) fn func_id(self) -> FuncId {
+        match self {
+            FuncRef::Main => FuncId::MAIN,
+            FuncRef::Id(id) => id,
+        }
+    }
+
+    pub(crate) fn definition(self, chunk: &Chunk) -> &tlua_compiler::Function {
+        match self {
+            FuncRef::Main => &chunk.main,
+            FuncRef::Id(id) => &chunk.functions[usize::from(id)],
+        }
+    }
+}
+
 #[derive(Debug, Trace)]
 pub struct Function {
     pub(crate) referenced_scopes: Vec<Scope>,
 
+    // The table holding the function's globals in place of the runtime's globals, see
+    // `Runtime::execute_with_env`.
+    pub(crate) env: Option<Gc<Table>>,
+
     #[trace(ignore)]
-    pub(crate) id: FuncId,
+    pub(crate) chunk: Rc<Chunk>,
+    #[trace(ignore)]
+    pub(crate) id: FuncRef,
 }
 
 impl Function {
-    pub(crate) fn new(available_scope: &ScopeSet, id: FuncId) -> Self {
+    pub(crate) fn new(
+        available_scope: &ScopeSet,
+        env: Option<Gc<Table>>,
+        chunk: Rc<Chunk>,
+        id: FuncId,
+    ) -> Self {
         // TODO(perf): This is way too pessimistic and could use info from the compiler
         // to cut down on the size of the scopes it captures.
         let mut referenced_scopes = available_scope.referenced.clone();
         referenced_scopes.extend(std::iter::once(available_scope.local.clone()));
         Self {
             referenced_scopes,
-            id,
+            env,
+            chunk,
+            id: FuncRef::Id(id),
+        }
+        .accounted()
+    }
+
+    /// Wraps the top-level code of `chunk` so that it can be called like any
+    /// other function.
+    pub(crate) fn main(chunk: Rc<Chunk>, global_scope: Scope) -> Self {
+        Self {
+            referenced_scopes: vec![global_scope],
+            env: None,
+            chunk,
+            id: FuncRef::Main,
+        }
+        .accounted()
+    }
+
+    /// Creates the function `id` of `chunk` outside of the code which declares
+    /// it. The scopes enclosing its declaration aren't shared with anything,
+    /// so fresh scopes stand in for them, and every upvalue starts out as
+    /// `nil`.
+    pub(crate) fn detached(chunk: Rc<Chunk>, global_scope: Scope, id: FuncId) -> Self {
+        let depth = chunk.functions[usize::from(id)].scope_depth;
+
+        // Any code in the chunk may refer to an enclosing scope, e.g. a closure
+        // created by the function, so each scope must fit every register used at
+        // its depth.
+        let mut sizes = vec![0; depth];
+        for instruction in std::iter::once(&chunk.main)
+            .chain(chunk.functions.iter())
+            .flat_map(|function| function.instructions.iter())
+        {
+            let register = match instruction {
+                Op::LoadRegister(LoadRegister {
+                    src: MappedRegister(register),
+                    ..
+                })
+                | Op::Store(Store {
+                    dst: MappedRegister(register),
+                    ..
+                }) => register,
+                _ => continue,
+            };
+
+            if let Some(size) = sizes.get_mut(usize::from(register.scope)) {
+                *size = (*size).max(usize::from(register.offset) + 1);
+            }
+        }
+
+        let referenced_scopes = std::iter::once(global_scope)
+            .chain(sizes.into_iter().skip(1).map(Scope::new))
+            .collect();
+
+        Self {
+            referenced_scopes,
+            env: None,
+            chunk,
+            id: FuncRef::Id(id),
         }
+        .accounted()
+    }
+
+    pub(crate) fn definition(&self) -> &tlua_compiler::Function {
+        self.id.definition(&self.chunk)
+    }
+
+    fn accounted(self) -> Self {
+        memory::allocated(self.allocated_bytes());
+        self
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.referenced_scopes.capacity() * std::mem::size_of::<Scope>()
+    }
+}
+
+impl Drop for Function {
+    fn drop(&mut self) {
+        memory::freed(self.allocated_bytes());
     }
 }
 
 impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        Rc::ptr_eq(&self.chunk, &other.chunk) && self.id == other.id
     }
 }
 
@@ -184,6 +326,7 @@ impl Eq for Function {}
 
 impl std::hash::Hash for Function {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::ptr::hash(Rc::as_ptr(&self.chunk), state);
         self.id.hash(state);
     }
 }
@@ -1,5 +1,6 @@
 use std::{
     cell::RefCell,
+    cmp::Ordering,
     fmt::Debug,
     hash::{
         Hash,
@@ -20,25 +21,156 @@ use tracing_rc::{
     Trace,
 };
 
+pub mod format;
 pub mod function;
+pub mod native_function;
+pub mod owned;
 pub mod table;
 
 pub use self::{
+    format::FormatOptions,
     function::Function,
+    native_function::NativeFunction,
+    owned::{
+        OwnedValue,
+        OwnedValueError,
+    },
     table::Table,
 };
 
-#[derive(Debug, Clone, Trace, From)]
+/// The default number of levels of nested tables which [`Value::deep_eq`] and
+/// [`Value::display_with`] will recurse into, which keeps pathologically deep
+/// tables from exhausting the stack.
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+#[derive(Debug, Clone, Default, Trace, From)]
 pub enum Value {
+    #[default]
     Nil,
     Bool(bool),
     Number(Number),
     String(Rc<RefCell<LuaString>>),
     Table(#[trace] Gc<Table>),
     Function(#[trace] Gc<Function>),
+    NativeFunction(NativeFunction),
 }
 
 impl Value {
+    /// The name of the value's type, as reported by LUA's `type` function.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Table(_) => "table",
+            Value::Function(_) | Value::NativeFunction(_) => "function",
+        }
+    }
+
+    /// Compares values, comparing the contents of distinct tables rather than
+    /// their identity. At most `max_depth` levels of tables are compared,
+    /// counting `self`, and distinct tables past that depth are considered
+    /// unequal.
+    ///
+    /// This is also how `==` compares values, with a `max_depth` of
+    /// [`DEFAULT_MAX_DEPTH`].
+    pub fn deep_eq(&self, other: &Self, max_depth: usize) -> bool {
+        match (self, other) {
+            (Self::Table(l0), Self::Table(r0)) => {
+                let (l0, r0) = (l0.borrow(), r0.borrow());
+                std::ptr::eq(&*l0, &*r0) || (max_depth > 0 && l0.eq_within(&r0, max_depth - 1))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares values by their contents, for hosts checking the results of
+    /// scripts. Unlike [`Value::deep_eq`], a pair of tables which is already
+    /// being compared further up is assumed to be equal, so tables which
+    /// contain cycles compare equal if they have the same shape.
+    ///
+    /// Tables nested more than [`DEFAULT_MAX_DEPTH`] levels deep are only
+    /// equal if they are the same table.
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        self.structural_eq_within(other, DEFAULT_MAX_DEPTH, &mut vec![])
+    }
+
+    pub(crate) fn structural_eq_within(
+        &self,
+        other: &Self,
+        max_depth: usize,
+        comparing: &mut Vec<(*const Table, *const Table)>,
+    ) -> bool {
+        match (self, other) {
+            (Self::Table(l0), Self::Table(r0)) => {
+                let (l0, r0) = (l0.borrow(), r0.borrow());
+                let pair = (&*l0 as *const Table, &*r0 as *const Table);
+
+                if std::ptr::eq(pair.0, pair.1) || comparing.contains(&pair) {
+                    return true;
+                }
+                if max_depth == 0 {
+                    return false;
+                }
+
+                comparing.push(pair);
+                let eq = l0.structural_eq_within(&r0, max_depth - 1, comparing);
+                comparing.pop();
+                eq
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Renders the value with every nested table expanded, for use in
+    /// assertions and debugging output.
+    pub fn to_debug_string(&self) -> String {
+        self.display_with(&FormatOptions {
+            table_depth: usize::MAX,
+            ..Default::default()
+        })
+    }
+
+    /// Compares values using a total order, for use when values need to be
+    /// sorted by the host, e.g. to produce deterministic output.
+    ///
+    /// Values are ordered by type first: nil, booleans, numbers, strings,
+    /// tables, functions, and native functions. Numbers are ordered by value,
+    /// with integers before floats of the same value and NaN after every other
+    /// number. Strings are ordered by their bytes, and tables and functions by
+    /// their address.
+    ///
+    /// Unlike Lua's `<`, this never fails, but it has no meaning to Lua code.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Nil, Self::Nil) => Ordering::Equal,
+            (Self::Bool(l0), Self::Bool(r0)) => l0.cmp(r0),
+            (Self::Number(l0), Self::Number(r0)) => total_cmp_numbers(l0, r0),
+            (Self::String(l0), Self::String(r0)) => l0.borrow().cmp(&*r0.borrow()),
+            (Self::Table(l0), Self::Table(r0)) => {
+                (&*l0.borrow() as *const Table).cmp(&(&*r0.borrow() as *const Table))
+            }
+            (Self::Function(l0), Self::Function(r0)) => {
+                (&*l0.borrow() as *const Function).cmp(&(&*r0.borrow() as *const Function))
+            }
+            (Self::NativeFunction(l0), Self::NativeFunction(r0)) => l0.as_ptr().cmp(&r0.as_ptr()),
+            _ => self.type_order().cmp(&other.type_order()),
+        }
+    }
+
+    fn type_order(&self) -> u8 {
+        match self {
+            Value::Nil => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Table(_) => 4,
+            Value::Function(_) => 5,
+            Value::NativeFunction(_) => 6,
+        }
+    }
+
     /// Hashes the value.
     ///
     /// # Warning
@@ -57,6 +189,10 @@ impl Value {
 
     /// Hashes the value using the provided hasher.
     ///
+    /// Values which compare equal hash the same. As with [`Number::hash_into`],
+    /// NaN hashes like every other NaN despite never being equal to anything,
+    /// which is why it may not be used as a table key.
+    ///
     /// # Warning
     /// You may not rely on equal hash values implying equal values. i.e. the
     /// following may panic:
@@ -75,14 +211,28 @@ impl Value {
             Value::String(s) => s.borrow().hash(hasher),
             Value::Table(t) => std::ptr::hash(&*t.borrow(), hasher),
             Value::Function(f) => f.borrow().hash(hasher),
+            Value::NativeFunction(f) => f.hash(hasher),
         }
     }
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Self::Nil
-    }
+fn total_cmp_numbers(lhs: &Number, rhs: &Number) -> Ordering {
+    let (lhs_f, rhs_f) = ((&lhs).as_float(), (&rhs).as_float());
+    let by_value = match (lhs_f.filter(|f| !f.is_nan()), rhs_f.filter(|f| !f.is_nan())) {
+        (Some(l0), Some(r0)) => l0.partial_cmp(&r0).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+
+    // Distinct integers may convert to the same float, so fall back to comparing
+    // them exactly.
+    by_value.then_with(|| match (lhs, rhs) {
+        (Number::Integer(l0), Number::Integer(r0)) => l0.cmp(r0),
+        (Number::Integer(_), Number::Float(_)) => Ordering::Less,
+        (Number::Float(_), Number::Integer(_)) => Ordering::Greater,
+        (Number::Float(_), Number::Float(_)) => Ordering::Equal,
+    })
 }
 
 impl PartialEq for Value {
@@ -91,8 +241,9 @@ impl PartialEq for Value {
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
             (Self::Number(l0), Self::Number(r0)) => l0 == r0,
             (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Table(l0), Self::Table(r0)) => l0 == r0,
-            (Self::Function(l0), Self::Function(r0)) => l0.borrow().id == r0.borrow().id,
+            (Self::Table(_), Self::Table(_)) => self.deep_eq(other, DEFAULT_MAX_DEPTH),
+            (Self::Function(l0), Self::Function(r0)) => *l0.borrow() == *r0.borrow(),
+            (Self::NativeFunction(l0), Self::NativeFunction(r0)) => l0 == r0,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
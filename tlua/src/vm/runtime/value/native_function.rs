@@ -0,0 +1,68 @@
+use std::rc::Rc;
+
+use tlua_bytecode::OpError;
+
+use crate::vm::runtime::{
+    execution_context::Context,
+    Value,
+};
+
+/// The signature of a function implemented in Rust which can be called from
+/// LUA code.
+pub type NativeFn =
+    dyn for<'call> Fn(&mut Context<'call>, Vec<Value>) -> Result<Vec<Value>, OpError>;
+
+/// A function implemented in Rust, such as a member of the standard library.
+///
+/// Native functions compare equal only to themselves (or clones of
+/// themselves).
+#[derive(Clone)]
+pub struct NativeFunction {
+    name: &'static str,
+    func: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(
+        name: &'static str,
+        func: impl for<'call> Fn(&mut Context<'call>, Vec<Value>) -> Result<Vec<Value>, OpError>
+            + 'static,
+    ) -> Self {
+        Self {
+            name,
+            func: Rc::new(func),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn call(&self, context: &mut Context, args: Vec<Value>) -> Result<Vec<Value>, OpError> {
+        (self.func)(context, args)
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.func) as *const ()
+    }
+}
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.as_ptr(), other.as_ptr())
+    }
+}
+
+impl Eq for NativeFunction {}
+
+impl std::hash::Hash for NativeFunction {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.as_ptr(), state);
+    }
+}
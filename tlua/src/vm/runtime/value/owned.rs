@@ -0,0 +1,146 @@
+use thiserror::Error;
+use tlua_bytecode::{
+    Number,
+    OpError,
+};
+use tlua_strings::LuaString;
+
+use crate::vm::runtime::{
+    value::DEFAULT_MAX_DEPTH,
+    Gc,
+    Runtime,
+    Table,
+    Value,
+};
+
+/// A plain copy of a [`Value`] which doesn't reference anything owned by the
+/// runtime, for passing data between LUA code and the host.
+///
+/// Tables are copied along with every table nested inside of them, so changes
+/// to the copy aren't visible to LUA and vice versa. Metatables aren't copied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedValue {
+    Nil,
+    Bool(bool),
+    Number(Number),
+    String(Vec<u8>),
+    /// A table whose keys are exactly `1..=n`, with its values in order.
+    Array(Vec<OwnedValue>),
+    /// Any other table, with its entries in the order [`Table::iter`] visits
+    /// them.
+    Map(Vec<(OwnedValue, OwnedValue)>),
+}
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum OwnedValueError {
+    #[error("a {type_name} can't be converted to an owned value")]
+    Unsupported { type_name: &'static str },
+    #[error("table contains itself")]
+    Cycle,
+    #[error("tables are nested too deeply")]
+    TooDeep,
+    #[error("invalid table key: {0}")]
+    InvalidKey(OpError),
+}
+
+impl Value {
+    /// Copies the value out of the runtime.
+    ///
+    /// Functions can't be copied, and neither can tables which contain
+    /// themselves or are nested more than [`DEFAULT_MAX_DEPTH`] levels deep.
+    /// A table which is referenced more than once without forming a cycle is
+    /// copied once for each reference.
+    pub fn to_owned_value(&self) -> Result<OwnedValue, OwnedValueError> {
+        self.to_owned_within(&mut vec![])
+    }
+
+    /// Copies the value, where `path` holds the tables currently being copied.
+    fn to_owned_within(&self, path: &mut Vec<*const Table>) -> Result<OwnedValue, OwnedValueError> {
+        Ok(match self {
+            Value::Nil => OwnedValue::Nil,
+            Value::Bool(b) => OwnedValue::Bool(*b),
+            Value::Number(n) => OwnedValue::Number(*n),
+            Value::String(s) => OwnedValue::String(s.borrow().to_vec()),
+            Value::Table(table) => {
+                let table = table.borrow();
+                let ptr = &*table as *const Table;
+
+                if path.contains(&ptr) {
+                    return Err(OwnedValueError::Cycle);
+                }
+                if path.len() >= DEFAULT_MAX_DEPTH {
+                    return Err(OwnedValueError::TooDeep);
+                }
+
+                path.push(ptr);
+                let owned = table_to_owned(&table, path);
+                path.pop();
+                owned?
+            }
+            Value::Function(_) | Value::NativeFunction(_) => {
+                return Err(OwnedValueError::Unsupported {
+                    type_name: self.type_name(),
+                })
+            }
+        })
+    }
+}
+
+fn table_to_owned(
+    table: &Table,
+    path: &mut Vec<*const Table>,
+) -> Result<OwnedValue, OwnedValueError> {
+    // The sequence is always visited first, so a table with no other entries is
+    // an array.
+    let is_array = table.border() > 0 && table.iter().count() as i64 == table.border();
+
+    if is_array {
+        table
+            .iter()
+            .map(|(_, value)| value.to_owned_within(path))
+            .collect::<Result<_, _>>()
+            .map(OwnedValue::Array)
+    } else {
+        table
+            .iter()
+            .map(|(key, value)| Ok((key.to_owned_within(path)?, value.to_owned_within(path)?)))
+            .collect::<Result<_, _>>()
+            .map(OwnedValue::Map)
+    }
+}
+
+impl Runtime {
+    /// Copies `value` into the runtime, allocating a new table for each array
+    /// or map.
+    ///
+    /// Fails if a map has a key which can't be stored in a table, i.e. nil or
+    /// NaN. Entries with a nil value are skipped, just as assigning nil to a
+    /// field does.
+    pub fn from_owned_value(&mut self, value: OwnedValue) -> Result<Value, OwnedValueError> {
+        Ok(match value {
+            OwnedValue::Nil => Value::Nil,
+            OwnedValue::Bool(b) => Value::Bool(b),
+            OwnedValue::Number(n) => Value::Number(n),
+            OwnedValue::String(s) => Value::from(LuaString::from(&s[..])),
+            OwnedValue::Array(values) => {
+                let mut table = Table::default();
+                for (index, value) in values.into_iter().enumerate() {
+                    let value = self.from_owned_value(value)?;
+                    table
+                        .set(Value::from(index as i64 + 1), value)
+                        .map_err(OwnedValueError::InvalidKey)?;
+                }
+                Value::Table(Gc::new(table))
+            }
+            OwnedValue::Map(entries) => {
+                let mut table = Table::default();
+                for (key, value) in entries {
+                    let key = self.from_owned_value(key)?;
+                    let value = self.from_owned_value(value)?;
+                    table.set(key, value).map_err(OwnedValueError::InvalidKey)?;
+                }
+                Value::Table(Gc::new(table))
+            }
+        })
+    }
+}
@@ -1,27 +1,570 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+};
 
+use indexmap::IndexMap;
 use tlua_bytecode::{
+    binop::f64inbounds,
     Number,
     OpError,
 };
-use tracing_rc::Trace;
+use tracing_rc::{
+    rc::{
+        Gc,
+        GcVisitor,
+        Trace,
+    },
+    Trace,
+};
 
-use crate::vm::runtime::Value;
+use crate::vm::runtime::{
+    memory,
+    value::DEFAULT_MAX_DEPTH,
+    Value,
+};
 
-#[derive(Debug, Default, PartialEq, Trace)]
+/// A LUA table.
+///
+/// Like reference LUA, values for small positive integer keys live in a
+/// contiguous array, and every other entry lives in a hash map. The hash map
+/// never holds a value for a key which has a slot in the array, nor for the
+/// key just past the end of the array.
+///
+/// Removing a value from the array leaves a nil slot behind rather than
+/// moving the values after it, so that removal is cheap and a traversal can
+/// continue past it. The length of the leading run of non-nil values is
+/// tracked separately, and is always exactly the table's sequence. Trailing
+/// nil slots are discarded the next time a key is added which doesn't fit in
+/// the array.
+///
+/// The hash map keeps its entries in the order they were inserted, so that
+/// traversing a table with `next` is deterministic. Removing an entry leaves
+/// its key behind with a nil value, for the same reason as the array. These
+/// dead keys are discarded the next time a new key is added to the hash map,
+/// which LUA doesn't allow during a traversal.
+#[derive(Debug)]
 pub struct Table {
-    pub entries: HashMap<TableKey, Value>,
+    array: Vec<Value>,
+    /// The number of non-nil values at the start of `array`.
+    sequence_len: usize,
+    /// Every slot after the one at `sequence_len` & before this one is known to
+    /// be non-nil, so that filling a hole at the end of the sequence doesn't
+    /// have to scan the values after it.
+    known_live_until: usize,
+    hash: IndexMap<TableKey, Value>,
+    /// The number of dead keys in `hash`.
+    dead_keys: usize,
+    pub metatable: Option<Gc<Table>>,
+    /// Which references held by the table are weak, as requested by the
+    /// `__mode` field of its metatable when it was set by `setmetatable`.
+    mode: WeakMode,
+    /// The size of the table as last reported to the memory usage counter.
+    accounted_bytes: usize,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        let mut table = Self {
+            array: Default::default(),
+            sequence_len: 0,
+            known_live_until: 0,
+            hash: Default::default(),
+            dead_keys: 0,
+            metatable: None,
+            mode: Default::default(),
+            accounted_bytes: 0,
+        };
+        table.update_accounting();
+        table
+    }
+}
+
+/// The weak references held by a table.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct WeakMode {
+    keys: bool,
+    values: bool,
+}
+
+impl WeakMode {
+    fn from_metatable(metatable: &Table) -> Self {
+        match metatable.get(&"__mode".into()) {
+            Value::String(mode) => {
+                let mode = mode.borrow();
+                Self {
+                    keys: mode.contains(&b'k'),
+                    values: mode.contains(&b'v'),
+                }
+            }
+            _ => Self::default(),
+        }
+    }
+
+    fn is_weak(self) -> bool {
+        self.keys || self.values
+    }
+}
+
+thread_local! {
+    // Weak tables are kept here so that their unreachable entries can be cleared after each
+    // collection. As this holds a strong reference, a weak table is only dropped once this is
+    // the last reference to it, so one which is part of a reference cycle through its strong
+    // entries is never freed.
+    static WEAK_TABLES: RefCell<Vec<Gc<Table>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes the entries of weak tables whose weak key or value is no longer
+/// referenced outside of the table.
+pub(crate) fn sweep_weak_tables() {
+    WEAK_TABLES.with(|tables| {
+        let mut tables = tables.borrow_mut();
+
+        // Clearing entries from one table can release the last reference to an entry of
+        // another, so sweep until nothing changes.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            tables.retain(|table| {
+                if Gc::strong_count(table) == 1 {
+                    changed = true;
+                    return false;
+                }
+
+                match table.try_borrow_mut() {
+                    Some(mut table) if table.mode.is_weak() => {
+                        changed |= table.remove_collected_entries();
+                        true
+                    }
+                    Some(_) => false,
+                    // Tables in use can't be swept, so try again after the next collection.
+                    None => true,
+                }
+            });
+        }
+    });
+}
+
+impl Trace for Table {
+    fn visit_children(&self, visitor: &mut GcVisitor) {
+        // Weak references aren't owned by the table, their entries are cleared by
+        // `sweep_weak_tables` once nothing else refers to them.
+        if !self.mode.values {
+            self.array.visit_children(visitor);
+        }
+
+        for (key, value) in self.hash.iter() {
+            if !self.mode.keys {
+                key.visit_children(visitor);
+            }
+            if !self.mode.values {
+                value.visit_children(visitor);
+            }
+        }
+
+        self.metatable.visit_children(visitor);
+    }
+}
+
+impl Table {
+    /// Reads the value stored for `key` without consulting any metamethods.
+    /// Keys which can never be stored, such as NaN, simply have no value.
+    ///
+    /// Like all table operations, floats with an integral value refer to the
+    /// same entry as the equivalent integer, so `t[2.0]` is `t[2]`.
+    pub fn get(&self, key: &Value) -> Value {
+        if let Some(index) = self.array_index(key) {
+            return self.array[index].clone();
+        }
+
+        TableKey::try_from(key.clone())
+            .ok()
+            .and_then(|key| self.hash.get(&key).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Stores `value` for `key` without consulting any metamethods. Storing
+    /// `nil` removes the entry.
+    pub fn set(&mut self, key: Value, value: Value) -> Result<(), OpError> {
+        if key == Value::Nil {
+            return Err(OpError::TableIndexNil);
+        }
+
+        let key = TableKey::try_from(key)?;
+        self.insert(key, value);
+
+        Ok(())
+    }
+
+    /// Finds a border of the table, i.e. the length of its sequence, without
+    /// consulting any metamethods.
+    pub fn border(&self) -> i64 {
+        // The array holds the entire sequence, see the invariants on `Table`.
+        self.sequence_len as i64
+    }
+
+    /// Looks up the metamethod `name`, e.g. `__index`, in the table's
+    /// metatable. Returns nil if there is no metatable or it has no such
+    /// field.
+    pub fn metamethod(&self, name: &str) -> Value {
+        self.metatable
+            .as_ref()
+            .map(|metatable| metatable.borrow().get(&name.into()))
+            .unwrap_or_default()
+    }
+
+    /// Replaces the metatable of `table`. If the new metatable has a `__mode`
+    /// field containing `k` or `v`, the table's keys or values become weak
+    /// references, and entries whose weak key or value is referenced by
+    /// nothing but the table are removed after the next collection.
+    ///
+    /// Weak keys and values which are part of a reference cycle are always
+    /// considered to be referenced, so their entries are kept.
+    pub(crate) fn set_metatable(table: &Gc<Table>, metatable: Option<Gc<Table>>) {
+        let mode = metatable
+            .as_ref()
+            .map(|metatable| WeakMode::from_metatable(&metatable.borrow()))
+            .unwrap_or_default();
+
+        let mut this = table.borrow_mut();
+        if mode.is_weak() && !this.mode.is_weak() {
+            WEAK_TABLES.with(|tables| tables.borrow_mut().push(table.clone()));
+        }
+
+        this.metatable = metatable;
+        this.mode = mode;
+    }
+
+    /// Iterates over every entry in the table. The entries in the array are
+    /// visited in order, followed by the remaining entries in the order they
+    /// were added.
+    /// This is the same order as traversing the table with [`Table::next`].
+    pub fn iter(&self) -> impl Iterator<Item = (Value, &Value)> + '_ {
+        self.array
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| is_live(value))
+            .map(|(index, value)| (Value::from(index as i64 + 1), value))
+            .chain(
+                self.live_entries()
+                    .map(|(key, value)| (key.0.clone(), value)),
+            )
+    }
+
+    /// Copies every entry in the table into a map, as they were stored before
+    /// tables were split into an array and a hash map.
+    #[deprecated(note = "use `Table::iter`, which doesn't copy the entries")]
+    // Keys hash tables & functions by identity, and strings are never mutated.
+    #[allow(clippy::mutable_key_type)]
+    pub fn entries(&self) -> HashMap<TableKey, Value> {
+        self.iter()
+            .map(|(key, value)| (TableKey(key), value.clone()))
+            .collect()
+    }
+
+    /// Finds the entry following `key` when traversing the table, or the first
+    /// entry if `key` is nil, without consulting any metamethods. Returns
+    /// `None` once every entry has been visited.
+    ///
+    /// Entries may be cleared during a traversal, but adding new entries makes
+    /// the rest of the traversal fail.
+    pub fn next(&self, key: &Value) -> Result<Option<(Value, Value)>, OpError> {
+        let (array_start, hash_start) = match key {
+            Value::Nil => (0, 0),
+            key => match self.array_index(key) {
+                Some(index) => (index + 1, 0),
+                None => {
+                    let table_key = TableKey::try_from(key.clone())?;
+                    match self.hash.get_index_of(&table_key) {
+                        Some(index) => (self.array.len(), index + 1),
+                        None => return Err(OpError::InvalidNextKey),
+                    }
+                }
+            },
+        };
+
+        if let Some((index, value)) = self
+            .array
+            .iter()
+            .enumerate()
+            .skip(array_start)
+            .find(|(_, value)| is_live(value))
+        {
+            return Ok(Some(((index as i64 + 1).into(), value.clone())));
+        }
+
+        Ok(self
+            .hash
+            .iter()
+            .skip(hash_start)
+            .find(|(_, value)| is_live(value))
+            .map(|(key, value)| (key.0.clone(), value.clone())))
+    }
+
+    pub(crate) fn insert(&mut self, key: TableKey, value: Value) {
+        let index = self.array_index(&key.0);
+
+        match (index, value) {
+            (Some(index), Value::Nil) => {
+                self.array[index] = Value::Nil;
+                if index < self.sequence_len {
+                    self.known_live_until = self.sequence_len;
+                    self.sequence_len = index;
+                } else {
+                    self.known_live_until = self.known_live_until.min(index);
+                }
+            }
+            (Some(index), value) => {
+                self.array[index] = value;
+                self.extend_sequence();
+            }
+            (None, Value::Nil) => {
+                if let Some(value) = self.hash.get_mut(&key).filter(|value| is_live(value)) {
+                    *value = Value::Nil;
+                    self.dead_keys += 1;
+                }
+            }
+            (None, value) => match self.hash.get_mut(&key).filter(|value| is_live(value)) {
+                Some(existing) => *existing = value,
+                None => self.insert_new(key, value),
+            },
+        }
+
+        self.update_accounting();
+    }
+
+    fn insert_new(&mut self, key: TableKey, value: Value) {
+        self.remove_dead_keys();
+
+        // No traversal can be in progress, so slots which were removed from the end of
+        // the array can be reclaimed.
+        while self.array.last().is_some_and(|value| !is_live(value)) {
+            self.array.pop();
+        }
+
+        if self.is_next_array_key(&key.0) {
+            self.array.push(value);
+
+            // The new value may connect the array to values already in the hash. Their
+            // order doesn't matter, as adding a key ends any traversal.
+            while let Some(value) = self.hash.swap_remove(&TableKey(self.next_array_key())) {
+                self.array.push(value);
+            }
+
+            self.extend_sequence();
+        } else {
+            self.hash.insert(key, value);
+        }
+    }
+
+    /// Extends the sequence over any values following it in the array, after
+    /// one has been added at its end.
+    fn extend_sequence(&mut self) {
+        if self.array.get(self.sequence_len).is_some_and(is_live) {
+            self.sequence_len = self.known_live_until.max(self.sequence_len + 1);
+        }
+
+        while self.array.get(self.sequence_len).is_some_and(is_live) {
+            self.sequence_len += 1;
+        }
+    }
+
+    fn remove_dead_keys(&mut self) {
+        if self.dead_keys > 0 {
+            self.hash.retain(|_, value| is_live(value));
+            self.dead_keys = 0;
+        }
+    }
+
+    /// The entries of the hash map which haven't been removed.
+    fn live_entries(&self) -> impl Iterator<Item = (&TableKey, &Value)> + '_ {
+        self.hash.iter().filter(|(_, value)| is_live(value))
+    }
+
+    /// The number of entries in the table.
+    fn live_len(&self) -> usize {
+        self.array.iter().filter(|value| is_live(value)).count() + self.hash.len() - self.dead_keys
+    }
+
+    /// Reports any change in the memory allocated by the table since it was
+    /// last reported.
+    fn update_accounting(&mut self) {
+        let bytes = std::mem::size_of::<Self>()
+            + self.array.capacity() * std::mem::size_of::<Value>()
+            + self.hash.capacity() * std::mem::size_of::<(TableKey, Value)>();
+
+        if bytes > self.accounted_bytes {
+            memory::allocated(bytes - self.accounted_bytes);
+        } else {
+            memory::freed(self.accounted_bytes - bytes);
+        }
+        self.accounted_bytes = bytes;
+    }
+
+    /// Compares the entries and metatables of two tables, recursing at most
+    /// `max_depth` levels into nested tables.
+    pub(crate) fn eq_within(&self, other: &Self, max_depth: usize) -> bool {
+        let metatables_eq = match (&self.metatable, &other.metatable) {
+            (None, None) => true,
+            (Some(l), Some(r)) => {
+                Value::Table(l.clone()).deep_eq(&Value::Table(r.clone()), max_depth)
+            }
+            _ => false,
+        };
+
+        metatables_eq
+            && self.live_len() == other.live_len()
+            && self.iter().all(|(key, l)| {
+                let r = other.get(&key);
+                is_live(&r) && l.deep_eq(&r, max_depth)
+            })
+    }
+
+    /// Compares the entries and metatables of two tables like
+    /// [`Table::eq_within`], but treats the pairs of tables in `comparing` as
+    /// equal. See [`Value::structural_eq`].
+    pub(crate) fn structural_eq_within(
+        &self,
+        other: &Self,
+        max_depth: usize,
+        comparing: &mut Vec<(*const Table, *const Table)>,
+    ) -> bool {
+        let metatables_eq = match (&self.metatable, &other.metatable) {
+            (None, None) => true,
+            (Some(l), Some(r)) => Value::Table(l.clone()).structural_eq_within(
+                &Value::Table(r.clone()),
+                max_depth,
+                comparing,
+            ),
+            _ => false,
+        };
+
+        metatables_eq
+            && self.live_len() == other.live_len()
+            && self.iter().all(|(key, l)| {
+                let r = other.get(&key);
+                is_live(&r) && l.structural_eq_within(&r, max_depth, comparing)
+            })
+    }
+
+    /// Removes the entries whose weak key or value is only referenced by this
+    /// table. Returns whether any entries were removed.
+    fn remove_collected_entries(&mut self) -> bool {
+        let mode = self.mode;
+        let collected = self
+            .iter()
+            .filter(|(key, value)| {
+                (mode.keys && is_collected(key, 1)) || (mode.values && is_collected(value, 0))
+            })
+            .map(|(key, _)| TableKey(key))
+            .collect::<Vec<_>>();
+
+        let removed = !collected.is_empty();
+        for key in collected {
+            self.insert(key, Value::Nil);
+        }
+        // Dead keys would keep weak keys alive.
+        self.remove_dead_keys();
+        self.update_accounting();
+
+        removed
+    }
+
+    /// Removes the tables nested directly in this one, leaving the other
+    /// entries in place.
+    fn take_nested_tables(&mut self) -> impl Iterator<Item = Gc<Table>> {
+        self.sequence_len = 0;
+        self.known_live_until = 0;
+        self.dead_keys = 0;
+        std::mem::take(&mut self.array)
+            .into_iter()
+            .chain(std::mem::take(&mut self.hash).into_values())
+            .filter_map(|value| match value {
+                Value::Table(t) => Some(t),
+                _ => None,
+            })
+            .chain(self.metatable.take())
+    }
+
+    fn array_index(&self, key: &Value) -> Option<usize> {
+        let key = match key {
+            Value::Number(Number::Integer(i)) => *i,
+            Value::Number(Number::Float(f)) => f64inbounds(*f).ok()?,
+            _ => return None,
+        };
+
+        usize::try_from(key)
+            .ok()
+            .and_then(|i| i.checked_sub(1))
+            .filter(|&index| index < self.array.len())
+    }
+
+    fn is_next_array_key(&self, key: &Value) -> bool {
+        matches!(key, Value::Number(Number::Integer(i)) if *i == self.array.len() as i64 + 1)
+    }
+
+    fn next_array_key(&self) -> Value {
+        Value::from(self.array.len() as i64 + 1)
+    }
+}
+
+/// Whether `value` is the value of an entry rather than a dead key.
+fn is_live(value: &Value) -> bool {
+    !matches!(value, Value::Nil)
+}
+
+/// Whether `value` is a reference which nothing but the table holding it
+/// refers to. `extra_refs` is the number of references to `value` held by the
+/// caller in addition to the table's.
+fn is_collected(value: &Value, extra_refs: usize) -> bool {
+    let strong_count = match value {
+        Value::Table(t) => Gc::strong_count(t),
+        Value::Function(f) => Gc::strong_count(f),
+        _ => return false,
+    };
+
+    strong_count <= 1 + extra_refs
 }
 
 #[derive(Debug, Clone, Trace)]
 pub struct TableKey(Value);
 
+impl AsRef<Value> for TableKey {
+    fn as_ref(&self) -> &Value {
+        &self.0
+    }
+}
+
 impl PartialEq for TableKey {
     fn eq(&self, other: &Self) -> bool {
         self.0 == other.0
     }
 }
 
+impl Drop for Table {
+    fn drop(&mut self) {
+        memory::freed(self.accounted_bytes);
+
+        // Dropping nested tables recursively can exhaust the stack for deeply nested
+        // tables, so any which would be freed along with this one are emptied
+        // iteratively instead.
+        let mut pending = self.take_nested_tables().collect::<Vec<_>>();
+        while let Some(table) = pending.pop() {
+            if Gc::strong_count(&table) == 1 {
+                if let Some(mut table) = table.try_borrow_mut() {
+                    pending.extend(table.take_nested_tables());
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for Table {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other) || self.eq_within(other, DEFAULT_MAX_DEPTH)
+    }
+}
+
 // We validate in TryFrom that no NaNs exist.
 impl Eq for TableKey {}
 
@@ -33,6 +576,9 @@ impl TryFrom<Value> for TableKey {
             Value::Number(Number::Float(f)) => {
                 if f.is_nan() {
                     Err(OpError::TableIndexNaN)
+                } else if let Ok(i) = f64inbounds(f) {
+                    // Floats with an integral value are stored as the equivalent integer.
+                    Ok(Self(Value::Number(Number::Integer(i))))
                 } else {
                     Ok(Self(Value::Number(Number::Float(f))))
                 }
@@ -0,0 +1,156 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+};
+
+#[test]
+fn swap() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b = 1, 2
+        a, b = b, a
+
+        local t = {10, 20}
+        t[1], t[2] = t[2], t[1]
+
+        return a, b, t[1], t[2]
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![2.into(), 1.into(), 20.into(), 10.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn missing_values_are_nil() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b, c = 5, 5, 5
+        a, b, c = 1, 2
+        return a, b, c
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn trailing_call_spreads() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f()
+            return 1, 2, 3
+        end
+
+        local a, b, c, d = 0, 0, 0, 0
+        a, b, c = f()
+        d = f()
+
+        local w, x, y, z = 4, f()
+
+        return a, b, c, d, w, x, y, z
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1.into(),
+            2.into(),
+            3.into(),
+            1.into(),
+            4.into(),
+            1.into(),
+            2.into(),
+            3.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parenthesized_call_truncates() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f()
+            return 1, 2, 3
+        end
+
+        local a, b = 0, 0
+        a, b = (f())
+
+        return a, b
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn targets_use_values_from_before_assignment() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local i = 1
+        local t = {}
+        i, t[i] = i + 1, 20
+
+        local x = 1
+        do
+            local x = x + 1
+            return i, t[1], t[2], x
+        end
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![2.into(), 20.into(), Value::Nil, 2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
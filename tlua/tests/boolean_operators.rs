@@ -0,0 +1,173 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+};
+
+#[test]
+fn or_chain_picks_first_truthy() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b, c = nil, false, 7
+        return a or b or c, nil or false or 7
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![7.into(), 7.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn or_chain_all_falsy_picks_last() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b, c = nil, false, nil
+        return a or b or c, b or a or b
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![Value::Nil, false.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn and_chain_picks_last_when_truthy() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b, c = 1, 2, 3
+        return a and b and c, 1 and 2 and 3
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn and_chain_short_circuits() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b = 1, nil
+        return a and b and error(), 1 and nil and error()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![Value::Nil, Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn or_short_circuits() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 10
+        return a or error()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![10.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn short_circuit_results_compare_as_operands() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b, c = nil, false, 1
+        return (nil or 5) == 5, (false and error()) == false, (1 and 2) == 2,
+            (a or 5) == 5, (b and error()) == false, (c and 2) == 2
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(); 6],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn skipped_operand_has_no_side_effects() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local calls = 0
+        local function touch()
+            calls = calls + 1
+            return calls
+        end
+
+        local a, b = false, 1
+        local x = a and touch()
+        local y = b or touch()
+        local z = b and touch()
+        return x, y, z, calls
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![false.into(), 1.into(), 1.into(), 1.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
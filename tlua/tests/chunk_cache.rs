@@ -0,0 +1,59 @@
+use std::{
+    num::NonZeroUsize,
+    rc::Rc,
+};
+
+use pretty_assertions::assert_eq;
+use tlua::{
+    vm::runtime::Runtime,
+    ChunkCache,
+};
+
+#[test]
+fn reuses_compiled_chunks() -> anyhow::Result<()> {
+    let mut cache = ChunkCache::new(NonZeroUsize::new(4).unwrap());
+
+    let first = cache.get_or_compile("return 1 + 2")?;
+    let second = cache.get_or_compile("return 1 + 2")?;
+    let other = cache.get_or_compile("return 3 * 4")?;
+
+    assert!(Rc::ptr_eq(&first, &second));
+    assert!(!Rc::ptr_eq(&first, &other));
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+
+    let mut rt = Runtime::default();
+    assert_eq!(rt.execute(Rc::clone(&second))?, vec![3.into()]);
+    assert_eq!(rt.execute(Rc::clone(&other))?, vec![12.into()]);
+
+    Ok(())
+}
+
+#[test]
+fn evicts_least_recently_used() -> anyhow::Result<()> {
+    let mut cache = ChunkCache::new(NonZeroUsize::new(2).unwrap());
+
+    let a = cache.get_or_compile("return 'a'")?;
+    cache.get_or_compile("return 'b'")?;
+    // Using `a` makes `b` the least recently used entry.
+    cache.get_or_compile("return 'a'")?;
+    cache.get_or_compile("return 'c'")?;
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!((cache.hits(), cache.misses()), (1, 3));
+
+    assert!(Rc::ptr_eq(&a, &cache.get_or_compile("return 'a'")?));
+    assert_eq!(cache.misses(), 3);
+
+    cache.get_or_compile("return 'b'")?;
+    assert_eq!(cache.misses(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn compile_errors_are_not_cached() {
+    let mut cache = ChunkCache::new(NonZeroUsize::new(2).unwrap());
+
+    assert!(cache.get_or_compile("return (").is_err());
+    assert!(cache.is_empty());
+}
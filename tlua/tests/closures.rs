@@ -0,0 +1,205 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+};
+
+#[test]
+fn closures_share_upvalues() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function counter()
+            local count = 0
+            local function increment()
+                count = count + 1
+                return count
+            end
+            local function get()
+                return count
+            end
+            return increment, get
+        end
+
+        local inc_a, get_a = counter()
+        local inc_b, get_b = counter()
+        inc_a()
+        inc_a()
+        inc_b()
+        return inc_a(), get_a(), get_b()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![3.into(), 3.into(), 1.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn upvalue_mutations_are_visible_to_the_enclosing_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local x = 1
+        local function get()
+            return x
+        end
+        local function set(v)
+            x = v
+        end
+
+        x = 2
+        local seen = get()
+        set(3)
+        return seen, x
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![2.into(), 3.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn loop_variables_are_fresh_each_iteration() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local numeric = {}
+        for i = 1, 3 do
+            numeric[i] = function() return i end
+        end
+
+        local generic = {}
+        for i, v in ipairs({ 10, 20, 30 }) do
+            generic[i] = function() return v end
+        end
+
+        local repeated = {}
+        local n = 0
+        repeat
+            local j = n
+            repeated[#repeated + 1] = function() return j end
+            n = n + 1
+        until j >= 2
+
+        return numeric[1]() + numeric[3]() * 10,
+            generic[1]() + generic[3](),
+            repeated[1]() + repeated[3]() * 10
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![31.into(), 40.into(), 20.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn jumps_out_of_blocks_leave_their_scopes() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(a)
+            local b = a * 2
+            return b
+        end
+
+        local count = 0
+        for i = 1, 3 do
+            for j = 1, 3 do
+                if j == 2 then
+                    break
+                end
+                count = count + 1
+            end
+        end
+
+        do
+            do
+                local x = 1
+                goto out
+            end
+        end
+        ::out::
+
+        local n = 0
+        repeat
+            local x = n
+            n = n + 1
+        until x == 2
+
+        return count, n, f(4)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![3.into(), 3.into(), 8.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn local_functions_are_visible_in_their_own_body() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fact(n)
+            if n <= 1 then
+                return 1
+            else
+                return n * fact(n - 1)
+            end
+        end
+
+        -- Unlike `local function`, the name isn't in scope until after the
+        -- expression, so this refers to the global `g`.
+        local g = function()
+            return g
+        end
+
+        return fact(5), g()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![120.into(), Value::Nil]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile_with_diagnostics,
+    Diagnostic,
+    DiagnosticKind,
+};
+
+/// Pairs each diagnostic with the source of the statement it was reported for.
+fn describe<'src>(src: &'src str, diagnostics: &[Diagnostic]) -> Vec<(&'src str, DiagnosticKind)> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                &src[diagnostic.span.start()..diagnostic.span.end()],
+                diagnostic.kind.clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn unused_locals() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a, b = 1, 2
+        local c = 3
+        local _ignored = 4
+        c = a
+        do
+            local d = 5
+            local function e() return d end
+        end
+        local function f(x) return f(x) end
+        return b
+    "};
+
+    let (result, diagnostics) = compile_with_diagnostics(src);
+    result?;
+
+    assert_eq!(
+        describe(src, &diagnostics),
+        vec![
+            (
+                "local c = 3",
+                DiagnosticKind::UnusedLocal { name: "c".into() }
+            ),
+            (
+                "local function e() return d end",
+                DiagnosticKind::UnusedLocal { name: "e".into() }
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unreachable_after_exit() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(a)
+            while a do
+                break
+                a = false
+            end
+            do return end
+            print(a)
+            print(a)
+        end
+        goto skip
+        ::skip::
+        f(1)
+        do
+            goto done
+        end
+        f(2)
+        ::done::
+        return f
+    "};
+
+    let (result, diagnostics) = compile_with_diagnostics(src);
+    result?;
+
+    assert_eq!(
+        describe(src, &diagnostics),
+        vec![
+            ("a = false", DiagnosticKind::UnreachableCode),
+            ("print(a)", DiagnosticKind::UnreachableCode),
+            ("f(2)", DiagnosticKind::UnreachableCode),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+#[test]
+fn empty_chunk() -> anyhow::Result<()> {
+    let src = "";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result, vec![], "{:#?} produced an incorrect result", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn whitespace_chunk() -> anyhow::Result<()> {
+    let src = "   \n\t\r\n  ";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result, vec![], "{:#?} produced an incorrect result", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn comment_chunk() -> anyhow::Result<()> {
+    let src = "-- comment";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result, vec![], "{:#?} produced an incorrect result", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn whitespace_comment_chunk() -> anyhow::Result<()> {
+    let src = "   \n-- comment\n\t--[[ multiline\n comment ]]\n";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result, vec![], "{:#?} produced an incorrect result", chunk);
+
+    Ok(())
+}
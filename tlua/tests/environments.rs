@@ -0,0 +1,226 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Gc,
+        Runtime,
+        Table,
+        Value,
+    },
+};
+
+fn env(name: &str, scale: i64) -> anyhow::Result<Gc<Table>> {
+    let mut table = Table::default();
+    table.set("name".into(), name.into())?;
+    table.set("scale".into(), scale.into())?;
+
+    Ok(Gc::new(table))
+}
+
+#[test]
+fn execute_with_different_envs() -> anyhow::Result<()> {
+    let src = indoc! {"
+        calls = (calls or 0) + 1
+        return name, scale * 10, calls
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let first = env("first", 1)?;
+    let second = env("second", 2)?;
+
+    assert_eq!(
+        rt.execute_with_env(&chunk, &first)?,
+        vec!["first".into(), 10.into(), 1.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        rt.execute_with_env(&chunk, &second)?,
+        vec!["second".into(), 20.into(), 1.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        rt.execute_with_env(&chunk, &first)?,
+        vec!["first".into(), 10.into(), 2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    assert_eq!(first.borrow().get(&"calls".into()), 2.into());
+    assert_eq!(second.borrow().get(&"calls".into()), 1.into());
+    assert_eq!(rt.load_global("calls"), None);
+
+    Ok(())
+}
+
+#[test]
+fn env_replaces_builtins() -> anyhow::Result<()> {
+    let chunk = compile("return select")?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute_with_env(&chunk, &Gc::new(Table::default()))?;
+
+    assert_eq!(
+        result,
+        vec![Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn env_reads_fields_assigned_by_the_chunk() -> anyhow::Result<()> {
+    let chunk = compile("env.x = 5 return x")?;
+
+    let mut rt = Runtime::default();
+    let env = Gc::new(Table::default());
+    env.borrow_mut()
+        .set("env".into(), Value::Table(env.clone()))?;
+
+    let result = rt.execute_with_env(&chunk, &env)?;
+
+    assert_eq!(
+        result,
+        vec![5.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(env.borrow().get(&"x".into()), 5.into());
+
+    Ok(())
+}
+
+#[test]
+fn env_keeps_fields_written_by_functions() -> anyhow::Result<()> {
+    let src = indoc! {"
+        function set_y(value)
+            y = value
+        end
+        set_y(1)
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let env = Gc::new(Table::default());
+    rt.execute_with_env(&chunk, &env)?;
+
+    let set_y = env.borrow().get(&"set_y".into());
+    rt.call(&set_y, vec![2.into()])?;
+    env.borrow_mut().set("z".into(), 3.into())?;
+
+    assert_eq!(env.borrow().get(&"y".into()), 2.into());
+    assert_eq!(env.borrow().get(&"z".into()), 3.into());
+
+    Ok(())
+}
+
+#[test]
+fn env_metamethods() -> anyhow::Result<()> {
+    let src = indoc! {"
+        written = answer + 1
+        return answer
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let fallback = Gc::new(Table::default());
+    fallback.borrow_mut().set("answer".into(), 42.into())?;
+    let log = Gc::new(Table::default());
+
+    let mut meta = Table::default();
+    meta.set("__index".into(), Value::Table(fallback))?;
+    meta.set("__newindex".into(), Value::Table(log.clone()))?;
+
+    let env = Gc::new(Table::default());
+    env.borrow_mut().metatable = Some(Gc::new(meta));
+
+    let result = rt.execute_with_env(&chunk, &env)?;
+
+    assert_eq!(
+        result,
+        vec![42.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(env.borrow().get(&"written".into()), Value::Nil);
+    assert_eq!(log.borrow().get(&"written".into()), 43.into());
+
+    Ok(())
+}
+
+#[test]
+fn iterate_and_remove_globals() -> anyhow::Result<()> {
+    let mut rt = Runtime::default();
+    let builtins = rt.globals().count();
+
+    let chunk = compile("a = 1 b = 'two' c = nil")?;
+    rt.execute(&chunk)?;
+
+    let mut added = rt
+        .globals()
+        .filter(|(name, _)| matches!(name.as_slice(), b"a" | b"b" | b"c"))
+        .map(|(name, value)| (name.to_string(), value))
+        .collect::<Vec<_>>();
+    added.sort_by(|(l, _), (r, _)| l.cmp(r));
+
+    assert_eq!(
+        added,
+        vec![("a".to_string(), 1.into()), ("b".to_string(), "two".into())]
+    );
+
+    rt.remove_global("a");
+    rt.remove_global("missing");
+    assert_eq!(rt.load_global("a"), None);
+    assert_eq!(rt.globals().count(), builtins + 1);
+
+    // Functions which were loaded before the global was removed see it being
+    // assigned again.
+    let chunk = compile("function get_a() return a end")?;
+    rt.execute(&chunk)?;
+    rt.remove_global("a");
+    rt.register_global("a", 3);
+
+    let result = rt.call_global("get_a", vec![]);
+    assert_eq!(result, Ok(vec![3.into()]));
+
+    Ok(())
+}
+
+#[test]
+fn restore_globals_snapshot() -> anyhow::Result<()> {
+    let mut rt = Runtime::default();
+
+    let chunk = compile("x = 1 t = {} function get_x() return x end")?;
+    rt.execute(&chunk)?;
+
+    let snapshot = rt.snapshot();
+    assert_eq!(snapshot.get("x"), Some(&1.into()));
+
+    let chunk = compile(indoc! {"
+        x = 2
+        y = 3
+        t.field = true
+        error_here()
+    "})?;
+    assert!(rt.execute(&chunk).is_err());
+    assert_eq!(rt.load_global("y"), Some(&3.into()));
+
+    rt.restore(&snapshot);
+
+    assert_eq!(rt.load_global("x"), Some(&1.into()));
+    assert_eq!(rt.load_global("y"), Some(&Value::Nil));
+    assert_eq!(rt.call_global("get_x", vec![]), Ok(vec![1.into()]));
+
+    // Tables are shared with the snapshot, so their contents aren't rolled back.
+    let chunk = compile("return t.field")?;
+    assert_eq!(rt.execute(&chunk), Ok(vec![true.into()]));
+
+    Ok(())
+}
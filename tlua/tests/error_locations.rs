@@ -0,0 +1,97 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+    LuaError,
+};
+
+fn error_line(src: &str) -> anyhow::Result<Option<usize>> {
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    match rt.execute(&chunk) {
+        Err(LuaError::ExecutionError { location, .. }) => Ok(location.map(|loc| loc.line)),
+        result => panic!("{:#?} did not fail: {:?}", chunk, result),
+    }
+}
+
+#[test]
+fn error_in_main() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 10
+        local b = {}
+
+        local c = a + b
+        return c
+    "};
+
+    assert_eq!(error_line(src)?, Some(4));
+
+    Ok(())
+}
+
+#[test]
+fn error_in_nested_block() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        if t then
+            local x = 1
+            x = x .. t
+        end
+    "};
+
+    assert_eq!(error_line(src)?, Some(4));
+
+    Ok(())
+}
+
+#[test]
+fn error_in_called_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fail(v)
+            return #v
+        end
+
+        local a = 1
+        return fail(a)
+    "};
+
+    assert_eq!(error_line(src)?, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn error_in_builtin() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 1
+        local b = rawlen(a)
+    "};
+
+    assert_eq!(error_line(src)?, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn error_message_includes_line() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = {}
+
+        return -a
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let err = rt.execute(&chunk).unwrap_err();
+
+    assert!(
+        err.to_string().contains("at line 3"),
+        "{} does not include the line",
+        err
+    );
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        FormatOptions,
+        Runtime,
+        Value,
+    },
+};
+
+#[test]
+fn float_precision() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return 3.14159
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let opts = FormatOptions {
+        float_precision: Some(2),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        result
+            .iter()
+            .map(|v| v.display_with(&opts))
+            .collect::<Vec<_>>(),
+        vec!["3.14".to_string()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(result[0].to_string(), "3.14159");
+
+    Ok(())
+}
+
+#[test]
+fn hex_integers() {
+    let opts = FormatOptions {
+        hex_integers: true,
+        ..Default::default()
+    };
+
+    assert_eq!(Value::from(255).display_with(&opts), "0xff");
+    assert_eq!(Value::from(255).to_string(), "255");
+}
+
+#[test]
+fn limited_table_depth() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return { a = { b = { c = 1 } } }
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let opts = FormatOptions {
+        table_depth: 2,
+        ..Default::default()
+    };
+
+    let rendered = result[0].display_with(&opts);
+    assert!(
+        rendered.starts_with(r#"{["a"] = {["b"] = table: 0x"#),
+        "{} was rendered incorrectly",
+        rendered
+    );
+    assert!(result[0].to_string().starts_with("table: 0x"));
+
+    Ok(())
+}
+
+#[test]
+fn deeply_nested_table() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        local i = 0
+        while i < 10000 do
+            t = { t }
+            i = i + 1
+        end
+        return t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let opts = FormatOptions {
+        table_depth: usize::MAX,
+        max_depth: 2,
+        ..Default::default()
+    };
+
+    assert_eq!(result[0].display_with(&opts), "{[1] = {[1] = <...>}}");
+
+    let opts = FormatOptions {
+        table_depth: usize::MAX,
+        ..Default::default()
+    };
+
+    let rendered = result[0].display_with(&opts);
+    assert!(
+        rendered.contains("<...>"),
+        "{} was rendered incorrectly",
+        rendered
+    );
+
+    Ok(())
+}
+
+#[test]
+fn debug_string_expands_tables() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = { 1, { 'nested' } }
+        t.loop = t
+        return t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result[0].to_debug_string(),
+        r#"{["loop"] = <cycle>, [1] = 1, [2] = {[1] = "nested"}}"#
+    );
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use pretty_assertions::assert_eq;
+use tlua::{
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    Chunk,
+    FunctionBuilder,
+};
+use tlua_bytecode::{
+    ByteCodeError,
+    Constant,
+    ImmediateRegister,
+};
+
+fn run(builder: FunctionBuilder) -> anyhow::Result<Vec<Value>> {
+    let chunk = Chunk {
+        main: builder.finish()?,
+        ..Default::default()
+    };
+    assert_eq!(chunk.verify(), Ok(()));
+
+    let mut rt = Runtime::default();
+    Ok(rt.execute(&chunk)?)
+}
+
+#[test]
+fn add_two_numbers() -> anyhow::Result<()> {
+    let (a, b, sum) = (
+        ImmediateRegister::from(0),
+        ImmediateRegister::from(1),
+        ImmediateRegister::from(2),
+    );
+
+    let mut builder = FunctionBuilder::new();
+    builder
+        .load_const(a, Constant::Integer(40))
+        .load_const(b, Constant::Integer(2))
+        .add(sum, a, b)
+        .set_ret(sum)
+        .ret();
+
+    assert_eq!(run(builder)?, vec![42.into()]);
+
+    Ok(())
+}
+
+#[test]
+fn jumps_to_labels() -> anyhow::Result<()> {
+    let (cond, out) = (ImmediateRegister::from(0), ImmediateRegister::from(1));
+
+    // Returns 1 if the condition holds, and 2 otherwise.
+    let build = |condition| {
+        let mut builder = FunctionBuilder::new();
+        let (otherwise, done) = (builder.new_label(), builder.new_label());
+        builder
+            .load_const(cond, Constant::Bool(condition))
+            .jump_if_not(cond, otherwise)
+            .load_const(out, Constant::Integer(1))
+            .jump(done)
+            .bind(otherwise)
+            .load_const(out, Constant::Integer(2))
+            .bind(done)
+            .set_ret(out);
+        builder
+    };
+
+    assert_eq!(run(build(true))?, vec![1.into()]);
+    assert_eq!(run(build(false))?, vec![2.into()]);
+
+    Ok(())
+}
+
+#[test]
+fn unbound_labels_fail() {
+    let mut builder = FunctionBuilder::new();
+    let nowhere = builder.new_label();
+    builder.jump(nowhere);
+
+    assert!(matches!(
+        builder.finish(),
+        Err(ByteCodeError::InvalidJumpTarget)
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Destination overlaps the rhs")]
+fn destination_overlapping_rhs_panics() {
+    let (a, b) = (ImmediateRegister::from(0), ImmediateRegister::from(1));
+
+    FunctionBuilder::new().sub(b, a, b);
+}
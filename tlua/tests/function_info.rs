@@ -0,0 +1,130 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile_named,
+    vm::runtime::Runtime,
+    FuncId,
+    LuaError,
+};
+
+#[test]
+fn describes_functions() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local scale = 2
+
+        local function scaled(a, b, ...)
+            local sum = a + b
+            return sum * scale
+        end
+
+        return scaled(1, 2)
+    "};
+
+    let chunk = compile_named(src, "scale.lua")?;
+
+    let main = chunk
+        .function_info(FuncId::MAIN)
+        .expect("Main always exists");
+    assert_eq!(main.to_string(), "main chunk scale.lua");
+    assert_eq!(main.named_args, 0);
+    assert!(!main.is_vararg);
+    assert_eq!(main.defined_at, None);
+
+    let scaled = chunk
+        .function_info(FuncId::from(0))
+        .expect("Chunk defines a function");
+    assert_eq!(scaled.to_string(), "function defined at scale.lua:3");
+    assert_eq!(scaled.named_args, 2);
+    assert!(scaled.is_vararg);
+    assert_eq!(scaled.local_registers, 3);
+    assert_eq!(
+        scaled
+            .locals
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>(),
+        vec!["a", "b", "sum"]
+    );
+
+    assert!(chunk.function_info(FuncId::from(1)).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn errors_report_chunk_name() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+
+        return -t
+    "};
+
+    let chunk = compile_named(src, "negate.lua")?;
+
+    let mut rt = Runtime::default();
+    let err = rt.execute(&chunk).unwrap_err();
+
+    assert!(
+        matches!(&err, LuaError::ExecutionError { chunk_name: Some(name), .. } if name == "negate.lua")
+    );
+    assert!(
+        err.to_string().contains("at negate.lua:3"),
+        "{} does not include the chunk name",
+        err
+    );
+
+    Ok(())
+}
+
+#[test]
+fn line_table_maps_statements() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 1
+        local b = a + 2
+
+        local function double(x)
+            return x * 2
+        end
+
+        return double(b)
+    "};
+
+    let chunk = compile_named(src, "lines.lua")?;
+    let line_table = chunk.line_table();
+
+    let lines = line_table
+        .iter()
+        .map(|entry| (entry.func, entry.location.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            (FuncId::MAIN, 1),
+            (FuncId::MAIN, 2),
+            (FuncId::MAIN, 4),
+            (FuncId::MAIN, 8),
+            (FuncId::from(0), 5),
+        ]
+    );
+
+    let main_len = chunk.main.instructions.len();
+    let main_ranges = line_table
+        .iter()
+        .filter(|entry| entry.func == FuncId::MAIN)
+        .map(|entry| entry.instructions.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(main_ranges.first().map(|range| range.start), Some(0));
+    assert_eq!(main_ranges.last().map(|range| range.end), Some(main_len));
+    assert!(main_ranges
+        .windows(2)
+        .all(|pair| pair[0].end == pair[1].start));
+
+    for entry in &line_table {
+        let span = entry.location.span;
+        let statement = src[span.start()..span.end()].lines().next().unwrap();
+        let line = src.lines().nth(entry.location.line - 1).unwrap();
+        assert_eq!(line.trim_start(), statement);
+    }
+
+    Ok(())
+}
@@ -2,7 +2,12 @@ use indoc::indoc;
 use pretty_assertions::assert_eq;
 use tlua::{
     compile,
-    vm::runtime::Runtime,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
 };
 
 #[test]
@@ -76,3 +81,222 @@ fn generic_for_break() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn generic_for_body_temporaries() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { 1, 2, 3 }
+        local sum = 0
+
+        local function next(state, control)
+            control = (control or 0) + 1
+            if state[control] then
+                return control, state[control]
+            end
+        end
+
+        for k, v in next, t, nil do
+            sum = sum + (k * 100 + v * 10) + (v + k) * (k - v)
+        end
+
+        return sum
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![660.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn ipairs_stops_at_first_nil() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { "a", "b", nil, "d" }
+        local result = ""
+        local count = 0
+
+        for i, v in ipairs(t) do
+            result = result .. i .. v
+            count = count + 1
+        end
+
+        return result, count
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["1a2b".into(), 2.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn ipairs_follows_index() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = setmetatable({ 1, 2 }, {
+            __index = function(t, k)
+                if k <= 4 then
+                    return k * 10
+                end
+            end
+        })
+        local sum = 0
+
+        for i, v in ipairs(t) do
+            sum = sum + v
+        end
+
+        return sum
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![73.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pairs_visits_in_insertion_order() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { "a", "b", z = 1, y = 2 }
+        t.x = 3
+        t[10] = 4
+        t.w = 5
+        t.y = 6
+        t[3] = "c"
+
+        local result = ""
+        for k, v in pairs(t) do
+            result = result .. k .. "=" .. v .. ";"
+        end
+
+        return result
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["1=a;2=b;3=c;z=1;y=6;x=3;10=4;w=5;".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn next_first_and_last() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { 10, a = 20 }
+        local k1, v1 = next(t)
+        local k2, v2 = next(t, k1)
+        return k1, v1, k2, v2, next(t, k2), next({})
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            1.into(),
+            10.into(),
+            "a".into(),
+            20.into(),
+            Value::Nil,
+            Value::Nil,
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pairs_allows_clearing_fields() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { 1, 2, 3, 4, a = 5, b = 6, c = 7 }
+
+        local sum = 0
+        for k, v in pairs(t) do
+            sum = sum + v
+            t[k] = nil
+        end
+
+        return sum, next(t)
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![28.into(), Value::Nil]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn next_invalid_key() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return next({ 1, a = 2 }, "b")
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidNextKey,
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result: {:?}",
+        chunk,
+        result
+    );
+
+    Ok(())
+}
@@ -151,7 +151,10 @@ fn goto_forwards_label_in_sibling_error() -> anyhow::Result<()> {
     let result = rt.execute(&chunk);
     assert!(matches!(
         result,
-        Err(LuaError::ExecutionError(OpError::MissingLabel))
+        Err(LuaError::ExecutionError {
+            error: OpError::MissingLabel,
+            ..
+        })
     ));
 
     Ok(())
@@ -181,7 +184,10 @@ fn goto_forwards_label_in_deeper_sibling_error() -> anyhow::Result<()> {
     let result = rt.execute(&chunk);
     assert!(matches!(
         result,
-        Err(LuaError::ExecutionError(OpError::MissingLabel))
+        Err(LuaError::ExecutionError {
+            error: OpError::MissingLabel,
+            ..
+        })
     ));
 
     Ok(())
@@ -216,3 +222,101 @@ fn goto_local_in_scope_label_outside_still_valid() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn goto_continue() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local sum = 0
+
+        for i = 1, 10 do
+            if i % 2 == 0 then
+                goto continue
+            end
+
+            local odd = i
+            sum = sum + odd
+
+            ::continue::
+        end
+
+        return sum
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![25.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn goto_continue_from_nested_blocks() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local sum = 0
+
+        for i = 1, 3 do
+            for j = 1, 3 do
+                do
+                    while true do
+                        if j == 2 then
+                            goto continue
+                        end
+                        sum = sum + i * j
+                        break
+                    end
+                end
+            end
+
+            ::continue::
+        end
+
+        return sum
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![6.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn goto_missing_label_error() -> anyhow::Result<()> {
+    let src = indoc! {"
+        for i = 1, 3 do
+            if i == 2 then
+                goto continue
+            end
+        end
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+    assert!(matches!(
+        result,
+        Err(LuaError::ExecutionError {
+            error: OpError::MissingLabel,
+            ..
+        })
+    ));
+
+    Ok(())
+}
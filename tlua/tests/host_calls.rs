@@ -0,0 +1,255 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+    FuncId,
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn call_extracted_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        scale = 10
+
+        function scaled_sum(a, b)
+            return (a + b) * scale
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let scaled_sum = rt
+        .load_function(&chunk, FuncId::from(0))
+        .expect("Chunk defines a function");
+
+    // The chunk hasn't been executed, so its globals haven't been assigned.
+    rt.register_global("scale", 2);
+    let result = rt.call(&scaled_sum, vec![1.into(), 2.into()])?;
+
+    assert_eq!(
+        result,
+        vec![6.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn extracted_function_shares_globals() -> anyhow::Result<()> {
+    let src = indoc! {"
+        function count()
+            calls = (calls or 0) + 1
+            return calls
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let count = rt
+        .load_function(&chunk, FuncId::from(0))
+        .expect("Chunk defines a function");
+
+    rt.call(&count, vec![])?;
+    let result = rt.call(&count, vec![])?;
+
+    assert_eq!(
+        result,
+        vec![2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(rt.load_global("calls"), Some(&2.into()));
+
+    Ok(())
+}
+
+#[test]
+fn load_missing_function() -> anyhow::Result<()> {
+    let chunk = compile("return 1")?;
+
+    let mut rt = Runtime::default();
+    assert_eq!(rt.load_function(&chunk, FuncId::from(0)), None);
+
+    Ok(())
+}
+
+#[test]
+fn load_nested_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        function outer()
+            local x = 41
+            return function()
+                x = x + 1
+                return x
+            end
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    // The closure is FuncId 0, and its upvalue only exists while outer runs.
+    assert_eq!(rt.load_function(&chunk, FuncId::from(0)), None);
+
+    let outer = rt
+        .load_function(&chunk, FuncId::from(1))
+        .expect("outer is declared at the top level");
+    let inner = rt.call(&outer, vec![])?.remove(0);
+    let result = rt.call(&inner, vec![])?;
+
+    assert_eq!(
+        result,
+        vec![42.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn call_global_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        function greet(name)
+            return 'hello ' .. name, #name
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    let result = rt.call_global("greet", vec!["lua".into()])?;
+
+    assert_eq!(
+        result,
+        vec!["hello lua".into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn call_global_non_function() -> anyhow::Result<()> {
+    let chunk = compile("count = 1")?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    for name in ["count", "missing"] {
+        let result = rt.call_global(name, vec![]);
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::InvalidType { op: "call" },
+                    ..
+                })
+            ),
+            "calling {} produced {:?}",
+            name,
+            result
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn call_global_propagates_errors() -> anyhow::Result<()> {
+    let src = indoc! {"
+        function add(a, b)
+            return a + b
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    let result = rt.call_global("add", vec![1.into(), true.into()]);
+    assert!(
+        matches!(result, Err(LuaError::ExecutionError { .. })),
+        "{:?} is not an execution error",
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn execute_nested_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local count = 0
+
+        function outer(x)
+            local scale = 10
+            do
+                local offset = 5
+                local function inner(a, b)
+                    count = (count or 0) + 1
+                    return a * (scale or 1) + b + (offset or 0), count, limit
+                end
+                return inner(x, 1)
+            end
+        end
+    "};
+
+    let chunk = compile(src)?;
+    let inner = (0..chunk.functions.len())
+        .map(FuncId::from)
+        .find(|&id| {
+            chunk
+                .function_info(id)
+                .is_some_and(|info| info.named_args == 2)
+        })
+        .expect("Chunk defines inner");
+    assert!(chunk.function(inner).unwrap().scope_depth > chunk.main_function().scope_depth);
+
+    let mut rt = Runtime::default();
+    rt.register_global("limit", 100);
+
+    // The enclosing locals were never initialized, so each upvalue is nil until the
+    // function assigns it.
+    let result = rt.execute_function(&chunk, inner, vec![3.into(), 4.into()]);
+    assert_eq!(
+        result,
+        Some(Ok(vec![7.into(), 1.into(), 100.into()])),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    // Every execution starts with fresh upvalues.
+    let result = rt.execute_function(&chunk, inner, vec![1.into(), 1.into()]);
+    assert_eq!(
+        result,
+        Some(Ok(vec![2.into(), 1.into(), 100.into()])),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let result = rt.execute_function(&chunk, FuncId::MAIN, vec![]);
+    assert_eq!(result, Some(Ok(vec![])));
+    assert_eq!(
+        rt.call_global("outer", vec![2.into()])?,
+        vec![26.into(), 1.into(), 100.into()]
+    );
+
+    assert_eq!(
+        rt.execute_function(&chunk, FuncId::from(chunk.functions.len()), vec![]),
+        None
+    );
+
+    Ok(())
+}
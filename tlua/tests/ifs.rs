@@ -7,6 +7,10 @@ use tlua::{
         Value,
     },
 };
+use tlua_bytecode::opcodes::{
+    Jump,
+    Op,
+};
 
 const SIMPLE_IF: &str = indoc! {"
     if a then
@@ -239,3 +243,63 @@ fn if_elif_else() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn if_chain_shares_exit() -> anyhow::Result<()> {
+    for (src, expected_exits) in [
+        (
+            indoc! {"
+                if a then x = 1 elseif b then x = 2 elseif c then x = 3 else x = 4 end
+                return x
+            "},
+            3,
+        ),
+        // The last branch falls through to the end of the chain.
+        (
+            indoc! {"
+                if a then x = 1 elseif b then x = 2 elseif c then x = 3 end
+                return x
+            "},
+            2,
+        ),
+    ] {
+        let chunk = compile(src)?;
+        let instructions = chunk.main.instructions.as_slice();
+
+        let conditional_jumps = instructions
+            .iter()
+            .filter(|op| matches!(op, Op::JumpNot(_)))
+            .count();
+        let exit_targets = instructions
+            .iter()
+            .filter_map(|op| match op {
+                Op::Jump(Jump { target }) => Some(*target),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(conditional_jumps, 3, "{:#?}", chunk);
+        assert_eq!(exit_targets.len(), expected_exits, "{:#?}", chunk);
+        assert!(
+            exit_targets.iter().all(|&target| target == exit_targets[0]),
+            "{:#?} jumps to more than one exit",
+            chunk
+        );
+
+        for (a, b, c, expected) in [(true, false, false, 1), (false, false, true, 3)] {
+            let mut rt = Runtime::default();
+            rt.register_global("a", a);
+            rt.register_global("b", b);
+            rt.register_global("c", c);
+
+            assert_eq!(
+                rt.execute(&chunk),
+                Ok(vec![expected.into()]),
+                "{:#?} produced an incorrect result",
+                chunk
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "io")]
+
+use std::{
+    cell::RefCell,
+    io::Write,
+    rc::Rc,
+};
+
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[derive(Debug, Default, Clone)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_chains() -> anyhow::Result<()> {
+    let src = indoc! {"
+        io.write('a', 1, ' ', 2.5, ' ', 3.0):write('\\n', 'b')
+        return rawequal(io.write(), io), io.read()
+    "};
+
+    let chunk = compile(src)?;
+
+    let output = SharedBuffer::default();
+    let mut rt = Runtime::default();
+    rt.set_stdout(output.clone());
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![true.into(), Value::Nil]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(output.0.borrow().as_slice(), b"a1 2.5 3.0\nb");
+
+    Ok(())
+}
+
+#[test]
+fn write_rejects_non_strings() -> anyhow::Result<()> {
+    let src = indoc! {"
+        io.write('a', {})
+    "};
+
+    let chunk = compile(src)?;
+
+    let output = SharedBuffer::default();
+    let mut rt = Runtime::default();
+    rt.set_stdout(output.clone());
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "write" },
+                ..
+            })
+        ),
+        "{:#?}",
+        result
+    );
+    assert_eq!(output.0.borrow().as_slice(), b"a");
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+#[test]
+fn load_string() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local f = load('return 1 + 2')
+        return f()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn loadstring_string() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local f = loadstring('return 1, 2')
+        local a, b = f()
+        return a, b
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_empty_string() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local f = load('')
+        return f()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result, vec![], "{:#?} produced an incorrect result", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn load_syntax_error() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local f, err = load('return +')
+        return f == nil, #err > 0
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_rejects_binary_chunk() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local f, err = load('\\27Lua')
+        return f, err
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            tlua::vm::runtime::Value::Nil,
+            "attempt to load a binary chunk".into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_reader_function() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local parts = { 'return ', '40 ', '+ 2' }
+        local i = 0
+        local f = load(function()
+            i = i + 1
+            return parts[i]
+        end)
+        return f()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![42.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_shares_globals() -> anyhow::Result<()> {
+    let src = indoc! {"
+        x = 1
+        local f = load('x = x + 1 y = 10')
+        f()
+        return x, y
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![2.into(), 10.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(rt.load_global("y"), Some(&10.into()));
+
+    Ok(())
+}
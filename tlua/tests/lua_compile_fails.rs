@@ -1,5 +1,9 @@
 use indoc::indoc;
-use tlua::compile;
+use tlua::{
+    compile,
+    compile_with_options,
+    CompilerOptions,
+};
 use tlua_compiler::CompileError;
 
 #[test]
@@ -14,6 +18,48 @@ fn constant_cond_fails_multiple_labels() {
     assert!(matches!(result, Err(CompileError::DuplicateLabel { .. })));
 }
 
+#[test]
+fn duplicate_label_in_block() {
+    let src = indoc! {"
+        do
+            ::top::
+            local a = 1
+            ::top::
+        end
+    "};
+    let result = compile(src);
+
+    match result {
+        Err(CompileError::DuplicateLabel {
+            label,
+            span: Some(span),
+        }) => {
+            assert_eq!(label, "top");
+            assert_eq!(&src[span.start()..span.end()], "::top::");
+            assert_eq!(span.start(), src.rfind("::top::").unwrap());
+        }
+        result => panic!("{result:?} is not a duplicate label error"),
+    }
+}
+
+#[test]
+fn same_label_in_separate_blocks() {
+    let src = indoc! {"
+        do
+            ::a::
+        end
+        do
+            ::a::
+            do
+                ::b::
+            end
+        end
+        ::b::
+    "};
+
+    assert!(compile(src).is_ok());
+}
+
 #[test]
 fn goto_across_local() {
     let src = indoc! {"
@@ -31,3 +77,222 @@ fn goto_across_local() {
         Err(CompileError::JumpIntoLocalScope { .. })
     ));
 }
+
+#[test]
+fn goto_across_local_label_not_at_block_end() {
+    let src = indoc! {"
+        do
+            goto a
+            local b = 10
+            ::a::
+            b = 20
+        end
+    "};
+    let result = compile(src);
+
+    assert!(matches!(
+        result,
+        Err(CompileError::JumpIntoLocalScope { .. })
+    ));
+}
+
+#[test]
+fn varargs_outside_vararg_function() {
+    let src = indoc! {"
+        local function f(a)
+            return a, ...
+        end
+    "};
+    let result = compile(src);
+
+    match result {
+        Err(CompileError::NoVarArgsAvailable { span }) => {
+            assert_eq!(&src[span.start()..span.end()], "...");
+            assert_eq!(span.start(), src.find("...").unwrap());
+        }
+        result => panic!("{result:?} is not a vararg error"),
+    }
+}
+
+#[test]
+fn nested_function_cannot_capture_varargs() {
+    let src = indoc! {"
+        function outer(...)
+            local first = ...
+            return function() return ... end
+        end
+    "};
+    let result = compile(src);
+
+    match result {
+        Err(CompileError::NoVarArgsAvailable { span }) => {
+            assert_eq!(&src[span.start()..span.end()], "...");
+            assert_eq!(span.start(), src.rfind("...").unwrap());
+        }
+        result => panic!("{result:?} is not a vararg error"),
+    }
+
+    let outer_only = indoc! {"
+        function outer(...)
+            local first = ...
+            return function() return first end
+        end
+    "};
+    assert!(compile(outer_only).is_ok());
+}
+
+#[test]
+fn configured_global_limit() {
+    let options = CompilerOptions {
+        max_globals: 2,
+        ..CompilerOptions::default()
+    };
+
+    assert!(compile_with_options("a = b", options).is_ok());
+
+    let result = compile_with_options("a = b + c", options);
+    assert!(
+        matches!(result, Err(CompileError::TooManyGlobals { max: 2, .. })),
+        "{result:?} is not a global limit error"
+    );
+}
+
+#[test]
+fn configured_local_limit() {
+    let options = CompilerOptions {
+        max_locals: 2,
+        ..CompilerOptions::default()
+    };
+
+    let src = indoc! {"
+        local a, b
+        do
+            local c, d
+        end
+    "};
+    assert!(compile_with_options(src, options).is_ok());
+
+    let result = compile_with_options("local a, b, c", options);
+    assert!(
+        matches!(result, Err(CompileError::TooManyLocals { max: 2, .. })),
+        "{result:?} is not a local limit error"
+    );
+}
+
+#[test]
+fn configured_scope_depth_limit() {
+    let options = CompilerOptions {
+        max_scope_depth: 2,
+        ..CompilerOptions::default()
+    };
+
+    let src = indoc! {"
+        local a
+        do
+            local b
+        end
+    "};
+    assert!(compile_with_options(src, options).is_ok());
+
+    let src = indoc! {"
+        local a
+        do
+            do
+                local c
+            end
+        end
+    "};
+    let result = compile_with_options(src, options);
+    assert!(
+        matches!(
+            result,
+            Err(CompileError::ScopeNestingTooDeep { max: 2, .. })
+        ),
+        "{result:?} is not a scope depth error"
+    );
+}
+
+#[test]
+fn configured_table_entry_limit() {
+    let options = CompilerOptions {
+        max_table_entries: 2,
+        ..CompilerOptions::default()
+    };
+
+    assert!(compile_with_options("return { 1, 2, x = 3 }", options).is_ok());
+
+    let result = compile_with_options("return { 1, 2, 3 }", options);
+    assert!(
+        matches!(
+            result,
+            Err(CompileError::TooManyTableEntries { max: 2, .. })
+        ),
+        "{result:?} is not a table size error"
+    );
+}
+
+#[test]
+fn limits_are_capped_by_the_bytecode() {
+    let options = CompilerOptions {
+        max_globals: usize::MAX,
+        ..CompilerOptions::default()
+    };
+
+    let src = (0..=65536)
+        .map(|i| format!("g{i} = 1"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let result = compile_with_options(&src, options);
+    assert!(
+        matches!(result, Err(CompileError::TooManyGlobals { max: 65536, .. })),
+        "{result:?} is not a global limit error"
+    );
+}
+
+#[test]
+fn errors_point_at_their_statement() {
+    let src = indoc! {"
+        ::a::
+        do
+            local b = 1
+            ::a::
+            print(b)
+        end
+    "};
+    let result = compile(src);
+
+    match result {
+        Err(err @ CompileError::DuplicateLabel { .. }) => {
+            let span = err.span().expect("Duplicate labels have a location");
+            assert_eq!(&src[span.start()..span.end()], "::a::");
+            assert_eq!(span.start(), src.rfind("::a::").unwrap());
+        }
+        result => panic!("{result:?} is not a duplicate label error"),
+    }
+
+    let options = CompilerOptions {
+        max_table_entries: 2,
+        ..CompilerOptions::default()
+    };
+    let src = indoc! {"
+        local t = { 1, 2 }
+        local function f()
+            return { 1, 2, 3 }
+        end
+    "};
+    let result = compile_with_options(src, options);
+
+    match result {
+        Err(err @ CompileError::TooManyTableEntries { .. }) => {
+            let span = err.span().expect("Table size errors have a location");
+            assert_eq!(&src[span.start()..span.end()], "return { 1, 2, 3 }");
+        }
+        result => panic!("{result:?} is not a table size error"),
+    }
+
+    let result = compile("return 1 +");
+    match result {
+        Err(err @ CompileError::ParseError(_)) => assert!(err.span().is_some()),
+        result => panic!("{result:?} is not a parse error"),
+    }
+}
@@ -0,0 +1,258 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn random_seed_reproduces_sequence() -> anyhow::Result<()> {
+    let src = indoc! {"
+        math.randomseed(42)
+        local a, b, c, d = math.random(), math.random(100), math.random(-5, 5), math.random(0)
+
+        math.randomseed(42)
+        local w, x, y, z = math.random(), math.random(100), math.random(-5, 5), math.random(0)
+
+        return a == w and b == x and c == y and d == z, a, b, c, d
+    "};
+
+    let chunk = compile(src)?;
+
+    let first = Runtime::default().execute(&chunk)?;
+    let second = Runtime::default().execute(&chunk)?;
+
+    assert_eq!(
+        first[0],
+        true.into(),
+        "{:#?} did not repeat its sequence",
+        chunk
+    );
+    assert_eq!(first, second, "{:#?} is not deterministic", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn random_ranges() -> anyhow::Result<()> {
+    let src = indoc! {"
+        math.randomseed(7)
+        local ok = true
+        local i = 0
+        while i < 1000 do
+            local f = math.random()
+            local m = math.random(6)
+            local r = math.random(-3, 3)
+            local one = math.random(9, 9)
+            ok = ok and f >= 0 and f < 1
+                and m >= 1 and m <= 6 and m // 1 == m
+                and r >= -3 and r <= 3
+                and one == 9
+            i = i + 1
+        end
+        return ok
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn random_empty_interval() -> anyhow::Result<()> {
+    for src in ["return math.random(2, 1)", "return math.random(-1)"] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::BadArgument {
+                        arg: 1,
+                        func: "random",
+                        msg: "interval is empty"
+                    },
+                    ..
+                })
+            ),
+            "{:#?} should have failed",
+            result
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "vm execution error at line 1: bad argument #1 to 'random' (interval is empty)"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn arithmetic_preserves_number_subtypes() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function types(a, b, c)
+            return math.type(a + b), math.type(a * b), math.type(a // b), math.type(a % b),
+                math.type(a / b), math.type(a ^ b), math.type(a + c), math.type(-a)
+        end
+
+        local folded = math.type(2 + 2) == 'integer' and math.type(2 / 1) == 'float'
+        return folded, types(7, 2, 1.5)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            true.into(),
+            "integer".into(),
+            "integer".into(),
+            "integer".into(),
+            "integer".into(),
+            "float".into(),
+            "float".into(),
+            "float".into(),
+            "integer".into(),
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn math_type_of_non_numbers() -> anyhow::Result<()> {
+    let chunk = compile("return math.type(1.0), math.type('1'), math.type(nil)")?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["float".into(), Value::Nil, Value::Nil]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let chunk = compile("return math.type()")?;
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "type" },
+                ..
+            })
+        ),
+        "{:#?} should have failed",
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fmod_truncates() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return math.fmod(-5, 3), -5 % 3, math.fmod(5, -3), 5 % -3,
+            math.fmod(-5.5, 2), -5.5 % 2, math.fmod(-9223372036854775808, -1), -7 // 2
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            (-2).into(),
+            1.into(),
+            2.into(),
+            (-1).into(),
+            (-1.5).into(),
+            0.5.into(),
+            0.into(),
+            (-4).into(),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let chunk = compile("return math.fmod(1, 0)")?;
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::BadArgument {
+                    arg: 2,
+                    func: "fmod",
+                    msg: "zero"
+                },
+                ..
+            })
+        ),
+        "{:#?} should have failed",
+        result
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "vm execution error at line 1: bad argument #2 to 'fmod' (zero)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tointeger_conversions() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return math.tointeger(3.0), math.tointeger(3), math.tointeger(3.5),
+            math.tointeger(2^63), math.tointeger('8'), math.type(math.tointeger(-0.0))
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            3.into(),
+            3.into(),
+            Value::Nil,
+            Value::Nil,
+            Value::Nil,
+            "integer".into(),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
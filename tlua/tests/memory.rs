@@ -0,0 +1,161 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+#[test]
+fn count_tracks_large_table() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local before = collectgarbage('count')
+
+        local function fill()
+            big = {}
+            for i = 1, 10000 do
+                big[i] = i
+            end
+        end
+        fill()
+        local during = collectgarbage('count')
+
+        big = nil
+        collectgarbage()
+        local after = collectgarbage('count')
+
+        return during > before, after < during
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn memory_usage_tracks_globals() -> anyhow::Result<()> {
+    let mut rt = Runtime::default();
+
+    let fill = compile(indoc! {"
+        big = {}
+        for i = 1, 10000 do
+            big['key' .. i] = {}
+        end
+    "})?;
+    let clear = compile("big = nil collectgarbage()")?;
+
+    let before = rt.memory_usage();
+    rt.execute(&fill)?;
+    let during = rt.memory_usage();
+    rt.execute(&clear)?;
+    let after = rt.memory_usage();
+
+    assert!(during > before, "{during} <= {before}");
+    assert!(after < during, "{after} >= {during}");
+
+    Ok(())
+}
+
+#[test]
+fn collectgarbage_options() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fill()
+            big = {}
+            for i = 1, 10000 do
+                big[i] = {}
+            end
+        end
+        fill()
+        local during = collectgarbage('count')
+
+        big = nil
+        local stepped = collectgarbage('step', 100)
+        local after = collectgarbage('count')
+
+        return collectgarbage('collect'), stepped, after < during, collectgarbage('incremental')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![0.into(), true.into(), true.into(), 0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn closure_cycles_are_collected() -> anyhow::Result<()> {
+    let src = indoc! {"
+        do
+            local t = {}
+            t.get = function() return t end
+        end
+
+        do
+            local shared = {}
+            local function first() return shared end
+            local function second() return first, shared end
+            shared.first, shared.second = first, second
+        end
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let before = rt.memory_usage();
+    rt.execute(&chunk)?;
+    let after = rt.memory_usage();
+
+    assert_eq!(after, before, "{:#?} left a cycle behind", chunk);
+
+    Ok(())
+}
+
+#[test]
+fn global_functions_are_kept() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function define()
+            function get()
+                return registry
+            end
+            registry = { get = get }
+        end
+
+        -- Nothing but the globals refers to either value once define returns.
+        define()
+        collectgarbage()
+
+        return rawequal(registry.get, get), rawequal(get(), registry)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -0,0 +1,433 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        value::Number,
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn set_and_get_metatable() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        local mt = {}
+        local returned = setmetatable(t, mt)
+        return rawequal(returned, t), rawequal(getmetatable(t), mt)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn clear_metatable() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, {})
+        setmetatable(t, nil)
+        return getmetatable(t), getmetatable('str'), getmetatable(10)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![Value::Nil, Value::Nil, Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn protected_metatable() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, { __metatable = 'locked' })
+        return getmetatable(t)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec!["locked".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn protected_metatable_cannot_change() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, { __metatable = false })
+        setmetatable(t, {})
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::ProtectedMetatable,
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn setmetatable_rejects_non_table() -> anyhow::Result<()> {
+    let src = indoc! {"
+        setmetatable({}, 10)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "setmetatable" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn len_metamethod_integral_float() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, { __len = function() return 3.0 end })
+        return #t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    // Integers and floats with the same value compare equal, so check the subtype
+    // as well.
+    assert!(
+        matches!(result.as_slice(), [Value::Number(Number::Integer(3))]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn len_metamethod_fractional_float() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, { __len = function() return 3.5 end })
+        return #t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::LengthNotInteger,
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn len_metamethod_string() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, { __len = function() return 'three' end })
+        return #t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::LengthNotInteger,
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn index_metamethod() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local base = { a = 1 }
+        local derived = setmetatable({ b = 2 }, { __index = base })
+        local computed = setmetatable({}, {
+            __index = function(t, k)
+                return k .. '!'
+            end
+        })
+        return derived.a, derived.b, derived.c, computed.x, rawget(computed, 'x')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into(), Value::Nil, "x!".into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn index_metamethod_cycle() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        setmetatable(t, { __index = t })
+        return t.x
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::MetaChainTooLong { name: "__index" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn weak_values_are_collected() -> anyhow::Result<()> {
+    let src = indoc! {"
+        cache = setmetatable({}, { __mode = 'v' })
+        kept = {}
+
+        cache[1] = {}
+        cache[2] = kept
+        cache.name = 'strings are not collected'
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    let cache = match rt.load_global("cache") {
+        Some(Value::Table(cache)) => cache,
+        other => panic!("{:?} is not a table", other),
+    };
+    let cache = cache.borrow();
+
+    assert_eq!(cache.get(&1.into()), Value::Nil);
+    assert_eq!(
+        cache.get(&2.into()),
+        rt.load_global("kept").cloned().unwrap()
+    );
+    assert_eq!(
+        cache.get(&"name".into()),
+        "strings are not collected".into()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn weak_keys_are_collected() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local kept = {}
+        cache = setmetatable({}, { __mode = 'k' })
+        cache[{}] = 'dropped'
+        cache[kept] = 'kept'
+        keep = kept
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    let cache = match rt.load_global("cache") {
+        Some(Value::Table(cache)) => cache,
+        other => panic!("{:?} is not a table", other),
+    };
+    let entries = cache
+        .borrow()
+        .iter()
+        .map(|(key, value)| (key, value.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        vec![(rt.load_global("keep").cloned().unwrap(), "kept".into())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn concat_metamethod() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local mt = {}
+        mt.__concat = function(lhs, rhs)
+            local l = getmetatable(lhs) and lhs.name or lhs
+            local r = getmetatable(rhs) and rhs.name or rhs
+            return "(" .. l .. "+" .. r .. ")"
+        end
+        local t = setmetatable({ name = "t" }, mt)
+        local u = setmetatable({ name = "u" }, mt)
+        return t .. "x", "x" .. t, t .. u, "a" .. 1 .. t .. "b" .. "c"
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            "(t+x)".into(),
+            "(x+t)".into(),
+            "(t+u)".into(),
+            "a1(t+bc)".into()
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn concat_without_metamethod() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = setmetatable({}, {})
+        return 'x' .. t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "concat" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bnot_metamethod() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local mt = { __bnot = function(a, b) return rawequal(a, b) and a.value end }
+        local t = setmetatable({ value = 5 }, mt)
+        return ~t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![5.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let chunk = compile("return ~{}")?;
+    let result = rt.execute(&chunk);
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::BitwiseOnNonNumber { ty: "table" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -2,7 +2,10 @@ use indoc::indoc;
 use pretty_assertions::assert_eq;
 use tlua::{
     compile,
-    vm::runtime::Runtime,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
 };
 
 #[test]
@@ -31,3 +34,115 @@ fn method_call() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn method_receiver_evaluated_once() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local calls = 0
+        local obj = { value = 10 }
+
+        function obj:get(offset)
+            return self.value + offset
+        end
+
+        function obj:name()
+            return "obj"
+        end
+
+        local function receiver()
+            calls = calls + 1
+            return obj
+        end
+
+        local a = receiver():get(1)
+        local b = receiver():name()
+        return a, b, calls
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![11.into(), "obj".into(), 2.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn method_chain_truncates_intermediate_results() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local obj = { value = 3 }
+
+        function obj:pair()
+            return self, "extra"
+        end
+
+        function obj:count(...)
+            return select('#', ...), self.value
+        end
+
+        return obj:pair():pair():count()
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![0.into(), 3.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn call_results_truncate_or_spread() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local function f()
+            return 1, 2, 3
+        end
+
+        local function g(...)
+            return select('#', ...)
+        end
+
+        local x, y = (f())
+        local spread = g(f())
+        local parenthesized = g((f()))
+        local leading = g(f(), 10)
+        return x, y, spread, parenthesized, leading, f()
+    "#};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            1.into(),
+            Value::Nil,
+            3.into(),
+            1.into(),
+            2.into(),
+            1.into(),
+            2.into(),
+            3.into()
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
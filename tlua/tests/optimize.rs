@@ -0,0 +1,159 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+    Chunk,
+};
+use tlua_bytecode::{
+    opcodes::{
+        Jump,
+        LoadConstant,
+        Op,
+        SetRet,
+    },
+    Constant,
+    ImmediateRegister,
+};
+use tlua_compiler::Function;
+
+fn instruction_count(chunk: &Chunk) -> usize {
+    chunk.main.instructions.len()
+        + chunk
+            .functions
+            .iter()
+            .map(|function| function.instructions.len())
+            .sum::<usize>()
+}
+
+#[test]
+fn removes_redundant_instructions() -> anyhow::Result<()> {
+    for (src, expected_before, expected_after) in [
+        ("local a = 1 local b = a return b", 7, 5),
+        ("local a = 1 local b = 2 local c = a + b return c", 11, 10),
+        ("x = 1 y = x return y", 7, 5),
+        ("repeat local x = 1 until true", 5, 4),
+        (
+            indoc! {"
+                local function f(x)
+                    local y = x * 2
+                    return y
+                end
+                return f(3)
+            "},
+            14,
+            12,
+        ),
+    ] {
+        let chunk = compile(src)?;
+        let optimized = chunk.clone().optimize();
+
+        assert_eq!(
+            (instruction_count(&chunk), instruction_count(&optimized)),
+            (expected_before, expected_after),
+            "{:#?} was optimized to {:#?}",
+            chunk,
+            optimized
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn optimized_chunks_behave_the_same() -> anyhow::Result<()> {
+    for src in [
+        "local a = 1 local b = a return b",
+        "x = 1 y = x return x + y",
+        indoc! {"
+            local function f(x)
+                local y = x * 2
+                return y
+            end
+            return f(3)
+        "},
+        indoc! {"
+            local t = {}
+            for i = 1, 3 do
+                t[i] = i
+            end
+            local sum = 0
+            for _, v in ipairs(t) do
+                sum = sum + v
+            end
+            return sum, #t
+        "},
+        indoc! {"
+            local a
+            if a then
+                a = 1
+            else
+                a = 2
+            end
+            repeat
+                local x = a
+            until true
+            return a
+        "},
+    ] {
+        let chunk = compile(src)?;
+        let optimized = chunk.clone().optimize();
+
+        let mut rt = Runtime::default();
+        let expected = rt.execute(&chunk);
+        let mut rt = Runtime::default();
+        let result = rt.execute(&optimized);
+
+        assert_eq!(
+            result, expected,
+            "{:#?} produced an incorrect result",
+            optimized
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn removes_dead_writes_and_jumps() -> anyhow::Result<()> {
+    let imm0 = ImmediateRegister::from(0);
+    let chunk = Chunk {
+        main: Function {
+            immediates: 1,
+            instructions: vec![
+                LoadConstant {
+                    dst: imm0,
+                    src: Constant::Integer(1),
+                }
+                .into(),
+                Jump { target: 2 }.into(),
+                LoadConstant {
+                    dst: imm0,
+                    src: Constant::Integer(2),
+                }
+                .into(),
+                Op::Nop,
+                SetRet { src: imm0 }.into(),
+                Op::Ret,
+            ]
+            .into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let optimized = chunk.clone().optimize();
+    assert_eq!(instruction_count(&optimized), 3, "{:#?}", optimized);
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&optimized);
+
+    assert_eq!(
+        result,
+        Ok(vec![2.into()]),
+        "{:#?} produced an incorrect result",
+        optimized
+    );
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "os")]
+
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+#[test]
+fn difftime() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return os.difftime(100, 40), os.difftime(7)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![60.0.into(), 7.0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn date_table_epoch() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local d = os.date('!*t', 0)
+        return d.year, d.month, d.day, d.hour, d.min, d.sec, d.wday, d.yday, d.isdst
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1970.into(),
+            1.into(),
+            1.into(),
+            0.into(),
+            0.into(),
+            0.into(),
+            5.into(),
+            1.into(),
+            false.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn date_utc_format() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local d = os.date('!*t', 86400 * 31)
+        return os.date('!%Y-%m-%d %H:%M:%S', 86400 + 3661), d.month, d.day, d.yday
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec!["1970-01-02 01:01:01".into(), 2.into(), 1.into(), 32.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
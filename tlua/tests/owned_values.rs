@@ -0,0 +1,137 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        value::Number,
+        OwnedValue,
+        OwnedValueError,
+        Runtime,
+        Value,
+    },
+};
+
+#[test]
+fn copies_values_out() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local shared = { 'x' }
+        return nil, true, 1, 2.5, 'str', { 10, 20, 30 }, { a = shared, b = shared, [1] = 0 }, {}
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt
+        .execute(&chunk)?
+        .iter()
+        .map(Value::to_owned_value)
+        .collect::<Result<Vec<_>, _>>();
+
+    let shared = OwnedValue::Array(vec![OwnedValue::String(b"x".to_vec())]);
+    assert_eq!(
+        result,
+        Ok(vec![
+            OwnedValue::Nil,
+            OwnedValue::Bool(true),
+            OwnedValue::Number(Number::Integer(1)),
+            OwnedValue::Number(Number::Float(2.5)),
+            OwnedValue::String(b"str".to_vec()),
+            OwnedValue::Array(vec![
+                OwnedValue::Number(Number::Integer(10)),
+                OwnedValue::Number(Number::Integer(20)),
+                OwnedValue::Number(Number::Integer(30)),
+            ]),
+            OwnedValue::Map(vec![
+                (
+                    OwnedValue::Number(Number::Integer(1)),
+                    OwnedValue::Number(Number::Integer(0))
+                ),
+                (OwnedValue::String(b"a".to_vec()), shared.clone()),
+                (OwnedValue::String(b"b".to_vec()), shared),
+            ]),
+            OwnedValue::Map(vec![]),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_uncopyable_values() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = { 1 }
+        t.self = { t }
+        return t, { f = function() end }
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(result[0].to_owned_value(), Err(OwnedValueError::Cycle));
+    assert_eq!(
+        result[1].to_owned_value(),
+        Err(OwnedValueError::Unsupported {
+            type_name: "function"
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn copies_values_in() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return #data.list, data.list[2], data.map.name, data.map[true]
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let data = rt.from_owned_value(OwnedValue::Map(vec![
+        (
+            OwnedValue::String(b"list".to_vec()),
+            OwnedValue::Array(vec![
+                OwnedValue::Number(Number::Integer(1)),
+                OwnedValue::Number(Number::Float(2.5)),
+            ]),
+        ),
+        (
+            OwnedValue::String(b"map".to_vec()),
+            OwnedValue::Map(vec![
+                (
+                    OwnedValue::String(b"name".to_vec()),
+                    OwnedValue::String(b"tlua".to_vec()),
+                ),
+                (OwnedValue::Bool(true), OwnedValue::Bool(false)),
+            ]),
+        ),
+    ]))?;
+    rt.register_global("data", data.clone());
+
+    let result = rt.execute(&chunk);
+    assert_eq!(
+        result,
+        Ok(vec![
+            Value::Number(Number::Integer(2)),
+            Value::Number(Number::Float(2.5)),
+            "tlua".into(),
+            Value::Bool(false),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    // The copied table can itself be copied back out.
+    assert!(matches!(data.to_owned_value(), Ok(OwnedValue::Map(entries)) if entries.len() == 2));
+
+    assert!(matches!(
+        rt.from_owned_value(OwnedValue::Map(vec![(OwnedValue::Nil, OwnedValue::Nil)])),
+        Err(OwnedValueError::InvalidKey(_))
+    ));
+
+    Ok(())
+}
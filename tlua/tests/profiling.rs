@@ -0,0 +1,94 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+    FuncId,
+};
+
+#[test]
+fn profile_counts_loop_body() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local count = 0
+        for i = 1, 100 do
+            count = count + 1
+        end
+        return count
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.set_profiling(true);
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![100.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let profile = rt.take_profile();
+    assert!(profile.keys().all(|(func, _)| *func == FuncId::MAIN));
+
+    // Only the loop body and condition run once per iteration.
+    let hot = profile.values().filter(|&&count| count >= 100).count();
+    let cold = profile.values().filter(|&&count| count == 1).count();
+    assert!(hot > 0, "{:#?}", profile);
+    assert!(cold > 0, "{:#?}", profile);
+
+    assert_eq!(rt.take_profile(), Default::default());
+
+    Ok(())
+}
+
+#[test]
+fn profile_attributes_functions() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(x)
+            return x + 1
+        end
+
+        local total = 0
+        for i = 1, 10 do
+            total = f(total)
+        end
+        return total
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.set_profiling(true);
+    rt.execute(&chunk)?;
+
+    let profile = rt.take_profile();
+    let func_counts = profile
+        .iter()
+        .filter(|((func, _), _)| *func == FuncId::from(0))
+        .map(|((_, offset), &count)| (*offset, count))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        func_counts.len(),
+        chunk.functions[0].instructions.len(),
+        "{:#?}",
+        profile
+    );
+    assert!(func_counts.iter().all(|&(_, count)| count == 10));
+
+    Ok(())
+}
+
+#[test]
+fn profile_disabled() -> anyhow::Result<()> {
+    let chunk = compile("return 1 + 2")?;
+
+    let mut rt = Runtime::default();
+    rt.execute(&chunk)?;
+
+    assert_eq!(rt.take_profile(), Default::default());
+
+    Ok(())
+}
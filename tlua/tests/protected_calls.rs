@@ -0,0 +1,162 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    compile_named,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+};
+
+#[test]
+fn pcall_returns_results() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function add(a, b)
+            return a + b, a - b
+        end
+
+        return pcall(add, 3, 2)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![Value::Bool(true), 5.into(), 1.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pcall_catches_errors() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fail(t)
+            return t.x.y
+        end
+
+        local ok, err = pcall(fail, {})
+        local call_ok = pcall(nil)
+        return ok, err, call_ok
+    "};
+
+    let chunk = compile_named(src, "fail.lua")?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            Value::Bool(false),
+            "fail.lua:2: Invalid types for operator \"index\"".into(),
+            Value::Bool(false),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn caught_errors_dont_hide_later_locations() -> anyhow::Result<()> {
+    let src = indoc! {"
+        pcall(function() return 1 + {} end)
+        local x = nil
+        return x.y
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(&result, Err(LuaError::ExecutionError { location: Some(location), .. }) if location.line == 3),
+        "{:#?} produced an incorrect result {:?}",
+        chunk,
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn xpcall_handles_errors() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fail()
+            return 1 + {}
+        end
+
+        local seen
+        local function handler(err)
+            seen = err
+            return 'handled', 'ignored'
+        end
+
+        local _, expected = pcall(fail)
+        local ok, handled = xpcall(fail, handler)
+        return ok, handled, seen == expected, xpcall(math.type, handler, 1)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            Value::Bool(false),
+            "handled".into(),
+            Value::Bool(true),
+            Value::Bool(true),
+            "integer".into(),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn xpcall_handler_errors() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function fail()
+            return 1 + {}
+        end
+
+        local function handler(err)
+            return err.message
+        end
+
+        local ok, err = xpcall(fail, handler)
+        return ok, err
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            Value::Bool(false),
+            "line 6: Invalid types for operator \"index\"".into(),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
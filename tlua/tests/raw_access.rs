@@ -0,0 +1,128 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn rawget_rawset() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        local same = rawequal(rawset(t, 'a', 10), t)
+        rawset(rawset(t, 1, 'x'), 2, 'y')
+        return same, rawget(t, 'a'), rawget(t, 1), rawget(t, 2), rawget(t, 'missing')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), 10.into(), "x".into(), "y".into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rawset_nil_removes() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = { 1, 2, 3 }
+        rawset(t, 3, nil)
+        return rawget(t, 3), rawlen(t)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![Value::Nil, 2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rawequal_identity() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = {}
+        local b = {}
+        return rawequal(a, a), rawequal(a, b), rawequal('x', 'x'), rawequal(1, 2)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), false.into(), true.into(), false.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rawlen_string_and_table() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return rawlen('abcd'), rawlen({ 'a', 'b', 'c' }), rawlen({})
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![4.into(), 3.into(), 0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rawlen_rejects_other_types() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return rawlen(10)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "rawlen" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+};
+
+const FUNCTIONS: &str = indoc! {"
+    local function f() return 1, 2, 3 end
+    local function g() return 4, 5 end
+    local function none() end
+    local function va(...) return ... end
+"};
+
+/// Runs `ret` after the definitions in `FUNCTIONS` and returns its results.
+fn returned(ret: &str) -> anyhow::Result<Vec<Value>> {
+    let src = format!("{FUNCTIONS}\n{ret}");
+    let chunk = compile(&src)?;
+
+    let mut rt = Runtime::default();
+    Ok(rt.execute(&chunk)?)
+}
+
+#[test]
+fn only_last_return_value_spreads() -> anyhow::Result<()> {
+    for (ret, expected) in [
+        ("return f()", vec![1, 2, 3]),
+        ("return f(), 1", vec![1, 1]),
+        ("return 1, f()", vec![1, 1, 2, 3]),
+        ("return f(), g()", vec![1, 4, 5]),
+        ("return g(), f(), g()", vec![4, 1, 4, 5]),
+        ("return (f())", vec![1]),
+        ("return 0, (f())", vec![0, 1]),
+        ("return va(f())", vec![1, 2, 3]),
+        ("return va(f(), g())", vec![1, 4, 5]),
+        ("return va(g(), 0)", vec![4, 0]),
+        ("local t = { m = f } return t:m(), t:m()", vec![1, 1, 2, 3]),
+        (
+            "local function h(...) return ..., 0 end return h(1, 2)",
+            vec![1, 0],
+        ),
+        (
+            "local function h(...) return 0, ... end return h(1, 2)",
+            vec![0, 1, 2],
+        ),
+    ] {
+        let expected = expected.into_iter().map(Value::from).collect::<Vec<_>>();
+        assert_eq!(returned(ret)?, expected, "{ret}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn empty_results_in_return_lists() -> anyhow::Result<()> {
+    for (ret, expected) in [
+        ("return none()", vec![]),
+        ("return none(), 1", vec![Value::Nil, 1.into()]),
+        ("return 1, none()", vec![1.into()]),
+        ("return (none())", vec![Value::Nil]),
+    ] {
+        assert_eq!(returned(ret)?, expected, "{ret}");
+    }
+
+    Ok(())
+}
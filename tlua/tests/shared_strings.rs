@@ -0,0 +1,83 @@
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile_with,
+    vm::runtime::Runtime,
+    StringTable,
+};
+
+#[test]
+fn chunks_share_string_table() -> anyhow::Result<()> {
+    let mut strings = StringTable::default();
+
+    let first = compile_with("shared = 'hello'", &mut strings)?;
+    let second = compile_with("local extra = 1 return shared, extra", &mut strings)?;
+
+    let first_ident = first.strings.lookup_ident("shared");
+    assert!(first_ident.is_some());
+    assert_eq!(second.strings.lookup_ident("shared"), first_ident);
+    assert_eq!(strings.lookup_ident("shared"), first_ident);
+
+    assert!(first.strings.lookup_ident("extra").is_none());
+    assert_eq!(
+        strings.lookup_ident("extra"),
+        second.strings.lookup_ident("extra")
+    );
+
+    let mut rt = Runtime::default();
+    rt.execute(&first)?;
+    let result = rt.execute(&second)?;
+
+    assert_eq!(
+        result,
+        vec!["hello".into(), 1.into()],
+        "{:#?} produced an incorrect result",
+        second
+    );
+
+    Ok(())
+}
+
+#[test]
+fn compiler_strings_are_shared() -> anyhow::Result<()> {
+    let mut strings = StringTable::default();
+
+    let first = compile_with("t = {} function t:m() return 1 end", &mut strings)?;
+    let second = compile_with("local unrelated = 2 return t:m(), unrelated", &mut strings)?;
+
+    let self_ident = first.strings.lookup_ident("self");
+    assert!(self_ident.is_some());
+    assert_eq!(strings.lookup_ident("self"), self_ident);
+    assert_ne!(second.strings.lookup_ident("unrelated"), self_ident);
+
+    let mut rt = Runtime::default();
+    rt.execute(&first)?;
+    let result = rt.execute(&second)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into()],
+        "{:#?} produced an incorrect result",
+        second
+    );
+
+    Ok(())
+}
+
+#[test]
+fn interning_deduplicates() -> anyhow::Result<()> {
+    let mut strings = StringTable::default();
+    let mut chunk = compile_with("return 'hello'", &mut strings)?;
+
+    let hello = strings.intern(b"hello");
+    assert_eq!(strings.intern(b"hello"), hello);
+    assert_eq!(chunk.strings.intern(b"hello"), hello);
+
+    let world = strings.intern(b"world");
+    assert_ne!(world, hello);
+    assert_eq!(
+        strings.get_string(world).map(|s| s.to_string()),
+        Some("world".to_string())
+    );
+
+    Ok(())
+}
@@ -279,6 +279,31 @@ fn local_call_call_as_arg() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn call_after_constant_operand() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function g() return 2, 3 end
+
+        local s = 'x' .. g()
+        return 1 + g(), s, 10 < g()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into(), "x2".into(), Value::Bool(false)],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
 #[test]
 fn global_call() -> anyhow::Result<()> {
     let src = indoc! {"
@@ -327,3 +352,112 @@ fn call_with_path() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn local_decl_multi_ret_truncates() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function multi() return 1, 2, 3 end
+
+        local a = multi()
+        return a
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn local_decl_multi_ret_spreads() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function multi() return 1, 2, 3 end
+
+        local a, b, c = multi()
+        local d, e, f, g = multi()
+        return a, b, c, d, e, f, g
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1.into(),
+            2.into(),
+            3.into(),
+            1.into(),
+            2.into(),
+            3.into(),
+            Value::Nil
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn local_decl_single_ret_pads_nil() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function single() return 1 end
+
+        local a, b = single()
+        return a, b
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn string_literal_args() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local function echo(s) return s end
+
+        return echo"a", echo'b', echo[[c]], echo[==[
+d]]]==]
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec!["a".into(), "b".into(), "c".into(), "d]]".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -2,9 +2,25 @@ use indoc::indoc;
 use pretty_assertions::assert_eq;
 use tlua::{
     compile,
-    vm::runtime::Runtime,
+    vm::runtime::{
+        value::Number,
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
 };
 
+/// Describes a number the same way `math.type` would, since integers and
+/// floats with the same value compare equal.
+fn number_type(value: &Value) -> &'static str {
+    match value {
+        Value::Number(Number::Integer(_)) => "integer",
+        Value::Number(Number::Float(_)) => "float",
+        _ => "not a number",
+    }
+}
+
 #[test]
 fn simple_addition() -> anyhow::Result<()> {
     let src = "return a + b";
@@ -53,6 +69,57 @@ fn simple_sequence() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn update_registered_global() -> anyhow::Result<()> {
+    let src = "x = x + 5";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.register_global("x", 10);
+
+    rt.execute(&chunk)?;
+
+    assert_eq!(rt.load_global("x"), Some(&15.into()));
+
+    // The updated value is what later executions see.
+    rt.execute(&chunk)?;
+
+    assert_eq!(rt.load_global("x"), Some(&20.into()));
+
+    Ok(())
+}
+
+#[test]
+fn globals_round_trip() -> anyhow::Result<()> {
+    let src = indoc! {"
+        c = a .. b
+        b, a = a, b
+        d = nil
+    "};
+
+    let chunk = compile(src)?;
+    assert!(chunk
+        .globals_map
+        .values()
+        .copied()
+        .eq(0..chunk.globals_map.len()));
+
+    let mut rt = Runtime::default();
+    rt.register_global("a", "x");
+    rt.register_global("b", "y");
+    rt.register_global("d", 1);
+
+    rt.execute(&chunk)?;
+
+    assert_eq!(rt.load_global("a"), Some(&"y".into()));
+    assert_eq!(rt.load_global("b"), Some(&"x".into()));
+    assert_eq!(rt.load_global("c"), Some(&"xy".into()));
+    assert_eq!(rt.load_global("d"), Some(&Value::Nil));
+
+    Ok(())
+}
+
 #[test]
 fn simple_reuse() -> anyhow::Result<()> {
     let src = "
@@ -182,3 +249,356 @@ fn simple_local_assign() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn mixed_arithmetic_promotion() -> anyhow::Result<()> {
+    // Locals keep the operations from being folded, so this checks the runtime.
+    let src = indoc! {"
+        local i, j, f, g = 1, 2, 2.0, 0.5
+        return
+            i + j, i + f, f + i, f + g,
+            i - j, i - f, f - i, f - g,
+            i * j, i * f, f * i, f * g
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let expected: Vec<Value> = vec![
+        3.into(),
+        3.0.into(),
+        3.0.into(),
+        2.5.into(),
+        (-1).into(),
+        (-1.0).into(),
+        1.0.into(),
+        1.5.into(),
+        2.into(),
+        2.0.into(),
+        2.0.into(),
+        1.0.into(),
+    ];
+
+    assert_eq!(
+        result, expected,
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        expected.iter().map(number_type).collect::<Vec<_>>(),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn mixed_arithmetic_promotion_folded() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return
+            1 + 2, 1 + 2.0, 2.0 + 1, 2.0 + 0.5,
+            1 - 2, 1 - 2.0, 2.0 - 1, 2.0 - 0.5,
+            1 * 2, 1 * 2.0, 2.0 * 1, 2.0 * 0.5
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let expected: Vec<Value> = vec![
+        3.into(),
+        3.0.into(),
+        3.0.into(),
+        2.5.into(),
+        (-1).into(),
+        (-1.0).into(),
+        1.0.into(),
+        1.5.into(),
+        2.into(),
+        2.0.into(),
+        2.0.into(),
+        1.0.into(),
+    ];
+
+    assert_eq!(
+        result, expected,
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        expected.iter().map(number_type).collect::<Vec<_>>(),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn integer_arithmetic_wraps() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local max, min = 9223372036854775807, -9223372036854775807 - 1
+        return max + 1, min - 1, max * 2
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        vec!["integer"; 3],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result,
+        vec![i64::MIN.into(), i64::MAX.into(), (-2).into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn integer_division_by_zero() -> anyhow::Result<()> {
+    let run = |src: &str| -> anyhow::Result<Option<OpError>> {
+        let chunk = compile(src)?;
+        let mut rt = Runtime::default();
+
+        Ok(match rt.execute(&chunk) {
+            Err(LuaError::ExecutionError { error, .. }) => Some(error),
+            _ => None,
+        })
+    };
+
+    // Both the folded constants and the runtime values produce the same errors.
+    for (src, op) in [
+        ("return 1 // 0", "//"),
+        ("local x = 0 return 5 // x", "//"),
+        ("return 1 % 0", "%%"),
+        ("local x = 0 return 5 % x", "%%"),
+    ] {
+        assert_eq!(run(src)?, Some(OpError::DivideByZero { op }), "{src}");
+    }
+
+    // Float operands follow IEEE 754 instead.
+    let src = "local x = 0.0 return 5 // x, -5 // 0.0";
+    let chunk = compile(src)?;
+    let mut rt = Runtime::default();
+    assert_eq!(
+        rt.execute(&chunk)?,
+        vec![f64::INFINITY.into(), f64::NEG_INFINITY.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    assert_eq!(
+        OpError::DivideByZero { op: "//" }.to_string(),
+        "attempt to perform 'n//0'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hex_literals_wrap_to_integers() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return 0xFFFFFFFFFFFFFFFF, 0x7FFFFFFFFFFFFFFF + 1, 0x10000000000000001
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![(-1).into(), i64::MIN.into(), 1.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        vec!["integer"; 3]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn decimal_literals_overflow_to_floats() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return 9223372036854775807, 9223372036854775808
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![i64::MAX.into(), 9223372036854775808f64.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        vec!["integer", "float"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn negated_literals_reach_min_integer() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local min = 0x8000000000000000
+        return -9223372036854775808, -0x8000000000000000, -min, -9223372036854775808.0, -9223372036854775808 ^ 1
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            i64::MIN.into(),
+            i64::MIN.into(),
+            i64::MIN.into(),
+            (-9223372036854775808f64).into(),
+            (-9223372036854775808f64).into(),
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert_eq!(
+        result.iter().map(number_type).collect::<Vec<_>>(),
+        vec!["integer", "integer", "integer", "float", "float"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn nan_and_signed_zero_equality() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local nan = 0/0
+        return nan == nan, nan ~= nan, 0.0 == -0.0, 0 == -0.0,
+            9007199254740993 == 2^53, 9007199254740992 == 2^53
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            false.into(),
+            true.into(),
+            true.into(),
+            true.into(),
+            false.into(),
+            true.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bitwise_operand_errors() -> anyhow::Result<()> {
+    let run = |src: &str| -> anyhow::Result<Option<OpError>> {
+        let chunk = compile(src)?;
+        let mut rt = Runtime::default();
+
+        Ok(match rt.execute(&chunk) {
+            Err(LuaError::ExecutionError { error, .. }) => Some(error),
+            _ => None,
+        })
+    };
+
+    // Both the folded constants and the runtime values produce the same errors.
+    for src in [
+        "return 3.5 & 1",
+        "local function f(a, b) return a & b end return f(3.5, 1)",
+    ] {
+        assert_eq!(
+            run(src)?,
+            Some(OpError::NoIntegerRepresentation { f: 3.5 }),
+            "{src}"
+        );
+    }
+
+    for src in [
+        r#"return "x" | 1"#,
+        r#"local function f(a, b) return a | b end return f("x", 1)"#,
+        r#"local function f(a, b) return a | b end return f(1, "x")"#,
+    ] {
+        assert_eq!(
+            run(src)?,
+            Some(OpError::BitwiseOnNonNumber { ty: "string" }),
+            "{src}"
+        );
+    }
+
+    assert_eq!(
+        run("local function f(a) return ~a end return f(0.5)")?,
+        Some(OpError::NoIntegerRepresentation { f: 0.5 })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unary_operators() -> anyhow::Result<()> {
+    // Both the folded constants and the runtime values produce the same results.
+    for src in [
+        r#"return not nil == true, #"abc" == 3, ~0 == -1, ~2.0, not 0, #"""#,
+        indoc! {r#"
+            local function f(n, s, z, x, e) return not n == true, #s == 3, ~z == -1, ~x, not z, #e end
+            return f(nil, "abc", 0, 2.0, "")
+        "#},
+    ] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        let result = rt.execute(&chunk)?;
+
+        assert_eq!(
+            result,
+            vec![
+                true.into(),
+                true.into(),
+                true.into(),
+                (-3).into(),
+                false.into(),
+                0.into()
+            ],
+            "{:#?} produced an incorrect result",
+            chunk
+        );
+        assert_eq!(number_type(&result[3]), "integer");
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
 use indoc::indoc;
 use tlua::{
     compile,
-    vm::runtime::Runtime,
+    vm::runtime::{
+        value::Number,
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
 };
 
 #[test]
@@ -84,6 +90,39 @@ fn simple_repeat() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn repeat_until_sees_body_locals() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local calls = 0
+        local function f()
+            calls = calls + 1
+            return calls * 3
+        end
+
+        repeat
+            local x = f()
+        until x > 10
+
+        return calls, x
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    // `x` is only in scope until the end of the condition, so the `x` returned is
+    // the (unset) global.
+    assert_eq!(
+        result,
+        vec![4.into(), Value::Nil],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
 #[test]
 fn simple_repeat_break() -> anyhow::Result<()> {
     let src = indoc! {"
@@ -139,6 +178,147 @@ fn simple_for() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn for_loop_variable() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local up = 0
+        for i = 1, 3 do
+            up = up * 10 + i
+        end
+
+        local down = 0
+        for i = 3, 1, -1 do
+            down = down * 10 + i
+        end
+
+        return up, down
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![123.into(), 321.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn for_loop_steps() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local down, count = 0, 0
+        for i = 10, 1, -1 do
+            down = down * 10 + i
+            count = count + 1
+        end
+
+        local sum, last = 0, nil
+        for i = 1, 2, 0.5 do
+            sum = sum + i
+            last = i
+        end
+
+        local empty = true
+        for i = 1, 2, -0.5 do
+            empty = false
+        end
+
+        return count, down, sum, last, empty
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            10.into(),
+            (10987654321i64).into(),
+            4.5.into(),
+            2.0.into(),
+            true.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+    assert!(matches!(result[3], Value::Number(Number::Float(_))));
+
+    Ok(())
+}
+
+#[test]
+fn for_loop_runtime_step() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function digits(from, to, step)
+            local result = 0
+            for i = from, to, step do
+                result = result * 10 + i
+            end
+            return result
+        end
+
+        local min = -9223372036854775807 - 1
+        local count = 0
+        for i = min, min + 2 do
+            count = count + 1
+        end
+
+        return digits(1, 3, 1), digits(3, 1, -1), digits(1, 3, -1), count
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![123.into(), 321.into(), 0.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn for_loop_zero_step() -> anyhow::Result<()> {
+    for src in [
+        "for i = 1, 10, 0 do end",
+        "for i = 1, 10, 0.0 do end",
+        "local step = 0 for i = 10, 1, step do end",
+    ] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::ZeroForStep,
+                    ..
+                })
+            ),
+            "{:#?} produced an incorrect result: {:?}",
+            chunk,
+            result
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn simple_for_break() -> anyhow::Result<()> {
     let src = indoc! {"
@@ -166,3 +346,73 @@ fn simple_for_break() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn sibling_loops_break() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 0
+        while true do
+            a = a + 1
+            break
+        end
+
+        local b = 0
+        repeat
+            b = b + 2
+            break
+        until false
+
+        return a, b
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sibling_for_loops_break() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = 0
+        for i = 1, 10 do
+            if i > 3 then
+                break
+            end
+            a = a + i
+        end
+
+        local b = 0
+        for i = 10, 1, -1 do
+            if i < 8 then
+                break
+            end
+            b = b + i
+        end
+
+        return a, b
+    "};
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![6.into(), 27.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
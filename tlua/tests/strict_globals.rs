@@ -0,0 +1,136 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn globals_are_lenient_by_default() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f()
+            created = 10
+        end
+        f()
+        return missing, created
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![Value::Nil, 10.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn strict_reads_of_undeclared_globals() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return registred
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.set_strict_globals(true);
+    rt.register_global("registered", 1);
+
+    let result = rt.execute(&chunk);
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::UndefinedGlobal { ref name },
+                ..
+            }) if &**name == "registred"
+        ),
+        "{:#?} produced an incorrect result {:?}",
+        chunk,
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn strict_declarations() -> anyhow::Result<()> {
+    let src = indoc! {"
+        -- Assigning from the top level declares a global, even as nil.
+        declared = nil
+        counter = 0
+
+        function bump()
+            counter = counter + 1
+            declared = counter
+        end
+
+        bump()
+        bump()
+        return counter, declared, registered, math.type(1)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.set_strict_globals(true);
+    rt.register_global("registered", false);
+
+    let result = rt.execute(&chunk);
+    assert_eq!(
+        result,
+        Ok(vec![2.into(), 2.into(), false.into(), "integer".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn strict_assignments_in_functions() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f()
+            typo = 1
+        end
+        f()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.set_strict_globals(true);
+
+    let result = rt.execute(&chunk);
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::UndefinedGlobal { ref name },
+                ..
+            }) if &**name == "typo"
+        ),
+        "{:#?} produced an incorrect result {:?}",
+        chunk,
+        result
+    );
+
+    // The failed assignment didn't give the global a value.
+    assert_eq!(
+        rt.load_global("typo").cloned().unwrap_or_default(),
+        Value::Nil
+    );
+
+    Ok(())
+}
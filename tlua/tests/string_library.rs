@@ -0,0 +1,159 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn byte_ranges() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local s = "ABC"
+        return string.byte(s, 1, 3), string.byte(s), string.byte(s, -1), string.byte(s, -10, 2)
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    // Only the last call in the list is expanded to all of its results.
+    assert_eq!(
+        result,
+        vec![65.into(), 65.into(), 67.into(), 65.into(), 66.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn byte_empty_ranges() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return select('#', string.byte("ABC", 3, 2)), select('#', string.byte("ABC", 4)),
+            select('#', string.byte(""))
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![0.into(), 0.into(), 0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn char_round_trips() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local s = string.char(72, 105, 0, 255)
+        return s == "Hi\0\255", #s, string.char()
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![true.into(), 4.into(), "".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn char_out_of_range() -> anyhow::Result<()> {
+    for src in ["return string.char(256)", "return string.char(65, -1)"] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::InvalidType { op: "char" },
+                    ..
+                })
+            ),
+            "{:#?} produced an incorrect result {:#?}",
+            chunk,
+            result
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sub_ranges() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local s = "Hello"
+        return string.sub(s, 2), string.sub(s, 2, 3), string.sub(s, -3), string.sub(s, -3, -2),
+            string.sub(s, 0, 100), string.sub(s, 4, 2), string.sub(s, -100, 1)
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            "ello".into(),
+            "el".into(),
+            "llo".into(),
+            "ll".into(),
+            "Hello".into(),
+            "".into(),
+            "H".into(),
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn runtime_strings_match_constants() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { key = 1 }
+        t["other"] = 2
+
+        local key = string.sub("a key", 3)
+        local other = string.sub("others", 1, -2)
+        t[string.sub("new!", 1, 3)] = 3
+
+        return t[key], t[other], t.new, key == "key", rawequal(other, "other")
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into(), 3.into(), true.into(), true.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -1,7 +1,15 @@
+use indoc::indoc;
 use pretty_assertions::assert_eq;
 use tlua::{
     compile,
+    compile_with_options,
     vm::runtime::Runtime,
+    CompilerOptions,
+    LuaError,
+    OpError,
+    ParseOptions,
+    UnicodeEscapes,
+    UnknownEscapes,
 };
 
 #[test]
@@ -131,3 +139,202 @@ fn constant_concat_string_number() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn concat_evaluation_order() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local order = ''
+        local function a() order = order .. 'a' return 'x' end
+        local function b() order = order .. 'b' return 'y' end
+        local function c() order = order .. 'c' return 'z' end
+
+        local s = a() .. b() .. c()
+        return s, order
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec!["xyz".into(), "abc".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn concat_chain() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f() return 'e', 'unused' end
+        local c = 'c'
+        return 'a' .. b .. c .. 1 .. 2.5 .. f() .. 'f' .. (g .. 'h') .. 'i' .. 'j'
+    "};
+
+    let chunk = compile(src)?;
+
+    // The whole chain, other than the parenthesized part, is joined by one
+    // instruction.
+    let concats = chunk
+        .main
+        .instructions
+        .iter()
+        .filter(|instr| format!("{instr:?}").contains("concat"))
+        .count();
+    assert_eq!(concats, 2);
+
+    let mut rt = Runtime::default();
+    rt.register_global("b", "b");
+    rt.register_global("g", "g");
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["abc12.5efghij".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn concat_chain_invalid_operand() -> anyhow::Result<()> {
+    let src = "return 'a' .. 'b' .. {} .. 'c'";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidType { op: "concat" },
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result {:#?}",
+        chunk,
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn arithmetic_coercion() -> anyhow::Result<()> {
+    let src = "return a + b, c * 2, d * 1, a & 7";
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    rt.register_global("a", "3");
+    rt.register_global("b", "4");
+    rt.register_global("c", "3.5");
+    rt.register_global("d", " 0x10 ");
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![7.into(), 7.0.into(), 16.into(), 3.into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constant_arithmetic_coercion() -> anyhow::Result<()> {
+    let src = r#"return "3" + "4", "3.5" * 2, "10" + 5, "-2" // 1"#;
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![7.into(), 7.0.into(), 15.into(), (-2).into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn arithmetic_non_numeric_string() -> anyhow::Result<()> {
+    for src in [r#"return "abc" + 1"#, "return a + 1"] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        rt.register_global("a", "1 2");
+
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::InvalidType { op: "add" },
+                    ..
+                })
+            ),
+            "{:#?} produced an incorrect result: {:?}",
+            chunk,
+            result
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn configured_escapes() -> anyhow::Result<()> {
+    let src = r#"return "a\qb""#;
+    assert!(compile(src).is_err());
+
+    let options = CompilerOptions {
+        parse_options: ParseOptions {
+            unknown_escapes: UnknownEscapes::PassThrough,
+            ..ParseOptions::default()
+        },
+        ..CompilerOptions::default()
+    };
+    let chunk = compile_with_options(src, options)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![r"a\qb".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    let src = r#"return "\u{D800}""#;
+    assert!(compile(src).is_ok());
+
+    let options = CompilerOptions {
+        parse_options: ParseOptions {
+            unicode_escapes: UnicodeEscapes::Strict,
+            ..ParseOptions::default()
+        },
+        ..CompilerOptions::default()
+    };
+    assert!(compile_with_options(src, options).is_err());
+
+    Ok(())
+}
@@ -3,12 +3,17 @@ use pretty_assertions::assert_eq;
 use tlua::{
     compile,
     vm::runtime::{
-        value::table::TableKey,
+        value::{
+            table::TableKey,
+            Number,
+        },
         Gc,
         Runtime,
         Table,
         Value,
     },
+    LuaError,
+    OpError,
 };
 
 #[test]
@@ -46,9 +51,7 @@ fn named_field_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected
-        .entries
-        .insert(TableKey::try_from(Value::from("a")).unwrap(), 11.into());
+    expected.set(Value::from("a"), 11.into())?;
 
     assert_eq!(
         result,
@@ -73,9 +76,7 @@ fn arraylike_field_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected
-        .entries
-        .insert(TableKey::try_from(Value::from(1)).unwrap(), 11.into());
+    expected.set(Value::from(1), 11.into())?;
 
     assert_eq!(
         result,
@@ -100,9 +101,7 @@ fn indexed_field_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected
-        .entries
-        .insert(TableKey::try_from(Value::from("a")).unwrap(), 10.into());
+    expected.set(Value::from("a"), 10.into())?;
 
     assert_eq!(
         result,
@@ -130,12 +129,10 @@ fn va_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected.entries.extend([
-        (TableKey::try_from(Value::from(1)).unwrap(), 1.into()),
-        (TableKey::try_from(Value::from(2)).unwrap(), 1.into()),
-        (TableKey::try_from(Value::from(3)).unwrap(), 2.into()),
-        (TableKey::try_from(Value::from(4)).unwrap(), 3.into()),
-    ]);
+    expected.set(Value::from(1), 1.into())?;
+    expected.set(Value::from(2), 1.into())?;
+    expected.set(Value::from(3), 2.into())?;
+    expected.set(Value::from(4), 3.into())?;
 
     assert_eq!(result.len(), 1, "{:#?} produced an incorrect result", chunk);
 
@@ -143,8 +140,8 @@ fn va_table_init() -> anyhow::Result<()> {
 
     if let Some(Value::Table(t)) = result.first() {
         assert_eq!(
-            t.borrow().entries,
-            expected.entries,
+            *t.borrow(),
+            expected,
             "{:#?} produced an incorrect result",
             chunk
         );
@@ -169,9 +166,7 @@ fn fn_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected
-        .entries
-        .insert(TableKey::try_from(Value::from(1)).unwrap(), 1.into());
+    expected.set(Value::from(1), 1.into())?;
 
     assert_eq!(result.len(), 1, "{:#?} produced an incorrect result", chunk);
 
@@ -179,8 +174,8 @@ fn fn_table_init() -> anyhow::Result<()> {
 
     if let Some(Value::Table(t)) = result.first() {
         assert_eq!(
-            t.borrow().entries,
-            expected.entries,
+            *t.borrow(),
+            expected,
             "{:#?} produced an incorrect result",
             chunk
         );
@@ -205,10 +200,8 @@ fn fn_table_init_multi() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected.entries.extend([
-        (TableKey::try_from(Value::from(1)).unwrap(), 1.into()),
-        (TableKey::try_from(Value::from(2)).unwrap(), 2.into()),
-    ]);
+    expected.set(Value::from(1), 1.into())?;
+    expected.set(Value::from(2), 2.into())?;
 
     assert_eq!(result.len(), 1, "{:#?} produced an incorrect result", chunk);
 
@@ -216,8 +209,8 @@ fn fn_table_init_multi() -> anyhow::Result<()> {
 
     if let Some(Value::Table(t)) = result.first() {
         assert_eq!(
-            t.borrow().entries,
-            expected.entries,
+            *t.borrow(),
+            expected,
             "{:#?} produced an incorrect result",
             chunk
         );
@@ -240,10 +233,8 @@ fn arraylike_takes_precedence_table_init() -> anyhow::Result<()> {
     let result = rt.execute(&chunk)?;
 
     let mut expected = Table::default();
-    expected.entries.extend([
-        (TableKey::try_from(Value::from(1)).unwrap(), 10.into()),
-        (TableKey::try_from(Value::from(2)).unwrap(), 11.into()),
-    ]);
+    expected.set(Value::from(1), 10.into())?;
+    expected.set(Value::from(2), 11.into())?;
 
     assert_eq!(result.len(), 2, "{:#?} produced an incorrect result", chunk);
 
@@ -254,15 +245,15 @@ fn arraylike_takes_precedence_table_init() -> anyhow::Result<()> {
 
     if let [Value::Table(x), Value::Table(y)] = result.as_slice() {
         assert_eq!(
-            x.borrow().entries,
-            expected.entries,
+            *x.borrow(),
+            expected,
             "{:#?} produced an incorrect result",
             chunk
         );
 
         assert_eq!(
-            y.borrow().entries,
-            expected.entries,
+            *y.borrow(),
+            expected,
             "{:#?} produced an incorrect result",
             chunk
         );
@@ -270,3 +261,446 @@ fn arraylike_takes_precedence_table_init() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn sequence_filled_out_of_order() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local x = {}
+        x[3] = "c"
+        x[2] = "b"
+        local before = #x
+        x[1] = "a"
+        return before, #x, x[1], x[2], x[3]
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![0.into(), 3.into(), "a".into(), "b".into(), "c".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sequence_hole_keeps_entries() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local x = { 1, 2, 3, 4 }
+        x[2] = nil
+        local len = #x
+        x[2] = 20
+        return len, #x, x[1], x[2], x[3], x[4]
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 4.into(), 1.into(), 20.into(), 3.into(), 4.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sequence_holes_refill_in_any_order() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = { 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 }
+        t[3] = nil
+        t[7] = nil
+        t[3] = 3
+        local a = #t
+        t[7] = 7
+        local b = #t
+        t[2] = nil
+        t[9] = nil
+        t[2] = 2
+        local c = #t
+        t[10] = nil
+        t[9] = 9
+        return a, b, c, #t
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![6.into(), 10.into(), 8.into(), 9.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn large_sequence_hole_refills() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local t = {}
+        for i = 1, 20000 do
+            t[i] = i
+        end
+
+        t[2] = nil
+        local len = #t
+        t[2] = 2
+        local refilled = #t
+
+        local count, sum = 0, 0
+        for k, v in pairs(t) do
+            count = count + 1
+            sum = sum + v
+            -- Clearing entries behind the traversal doesn't disturb it.
+            if k == 10000 then
+                t[5] = nil
+            end
+        end
+
+        return len, refilled, #t, count, sum
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1.into(),
+            20000.into(),
+            4.into(),
+            20000.into(),
+            (20000 * 20001 / 2).into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn integral_float_keys() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        t[1] = 10
+        t[2.0] = 20
+        t[2.5] = 25
+        return t[1.0], t[2], #t, t[2.5]
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![10.into(), 20.into(), 2.into(), 25.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn integral_float_keys_stored_as_integers() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        t[-0.0] = 'zero'
+        t[3.0] = 'three'
+        return t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let table = match result.as_slice() {
+        [Value::Table(t)] => t.clone(),
+        _ => panic!("{:#?} produced an incorrect result", chunk),
+    };
+    let mut keys = table
+        .borrow()
+        .iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    keys.sort_by(Value::total_cmp);
+
+    assert!(
+        matches!(
+            keys.as_slice(),
+            [
+                Value::Number(Number::Integer(0)),
+                Value::Number(Number::Integer(3))
+            ]
+        ),
+        "{:#?} has non-integer keys",
+        keys
+    );
+
+    Ok(())
+}
+
+#[test]
+#[allow(deprecated, clippy::mutable_key_type)]
+fn entries_include_array_and_hash() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return { 'a', 'b', x = 'c' }
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let table = match result.as_slice() {
+        [Value::Table(t)] => t.clone(),
+        _ => panic!("{:#?} produced an incorrect result", chunk),
+    };
+    let entries = table.borrow().entries();
+
+    assert_eq!(entries.len(), 3);
+    for (key, value) in [(Value::from(1), "a"), (2.into(), "b"), ("x".into(), "c")] {
+        assert_eq!(entries.get(&TableKey::try_from(key)?), Some(&value.into()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_keys_fail_on_write() -> anyhow::Result<()> {
+    for (src, expected) in [
+        ("local t = {} t[0/0] = 1", OpError::TableIndexNaN),
+        ("local t = {} t[nil] = 1", OpError::TableIndexNil),
+    ] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(&result, Err(LuaError::ExecutionError { error, .. }) if *error == expected),
+            "{:#?} produced an incorrect result",
+            chunk
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deeply_nested_table_eq() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function nest(depth)
+            local t = {}
+            local i = 0
+            while i < depth do
+                t = { t }
+                i = i + 1
+            end
+            return t
+        end
+        return nest(10), nest(10), nest(10000), nest(10000)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert!(matches!(
+        result.as_slice(),
+        [
+            Value::Table(_),
+            Value::Table(_),
+            Value::Table(_),
+            Value::Table(_)
+        ]
+    ));
+
+    assert_eq!(result[0], result[1]);
+    // The innermost table is nested 10 levels below the outermost.
+    assert!(result[0].deep_eq(&result[1], 11));
+    assert!(!result[0].deep_eq(&result[1], 10));
+
+    // Past the depth limit, distinct tables are treated as unequal.
+    assert_ne!(result[2], result[3]);
+    assert_eq!(result[2], result[2]);
+
+    Ok(())
+}
+
+#[test]
+fn zero_and_negative_zero_keys_match() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = {}
+        t[0.0] = 'zero'
+        t[-2^63] = 'min'
+        return t[-0.0], t[0], t[-9223372036854775807 - 1]
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec!["zero".into(), "zero".into(), "min".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constructor_expands_trailing_call() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f() return 2, 3 end
+        local t = {1, f()}
+        return t[1], t[2], t[3], #t
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into(), 3.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constructor_truncates_inner_calls() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f() return 1, 2 end
+        local function g() return 3, 4 end
+        local t = {f(), g()}
+        local u = {f(), x = g()}
+        return t[1], t[2], t[3], #t, u[1], u.x, #u
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1.into(),
+            3.into(),
+            4.into(),
+            3.into(),
+            1.into(),
+            3.into(),
+            1.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constructor_expands_varargs() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(...)
+            local t = {0, ...}
+            return #t, t[1], t[2], t[4]
+        end
+        return f(1, 2, 3)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![4.into(), 0.into(), 1.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn table_pack() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local t = table.pack(1, nil, 3)
+        local empty = table.pack()
+        return t.n, t[1], t[2], t[3], empty.n
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into(), 1.into(), Value::Nil, 3.into(), 0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn structural_eq_cyclic_tables() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local a = { 1, x = 'a' }
+        a.self = a
+        local b = { 1, x = 'a' }
+        b.self = b
+        local c = { 1, x = 'c' }
+        c.self = c
+        return a, b, c
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    let [a, b, c] = result.as_slice() else {
+        panic!("{:#?} produced an incorrect result", chunk);
+    };
+
+    assert!(a.structural_eq(b));
+    assert!(b.structural_eq(a));
+    assert!(!a.structural_eq(c));
+    assert!(a.structural_eq(a));
+
+    Ok(())
+}
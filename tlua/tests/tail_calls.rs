@@ -0,0 +1,133 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::Runtime,
+};
+
+#[test]
+fn deep_tail_recursion() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function count(n, acc)
+            if n == 0 then
+                return acc
+            end
+            return count(n - 1, acc + 1)
+        end
+
+        return count(200000, 0)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![200000.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn mutual_tail_recursion() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local is_odd
+
+        local function is_even(n)
+            if n == 0 then
+                return true
+            end
+            return is_odd(n - 1)
+        end
+
+        is_odd = function(n)
+            if n == 0 then
+                return false
+            end
+            return is_even(n - 1)
+        end
+
+        return is_even(100001)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![false.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tail_call_forwards_all_results() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function inner(...)
+            return ...
+        end
+
+        local function outer(a, b)
+            return inner(a, b, 3)
+        end
+
+        return outer(1, 2)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![1.into(), 2.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tail_call_with_call_args() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function pair()
+            return 1, 2
+        end
+
+        local function add(a, b)
+            return a + b
+        end
+
+        local function outer()
+            return add(pair())
+        end
+
+        return outer()
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
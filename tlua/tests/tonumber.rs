@@ -0,0 +1,114 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn converts_numerals() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return tonumber(" 10 "), tonumber("-0x10"), tonumber("+1.5"), tonumber("0x1.8p1"),
+            tonumber(7), tonumber("1e2")
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            10.into(),
+            (-16).into(),
+            1.5.into(),
+            3.0.into(),
+            7.into(),
+            100.0.into()
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rejects_non_numerals() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return tonumber("12abc"), tonumber(""), tonumber("1 2"), tonumber({}), tonumber(nil)
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![Value::Nil; 5]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn converts_with_base() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return tonumber("ff", 16), tonumber(" -101 ", 2), tonumber("zZ", 36),
+            tonumber("8", 8), tonumber("1.5", 10)
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            255.into(),
+            (-5).into(),
+            1295.into(),
+            Value::Nil,
+            Value::Nil
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_base() -> anyhow::Result<()> {
+    for src in [r#"return tonumber("10", 37)"#, "return tonumber(10, 16)"] {
+        let chunk = compile(src)?;
+
+        let mut rt = Runtime::default();
+        let result = rt.execute(&chunk);
+
+        assert!(
+            matches!(
+                result,
+                Err(LuaError::ExecutionError {
+                    error: OpError::InvalidType { op: "tonumber" },
+                    ..
+                })
+            ),
+            "{:#?} produced an incorrect result: {:?}",
+            chunk,
+            result
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        value::Number,
+        Runtime,
+    },
+};
+use tlua_strings::LuaString;
+
+#[test]
+fn formats_numbers() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return tostring(1.0), tostring(0.1), tostring(1e20), tostring(-0.0), tostring(1 / 0),
+            tostring(-1 / 0), tostring(10), tostring(-3), tostring(2^53), tostring(1 / 3),
+            tostring(1e-5), tostring(123456789012345.0)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec![
+            "1.0".into(),
+            "0.1".into(),
+            "1e+20".into(),
+            "-0.0".into(),
+            "inf".into(),
+            "-inf".into(),
+            "10".into(),
+            "-3".into(),
+            "9.007199254741e+15".into(),
+            "0.33333333333333".into(),
+            "1e-05".into(),
+            "1.2345678901234e+14".into(),
+        ]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn concatenation_formats_numbers() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local half = 0.5
+        return 'x' .. 2.0, 1.5 .. '', half * 4 .. '|' .. 7
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["x2.0".into(), "1.5".into(), "2.0|7".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn formats_special_floats() {
+    let format = |f: f64| Number::Float(f).to_lua_string();
+
+    assert_eq!(format(f64::NAN), LuaString::from("nan"));
+    assert_eq!(format(-f64::NAN), LuaString::from("-nan"));
+    assert_eq!(format(f64::INFINITY), LuaString::from("inf"));
+    assert_eq!(format(-0.0), LuaString::from("-0.0"));
+    assert_eq!(format(99999999999999.99), LuaString::from("1e+14"));
+    assert_eq!(
+        Number::Integer(i64::MIN).to_lua_string(),
+        LuaString::from("-9223372036854775808")
+    );
+}
+
+#[test]
+fn formats_other_values() -> anyhow::Result<()> {
+    let src = indoc! {"
+        return tostring(nil), tostring(true), tostring('abc')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert_eq!(
+        result,
+        Ok(vec!["nil".into(), "true".into(), "abc".into()]),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
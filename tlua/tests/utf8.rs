@@ -0,0 +1,152 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    vm::runtime::{
+        Runtime,
+        Value,
+    },
+    LuaError,
+    OpError,
+};
+
+#[test]
+fn char_and_codepoint() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local s = utf8.char(72, 228, 0x2764, 0x1F600)
+        return s, #s, utf8.codepoint(s, 1, -1)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            "Hä❤😀".into(),
+            10.into(),
+            72.into(),
+            228.into(),
+            0x2764.into(),
+            0x1F600.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn len_counts_characters() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local s = 'añ❤'
+        return utf8.len(s), utf8.len(s, 2), utf8.len(s, 4), utf8.len(s, -3), utf8.len('')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into(), 2.into(), 1.into(), 1.into(), 0.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn len_reports_invalid_position() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        local a, b = utf8.len("ab\xFFc")
+        local c, d = utf8.len("a\xE2\x9D")
+        local e, f = utf8.len("\u{D800}")
+        return a, b, c, d, e, f, utf8.len("\u{D800}", 1, -1, true)
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            Value::Nil,
+            3.into(),
+            Value::Nil,
+            2.into(),
+            Value::Nil,
+            1.into(),
+            1.into()
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn codepoint_rejects_invalid() -> anyhow::Result<()> {
+    let src = indoc! {r#"
+        return utf8.codepoint("\xC0\x80")
+    "#};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk);
+
+    assert!(
+        matches!(
+            result,
+            Err(LuaError::ExecutionError {
+                error: OpError::InvalidUtf8Code,
+                ..
+            })
+        ),
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn offset() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local s = 'añ❤b'
+        return utf8.offset(s, 1), utf8.offset(s, 3), utf8.offset(s, 5), utf8.offset(s, 6),
+            utf8.offset(s, -1), utf8.offset(s, -2), utf8.offset(s, 0, 5), utf8.offset(s, -5)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![
+            1.into(),
+            4.into(),
+            8.into(),
+            Value::Nil,
+            7.into(),
+            4.into(),
+            4.into(),
+            Value::Nil
+        ],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -116,3 +116,55 @@ fn va_args_middle_single_pop() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn return_va_args_directly() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(...)
+            return ...
+        end
+
+        return select('#', f(1, 2, 3)), f(1, 2, 3)
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![3.into(), 1.into(), 2.into(), 3.into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
+
+#[test]
+fn return_no_va_args_directly() -> anyhow::Result<()> {
+    let src = indoc! {"
+        local function f(...)
+            return ...
+        end
+
+        return select('#', f()), select(2, 'a', 'b', 'c')
+    "};
+
+    let chunk = compile(src)?;
+
+    let mut rt = Runtime::default();
+
+    let result = rt.execute(&chunk)?;
+
+    assert_eq!(
+        result,
+        vec![0.into(), "b".into(), "c".into()],
+        "{:#?} produced an incorrect result",
+        chunk
+    );
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use pretty_assertions::assert_eq;
+use tlua::vm::runtime::{
+    value::Number,
+    Value,
+};
+
+#[test]
+fn sort_mixed_values() {
+    let mut values = vec![
+        Value::from("b"),
+        Value::from(f64::NAN),
+        Value::from(2),
+        Value::Bool(true),
+        Value::from(1.5),
+        Value::Nil,
+        Value::from("a"),
+        Value::from(2.0),
+        Value::Bool(false),
+        Value::from(f64::NEG_INFINITY),
+    ];
+
+    values.sort_by(Value::total_cmp);
+
+    let described = values
+        .iter()
+        .map(|value| match value {
+            Value::Number(Number::Integer(i)) => format!("{i}i"),
+            Value::Number(Number::Float(f)) => format!("{f}f"),
+            value => value.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        described,
+        vec!["nil", "false", "true", "-inff", "1.5f", "2i", "2f", "NaNf", "a", "b"]
+    );
+}
+
+#[test]
+fn total_cmp_is_reflexive_for_nan() {
+    let nan = Value::from(f64::NAN);
+
+    assert_eq!(nan.total_cmp(&nan), std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn equal_numbers_hash_equally() {
+    let zeros = [Value::from(0), Value::from(0.0), Value::from(-0.0)];
+    for zero in &zeros {
+        assert_eq!(zero, &zeros[0]);
+        assert_eq!(zero.hash(), zeros[0].hash());
+    }
+
+    assert_eq!(Value::from(i64::MIN), Value::from(-(2f64.powi(63))));
+    assert_eq!(
+        Value::from(i64::MIN).hash(),
+        Value::from(-(2f64.powi(63))).hash()
+    );
+
+    // Distinct integers can round to the same float, but only one is equal to it.
+    let float = Value::from(2f64.powi(53));
+    assert_eq!(Value::from(1i64 << 53), float);
+    assert_ne!(Value::from((1i64 << 53) + 1), float);
+
+    let nan = Value::from(f64::NAN);
+    assert_ne!(nan, nan.clone());
+}
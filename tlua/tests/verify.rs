@@ -0,0 +1,167 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use tlua::{
+    compile,
+    Chunk,
+};
+use tlua_bytecode::{
+    opcodes::{
+        Alloc,
+        Call,
+        ConsumeRetRange,
+        Instruction,
+        Jump,
+        LoadConstant,
+        Op,
+        SetRet,
+    },
+    ByteCodeError,
+    Constant,
+    ImmediateRegister,
+};
+use tlua_compiler::{
+    BuiltinType,
+    FuncId,
+    Function,
+};
+
+fn main_only(immediates: usize, instructions: Vec<Instruction>) -> Chunk {
+    Chunk {
+        main: Function {
+            immediates,
+            instructions: instructions.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn compiled_chunks_verify() -> anyhow::Result<()> {
+    for src in [
+        "return 1 + 2",
+        indoc! {"
+            local function f(x, ...)
+                local t = { ... }
+                return x, #t
+            end
+            local a, b = f(1, 2, 3)
+            return f(a + b)
+        "},
+        indoc! {"
+            local count = 0
+            for i = 1, 10 do
+                if i % 2 == 0 then
+                    goto continue
+                end
+                count = count + (function() return i end)()
+                ::continue::
+            end
+            return count
+        "},
+    ] {
+        let chunk = compile(src)?;
+
+        assert_eq!(chunk.verify(), Ok(()), "{:#?} failed to verify", chunk);
+        assert_eq!(chunk.clone().optimize().verify(), Ok(()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn malformed_chunks_fail_verification() {
+    let imm0 = ImmediateRegister::from(0);
+
+    for (chunk, expected) in [
+        (
+            main_only(0, vec![Jump { target: 3 }.into(), Op::Ret]),
+            ByteCodeError::InvalidJumpTarget,
+        ),
+        (
+            main_only(
+                1,
+                vec![
+                    LoadConstant {
+                        dst: imm0,
+                        src: Constant::Integer(1),
+                    }
+                    .into(),
+                    SetRet {
+                        src: ImmediateRegister::from(1),
+                    }
+                    .into(),
+                    Op::Ret,
+                ],
+            ),
+            ByteCodeError::RegisterOutOfBounds,
+        ),
+        (
+            main_only(
+                2,
+                vec![
+                    ConsumeRetRange {
+                        dst_start: 0,
+                        count: 2,
+                    }
+                    .into(),
+                    Op::Ret,
+                ],
+            ),
+            ByteCodeError::UnexpectedCallInstruction,
+        ),
+        (
+            main_only(0, vec![Op::TailCall]),
+            ByteCodeError::MissingCallInvocation,
+        ),
+        (
+            main_only(
+                1,
+                vec![
+                    Alloc {
+                        dst: imm0,
+                        type_id: BuiltinType::Function(FuncId::from(0)).into(),
+                    }
+                    .into(),
+                    Op::Ret,
+                ],
+            ),
+            ByteCodeError::InvalidFunctionId,
+        ),
+    ] {
+        assert_eq!(chunk.verify(), Err(expected), "{:#?}", chunk);
+    }
+}
+
+#[test]
+fn jumps_cannot_skip_calls() {
+    let imm0 = ImmediateRegister::from(0);
+
+    // The results of a call can only be consumed directly after it, not by
+    // jumping past the call.
+    let chunk = main_only(
+        1,
+        vec![
+            Jump { target: 2 }.into(),
+            Call {
+                target: imm0,
+                mapped_args_start: 0,
+                mapped_args_count: 0,
+            }
+            .into(),
+            ConsumeRetRange {
+                dst_start: 0,
+                count: 1,
+            }
+            .into(),
+            Op::Ret,
+        ],
+    );
+
+    assert_eq!(
+        chunk.verify(),
+        Err(ByteCodeError::UnexpectedCallInstruction),
+        "{:#?}",
+        chunk
+    );
+}